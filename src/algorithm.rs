@@ -0,0 +1,158 @@
+use crate::error::{Error, Sha2Corrupted};
+
+/// Identifies one of the SHA-2 variants implemented by this crate.
+///
+/// Parses from names such as `"SHA-256"`, `"sha512"` or `"sha-512/256"` (case-insensitive), and
+/// from the dotted NIST object identifiers, e.g. `"2.16.840.1.101.3.4.2.1"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Sha2Algorithm {
+    Sha224,
+    Sha256,
+    Sha384,
+    Sha512,
+    Sha512_224,
+    Sha512_256,
+}
+
+impl Sha2Algorithm {
+    pub const ALL: [Sha2Algorithm; 6] = [
+        Sha2Algorithm::Sha224,
+        Sha2Algorithm::Sha256,
+        Sha2Algorithm::Sha384,
+        Sha2Algorithm::Sha512,
+        Sha2Algorithm::Sha512_224,
+        Sha2Algorithm::Sha512_256,
+    ];
+
+    /// Length of the digest in bytes.
+    pub fn digest_len(self) -> usize {
+        match self {
+            Sha2Algorithm::Sha224 => 28,
+            Sha2Algorithm::Sha256 => 32,
+            Sha2Algorithm::Sha384 => 48,
+            Sha2Algorithm::Sha512 => 64,
+            Sha2Algorithm::Sha512_224 => 28,
+            Sha2Algorithm::Sha512_256 => 32,
+        }
+    }
+
+    /// Size of a message block in bytes.
+    pub fn block_size(self) -> usize {
+        match self {
+            Sha2Algorithm::Sha224 | Sha2Algorithm::Sha256 => 64,
+            _ => 128,
+        }
+    }
+
+    /// The canonical name as used in FIPS 180-4.
+    pub fn name(self) -> &'static str {
+        match self {
+            Sha2Algorithm::Sha224 => "SHA-224",
+            Sha2Algorithm::Sha256 => "SHA-256",
+            Sha2Algorithm::Sha384 => "SHA-384",
+            Sha2Algorithm::Sha512 => "SHA-512",
+            Sha2Algorithm::Sha512_224 => "SHA-512/224",
+            Sha2Algorithm::Sha512_256 => "SHA-512/256",
+        }
+    }
+
+    /// Lowercase name without separators after "sha", as used in password hash identifiers
+    /// such as `pbkdf2-sha256`.
+    pub fn short_name(self) -> &'static str {
+        match self {
+            Sha2Algorithm::Sha224 => "sha224",
+            Sha2Algorithm::Sha256 => "sha256",
+            Sha2Algorithm::Sha384 => "sha384",
+            Sha2Algorithm::Sha512 => "sha512",
+            Sha2Algorithm::Sha512_224 => "sha512-224",
+            Sha2Algorithm::Sha512_256 => "sha512-256",
+        }
+    }
+
+    /// The object identifier under `2.16.840.1.101.3.4.2` (NIST hash algorithms).
+    pub fn oid(self) -> &'static str {
+        match self {
+            Sha2Algorithm::Sha224 => "2.16.840.1.101.3.4.2.4",
+            Sha2Algorithm::Sha256 => "2.16.840.1.101.3.4.2.1",
+            Sha2Algorithm::Sha384 => "2.16.840.1.101.3.4.2.2",
+            Sha2Algorithm::Sha512 => "2.16.840.1.101.3.4.2.3",
+            Sha2Algorithm::Sha512_224 => "2.16.840.1.101.3.4.2.5",
+            Sha2Algorithm::Sha512_256 => "2.16.840.1.101.3.4.2.6",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        let name = name.to_ascii_lowercase();
+        let bits = name.strip_prefix("sha")?;
+        let bits = bits.strip_prefix('-').unwrap_or(bits);
+
+        match bits {
+            "224" => Some(Sha2Algorithm::Sha224),
+            "256" => Some(Sha2Algorithm::Sha256),
+            "384" => Some(Sha2Algorithm::Sha384),
+            "512" => Some(Sha2Algorithm::Sha512),
+            "512/224" | "512_224" | "512-224" => Some(Sha2Algorithm::Sha512_224),
+            "512/256" | "512_256" | "512-256" => Some(Sha2Algorithm::Sha512_256),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for Sha2Algorithm {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        Sha2Algorithm::ALL
+            .into_iter()
+            .find(|alg| alg.oid() == s)
+            .or_else(|| Self::from_name(s))
+            .ok_or(Error(Sha2Corrupted::BadParam))
+    }
+}
+
+impl std::fmt::Display for Sha2Algorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sha2Algorithm;
+
+    #[test]
+    fn test_parse_names() {
+        assert_eq!("sha-256".parse().ok(), Some(Sha2Algorithm::Sha256));
+        assert_eq!("SHA512".parse().ok(), Some(Sha2Algorithm::Sha512));
+        assert_eq!("SHA-384".parse().ok(), Some(Sha2Algorithm::Sha384));
+        assert_eq!("sha224".parse().ok(), Some(Sha2Algorithm::Sha224));
+        assert_eq!("SHA-512/224".parse().ok(), Some(Sha2Algorithm::Sha512_224));
+        assert_eq!("sha512_256".parse().ok(), Some(Sha2Algorithm::Sha512_256));
+        assert!("sha-1".parse::<Sha2Algorithm>().is_err());
+        assert!("sha".parse::<Sha2Algorithm>().is_err());
+    }
+
+    #[test]
+    fn test_parse_oids() {
+        assert_eq!(
+            "2.16.840.1.101.3.4.2.1".parse().ok(),
+            Some(Sha2Algorithm::Sha256)
+        );
+        assert_eq!(
+            "2.16.840.1.101.3.4.2.6".parse().ok(),
+            Some(Sha2Algorithm::Sha512_256)
+        );
+        assert!("2.16.840.1.101.3.4.2.7".parse::<Sha2Algorithm>().is_err());
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        for alg in Sha2Algorithm::ALL {
+            assert_eq!(alg.name().parse().ok(), Some(alg));
+            assert_eq!(alg.oid().parse().ok(), Some(alg));
+            assert_eq!(alg.short_name().parse().ok(), Some(alg));
+        }
+    }
+}
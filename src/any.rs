@@ -0,0 +1,174 @@
+use crate::algorithm::Sha2Algorithm;
+use crate::context::Sha2Context;
+use crate::error::{Error, Result, Sha2Corrupted};
+use crate::params::{
+    Sha224Params, Sha256Params, Sha2Params, Sha384Params, Sha512Params, Sha512_224Params,
+    Sha512_256Params,
+};
+
+/// A SHA-2 context whose variant is chosen at runtime.
+///
+/// This is the counterpart of the `USHAContext` interface of RFC 6234: the methods dispatch to
+/// the [`Sha2Context`] of the selected algorithm.
+pub enum AnySha2 {
+    Sha224(Sha2Context<Sha224Params>),
+    Sha256(Sha2Context<Sha256Params>),
+    Sha384(Sha2Context<Sha384Params>),
+    Sha512(Sha2Context<Sha512Params>),
+    Sha512_224(Sha2Context<Sha512_224Params>),
+    Sha512_256(Sha2Context<Sha512_256Params>),
+}
+
+macro_rules! dispatch {
+    ($self:expr, $ctx:ident => $body:expr) => {
+        match $self {
+            AnySha2::Sha224($ctx) => $body,
+            AnySha2::Sha256($ctx) => $body,
+            AnySha2::Sha384($ctx) => $body,
+            AnySha2::Sha512($ctx) => $body,
+            AnySha2::Sha512_224($ctx) => $body,
+            AnySha2::Sha512_256($ctx) => $body,
+        }
+    };
+}
+
+impl AnySha2 {
+    pub fn new(algorithm: Sha2Algorithm) -> Self {
+        match algorithm {
+            Sha2Algorithm::Sha224 => AnySha2::Sha224(Sha2Context::new()),
+            Sha2Algorithm::Sha256 => AnySha2::Sha256(Sha2Context::new()),
+            Sha2Algorithm::Sha384 => AnySha2::Sha384(Sha2Context::new()),
+            Sha2Algorithm::Sha512 => AnySha2::Sha512(Sha2Context::new()),
+            Sha2Algorithm::Sha512_224 => AnySha2::Sha512_224(Sha2Context::new()),
+            Sha2Algorithm::Sha512_256 => AnySha2::Sha512_256(Sha2Context::new()),
+        }
+    }
+
+    pub fn algorithm(&self) -> Sha2Algorithm {
+        match self {
+            AnySha2::Sha224(_) => Sha2Algorithm::Sha224,
+            AnySha2::Sha256(_) => Sha2Algorithm::Sha256,
+            AnySha2::Sha384(_) => Sha2Algorithm::Sha384,
+            AnySha2::Sha512(_) => Sha2Algorithm::Sha512,
+            AnySha2::Sha512_224(_) => Sha2Algorithm::Sha512_224,
+            AnySha2::Sha512_256(_) => Sha2Algorithm::Sha512_256,
+        }
+    }
+
+    pub fn digest_len(&self) -> usize {
+        self.algorithm().digest_len()
+    }
+
+    pub fn block_size(&self) -> usize {
+        self.algorithm().block_size()
+    }
+
+    pub fn reset(&mut self) -> Result<()> {
+        dispatch!(self, ctx => ctx.reset())
+    }
+
+    pub fn input(&mut self, msg_chunk: &[u8]) -> Result<()> {
+        dispatch!(self, ctx => ctx.input(msg_chunk))
+    }
+
+    pub fn final_bits(&mut self, msg_bits: u8, msg_bits_count: usize) -> Result<()> {
+        dispatch!(self, ctx => ctx.final_bits(msg_bits, msg_bits_count))
+    }
+
+    /// Writes the digest to the first [`AnySha2::digest_len`] bytes of `dst`.
+    pub fn result(&mut self, dst: &mut [u8]) -> Result<()> {
+        if dst.len() < self.digest_len() {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        dispatch!(self, ctx => Self::result_into(ctx, dst))
+    }
+
+    pub fn result_vec(&mut self) -> Result<Vec<u8>> {
+        let mut digest = vec![0u8; self.digest_len()];
+        self.result(&mut digest)?;
+        Ok(digest)
+    }
+
+    fn result_into<P: Sha2Params>(ctx: &mut Sha2Context<P>, dst: &mut [u8]) -> Result<()> {
+        let mut digest = P::new_digest();
+        ctx.result(&mut digest)?;
+        dst[..P::HASH_LEN_BYTES].copy_from_slice(digest.as_ref());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AnySha2;
+    use crate::algorithm::Sha2Algorithm;
+    use crate::error::Result;
+
+    use hex_literal::hex;
+
+    fn digest(alg: Sha2Algorithm, msg: &[u8]) -> Result<Vec<u8>> {
+        let mut ctx = AnySha2::new(alg);
+        ctx.input(msg)?;
+        ctx.result_vec()
+    }
+
+    #[test]
+    fn test_any_abc() -> Result<()> {
+        assert_eq!(
+            digest(Sha2Algorithm::Sha224, b"abc")?,
+            hex!("23097d223405d8228642a477bda255b32aadbce4bda0b3f7e36c9da7")
+        );
+        assert_eq!(
+            digest(Sha2Algorithm::Sha256, b"abc")?,
+            hex!("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        assert_eq!(
+            digest(Sha2Algorithm::Sha384, b"abc")?,
+            hex!("cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed8086072ba1e7cc2358baeca134c825a7")
+        );
+        assert_eq!(
+            digest(Sha2Algorithm::Sha512, b"abc")?,
+            hex!("ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f")
+        );
+        assert_eq!(
+            digest(Sha2Algorithm::Sha512_224, b"abc")?,
+            hex!("4634270f707b6a54daae7530460842e20e37ed265ceee9a43e8924aa")
+        );
+        assert_eq!(
+            digest(Sha2Algorithm::Sha512_256, b"abc")?,
+            hex!("53048e2681941ef99b2e29b76b4c7dabe4c2d0c634fc6d46e0e2f13107e7af23")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_any_multiblock() -> Result<()> {
+        let msg = [0x61u8; 200];
+
+        assert_eq!(
+            digest(Sha2Algorithm::Sha224, &msg)?,
+            hex!("2559984fd15e055f0d84c346483508242f02653ab7956401e551511c")
+        );
+        assert_eq!(
+            digest(Sha2Algorithm::Sha384, &msg)?,
+            hex!("0691b6e978614b67d60557b2a2cddd53406508522efa21c624dbbfa8ab6e726d5c586b489c7c09f24109a64c10211d48")
+        );
+        assert_eq!(
+            digest(Sha2Algorithm::Sha512_224, &msg)?,
+            hex!("230fd37b82564b0c3b19d2cf19f91b297a4aca0124b4b691ccee1870")
+        );
+        assert_eq!(
+            digest(Sha2Algorithm::Sha512_256, &msg)?,
+            hex!("19b1e37317d7fd3d7651f397005e31f154ef4912d1345743d2d5889aaca28996")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_any_short_dst() {
+        let mut ctx = AnySha2::new(Sha2Algorithm::Sha384);
+        let mut dst = [0u8; 32];
+
+        assert!(ctx.result(&mut dst).is_err());
+    }
+}
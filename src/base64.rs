@@ -0,0 +1,160 @@
+//! Base64 variants used by the password hash string formats.
+
+use crate::error::{Error, Result, Sha2Corrupted};
+
+pub(crate) struct Base64 {
+    alphabet: &'static [u8; 64],
+    padding: bool,
+}
+
+const STANDARD_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+const ADAPTED_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789./";
+
+/// RFC 4648 base64 with `=` padding.
+pub(crate) const STANDARD: Base64 = Base64 {
+    alphabet: STANDARD_ALPHABET,
+    padding: true,
+};
+
+/// RFC 4648 base64 without padding, as used by the PHC string format.
+pub(crate) const STANDARD_NO_PAD: Base64 = Base64 {
+    alphabet: STANDARD_ALPHABET,
+    padding: false,
+};
+
+/// Passlib's "adapted base64": `.` instead of `+` and no padding.
+pub(crate) const ADAPTED: Base64 = Base64 {
+    alphabet: ADAPTED_ALPHABET,
+    padding: false,
+};
+
+impl Base64 {
+    pub(crate) fn encode(&self, data: &[u8]) -> String {
+        let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+
+        for chunk in data.chunks(3) {
+            let b = [
+                chunk[0],
+                chunk.get(1).copied().unwrap_or(0),
+                chunk.get(2).copied().unwrap_or(0),
+            ];
+            let sextets = [
+                b[0] >> 2,
+                (b[0] << 4 | b[1] >> 4) & 0x3f,
+                (b[1] << 2 | b[2] >> 6) & 0x3f,
+                b[2] & 0x3f,
+            ];
+
+            for &sextet in &sextets[..(chunk.len() + 1)] {
+                out.push(self.alphabet[sextet as usize] as char);
+            }
+
+            if self.padding {
+                for _ in chunk.len()..3 {
+                    out.push('=');
+                }
+            }
+        }
+
+        out
+    }
+
+    pub(crate) fn decode(&self, encoded: &str) -> Result<Vec<u8>> {
+        let mut encoded = encoded.as_bytes();
+        let rem = encoded.len() % 4;
+
+        if self.padding {
+            if rem != 0 {
+                return Err(Error(Sha2Corrupted::BadParam));
+            }
+
+            // at most two `=`, completing the final group; any others fail the alphabet lookup
+            let pad = encoded
+                .iter()
+                .rev()
+                .take(2)
+                .take_while(|&&c| c == b'=')
+                .count();
+            encoded = &encoded[..encoded.len() - pad];
+        } else if rem == 1 {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        let mut out = Vec::with_capacity(encoded.len() * 3 / 4);
+
+        for chunk in encoded.chunks(4) {
+            let mut acc = 0u32;
+
+            for (i, &c) in chunk.iter().enumerate() {
+                let sextet = self
+                    .alphabet
+                    .iter()
+                    .position(|&a| a == c)
+                    .ok_or(Error(Sha2Corrupted::BadParam))?;
+                acc |= (sextet as u32) << (18 - 6 * i);
+            }
+
+            let bytes = acc.to_be_bytes();
+            let n = chunk.len() - 1;
+
+            // reject non-canonical encodings with stray bits after the last full byte
+            if bytes[(n + 1)..].iter().any(|&b| b != 0) {
+                return Err(Error(Sha2Corrupted::BadParam));
+            }
+
+            out.extend_from_slice(&bytes[1..(n + 1)]);
+        }
+
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ADAPTED, STANDARD, STANDARD_NO_PAD};
+
+    #[test]
+    fn test_rfc4648_vectors() {
+        let vectors = [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ];
+
+        for (plain, encoded) in vectors {
+            assert_eq!(STANDARD.encode(plain.as_bytes()), encoded);
+            assert_eq!(STANDARD.decode(encoded).unwrap(), plain.as_bytes());
+
+            let unpadded = encoded.trim_end_matches('=');
+            assert_eq!(STANDARD_NO_PAD.encode(plain.as_bytes()), unpadded);
+            assert_eq!(STANDARD_NO_PAD.decode(unpadded).unwrap(), plain.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_adapted() {
+        assert_eq!(ADAPTED.encode(&[0xfb, 0xff]), "./8");
+        assert_eq!(ADAPTED.decode("./8").unwrap(), [0xfb, 0xff]);
+        assert!(ADAPTED.decode("+/8").is_err());
+    }
+
+    #[test]
+    fn test_reject_invalid() {
+        assert!(STANDARD.decode("Zg=").is_err());
+        assert!(STANDARD.decode("QQ======").is_err());
+        assert!(STANDARD.decode("====").is_err());
+        assert!(STANDARD.decode("Zm9v====").is_err());
+        assert!(STANDARD.decode("Zg=A").is_err());
+        assert!(STANDARD.decode("Z!==").is_err());
+        assert!(STANDARD_NO_PAD.decode("Z!").is_err());
+        assert!(STANDARD_NO_PAD.decode("Zm9vY").is_err());
+        assert!(STANDARD_NO_PAD.decode("Zh").is_err());
+    }
+}
@@ -1,5 +1,6 @@
-use super::Sha2Params;
+use super::{Sha2Params, Sha2Word};
 use crate::error::{Result, Sha2Corrupted};
+use crate::wipe::{wipe_slice, wipe_value};
 use num_traits::ops::wrapping::WrappingAdd;
 
 pub struct Sha2Context<P: Sha2Params + ?Sized> {
@@ -13,6 +14,16 @@ pub struct Sha2Context<P: Sha2Params + ?Sized> {
     corrupted: Sha2Corrupted,
 }
 
+impl<P: Sha2Params + ?Sized> Sha2Context<P> {
+    /// Overwrites the chaining value, the buffered message bytes and the length counter.
+    fn wipe(&mut self) {
+        wipe_slice(self.intermediate_hash.as_mut(), P::Word::ZERO);
+        wipe_slice(self.msg_block.as_mut(), 0);
+        wipe_value(&mut self.length, 0);
+        wipe_value(&mut self.msg_block_idx, 0);
+    }
+}
+
 impl<P: Sha2Params> Sha2Context<P> {
     pub fn new() -> Self {
         Sha2Context {
@@ -26,8 +37,8 @@ impl<P: Sha2Params> Sha2Context<P> {
     }
 
     pub fn reset(&mut self) -> Result<()> {
-        self.length = 0;
-        self.msg_block_idx = 0;
+        self.wipe();
+
         self.intermediate_hash = P::H0;
         self.computed = false;
         self.corrupted = Sha2Corrupted::Success;
@@ -47,22 +58,25 @@ impl<P: Sha2Params> Sha2Context<P> {
         self.corrupted.into_result(())?;
 
         while !msg_chunk.is_empty() {
-            let msg_block = self.msg_block.as_mut();
-            msg_block[self.msg_block_idx] = msg_chunk[0];
-            self.msg_block_idx += 1;
+            let take = usize::min(P::MSG_BLOCK_SIZE - self.msg_block_idx, msg_chunk.len());
 
-            if let Some(new_length) = self.length.checked_add(8) {
+            if let Some(new_length) = self.length.checked_add(8 * take as u128) {
                 self.length = new_length
             } else {
                 self.corrupted = Sha2Corrupted::StateError;
                 break;
             }
 
+            let msg_block = self.msg_block.as_mut();
+            msg_block[self.msg_block_idx..(self.msg_block_idx + take)]
+                .copy_from_slice(&msg_chunk[..take]);
+            self.msg_block_idx += take;
+
             if self.msg_block_idx == P::MSG_BLOCK_SIZE {
                 self.process_message_block()?;
             }
 
-            msg_chunk = &msg_chunk[1..];
+            msg_chunk = &msg_chunk[take..];
         }
 
         self.corrupted.into_result(())
@@ -72,10 +86,11 @@ impl<P: Sha2Params> Sha2Context<P> {
         let mut w = P::new_w();
 
         let msg_block_bytes: &[u8] = self.msg_block.as_ref();
+        let word_bytes = std::mem::size_of::<P::Word>();
 
         #[allow(clippy::needless_range_loop)]
         for t in 0..16 {
-            w[t] = P::parse_word(&msg_block_bytes[(t * 4)..]);
+            w[t] = P::parse_word(&msg_block_bytes[(t * word_bytes)..]);
         }
 
         #[allow(clippy::needless_range_loop)]
@@ -127,6 +142,11 @@ impl<P: Sha2Params> Sha2Context<P> {
         intermediate_hash[6] = intermediate_hash[6].wrapping_add(&g);
         intermediate_hash[7] = intermediate_hash[7].wrapping_add(&h);
 
+        #[cfg(feature = "zeroize")]
+        for t in 0..P::W_LEN {
+            wipe_value(&mut w[t], P::Word::ZERO);
+        }
+
         self.msg_block_idx = 0;
 
         self.corrupted.into_result(())
@@ -171,9 +191,7 @@ impl<P: Sha2Params> Sha2Context<P> {
     fn finalize(&mut self, pad_byte: u8) -> Result<()> {
         self.pad_message(pad_byte)?;
 
-        for i in 0..(P::MSG_BLOCK_SIZE) {
-            self.msg_block.as_mut()[i] = 0;
-        }
+        wipe_slice(self.msg_block.as_mut(), 0);
 
         self.length = 0;
         self.computed = true;
@@ -182,7 +200,10 @@ impl<P: Sha2Params> Sha2Context<P> {
     }
 
     fn pad_message(&mut self, pad_byte: u8) -> Result<()> {
-        if self.msg_block_idx >= P::MSG_BLOCK_SIZE - 8 {
+        // the length field is twice the word size: 64 bits for SHA-256, 128 bits for SHA-512
+        let length_bytes = 2 * std::mem::size_of::<P::Word>();
+
+        if self.msg_block_idx >= P::MSG_BLOCK_SIZE - length_bytes {
             self.msg_block.as_mut()[self.msg_block_idx] = pad_byte;
             self.msg_block_idx += 1;
 
@@ -197,21 +218,14 @@ impl<P: Sha2Params> Sha2Context<P> {
             self.msg_block_idx += 1;
         }
 
-        while self.msg_block_idx < P::MSG_BLOCK_SIZE - 8 {
+        while self.msg_block_idx < P::MSG_BLOCK_SIZE - length_bytes {
             self.msg_block.as_mut()[self.msg_block_idx] = 0;
             self.msg_block_idx += 1;
         }
 
-        // TODO make this generic
-
-        self.msg_block.as_mut()[56] = (self.length >> 56) as u8;
-        self.msg_block.as_mut()[57] = (self.length >> 48) as u8;
-        self.msg_block.as_mut()[58] = (self.length >> 40) as u8;
-        self.msg_block.as_mut()[59] = (self.length >> 32) as u8;
-        self.msg_block.as_mut()[60] = (self.length >> 24) as u8;
-        self.msg_block.as_mut()[61] = (self.length >> 16) as u8;
-        self.msg_block.as_mut()[62] = (self.length >> 8) as u8;
-        self.msg_block.as_mut()[63] = self.length as u8;
+        for i in 0..length_bytes {
+            self.msg_block.as_mut()[P::MSG_BLOCK_SIZE - 1 - i] = (self.length >> (8 * i)) as u8;
+        }
 
         self.process_message_block()
     }
@@ -237,8 +251,49 @@ impl<P: Sha2Params> Sha2Context<P> {
     }
 }
 
+impl<P: Sha2Params> Clone for Sha2Context<P> {
+    fn clone(&self) -> Self {
+        Sha2Context {
+            intermediate_hash: self.intermediate_hash.clone(),
+            length: self.length,
+            msg_block_idx: self.msg_block_idx,
+            msg_block: self.msg_block.clone(),
+            computed: self.computed,
+            corrupted: self.corrupted,
+        }
+    }
+}
+
 impl<P: Sha2Params> std::default::Default for Sha2Context<P> {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(feature = "zeroize")]
+impl<P: Sha2Params + ?Sized> Drop for Sha2Context<P> {
+    fn drop(&mut self) {
+        self.wipe();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sha2Context;
+    use crate::error::Result;
+    use crate::params::{Sha256Params, Sha2Params};
+
+    #[test]
+    fn test_reset_wipes_buffers() -> Result<()> {
+        let mut ctx: Sha2Context<Sha256Params> = Sha2Context::new();
+
+        ctx.input(&[0xa5; 100])?;
+        ctx.reset()?;
+
+        assert!(ctx.msg_block.iter().all(|&b| b == 0));
+        assert_eq!(ctx.intermediate_hash, Sha256Params::H0);
+        assert_eq!(ctx.length, 0);
+        assert_eq!(ctx.msg_block_idx, 0);
+        Ok(())
+    }
+}
@@ -0,0 +1,190 @@
+use crate::context::Sha2Context;
+use crate::error::{Error, Result, Sha2Corrupted};
+use crate::params::Sha2Params;
+use crate::wipe::wipe_slice;
+
+/// The hash-based one-step KDF of SP 800-56C rev 2, section 4.1 (option 1), also known as the
+/// Concat KDF: `K(i) = H(counter || Z || OtherInfo)` with a 32-bit big-endian counter starting
+/// at 1.
+pub fn one_step_kdf<P: Sha2Params>(z: &[u8], other_info: &[u8], out: &mut [u8]) -> Result<()> {
+    hash_kdf::<P>(out, |ctx, counter| {
+        ctx.input(counter)?;
+        ctx.input(z)?;
+        ctx.input(other_info)
+    })
+}
+
+/// The ANSI X9.63 KDF (also SEC 1, section 3.6.1): `K(i) = H(Z || counter || SharedInfo)` with
+/// a 32-bit big-endian counter starting at 1.
+pub fn x963_kdf<P: Sha2Params>(z: &[u8], shared_info: &[u8], out: &mut [u8]) -> Result<()> {
+    hash_kdf::<P>(out, |ctx, counter| {
+        ctx.input(z)?;
+        ctx.input(counter)?;
+        ctx.input(shared_info)
+    })
+}
+
+/// Encodes the `OtherInfo` used by JOSE ECDH-ES (RFC 7518, section 4.6.2): the AlgorithmID,
+/// PartyUInfo and PartyVInfo fields each prefixed with their 32-bit big-endian length, followed
+/// by the key data length in bits as SuppPubInfo.
+///
+/// `algorithm_id` is the `enc` value for direct key agreement and the `alg` value when the
+/// derived key wraps a content encryption key. `apu` and `apv` are the decoded `apu` and `apv`
+/// header parameters.
+pub fn jose_other_info(
+    algorithm_id: &str,
+    apu: &[u8],
+    apv: &[u8],
+    key_data_len_bits: u32,
+) -> Result<Vec<u8>> {
+    let mut other_info = Vec::with_capacity(16 + algorithm_id.len() + apu.len() + apv.len());
+
+    for field in [algorithm_id.as_bytes(), apu, apv] {
+        let len = u32::try_from(field.len()).map_err(|_| Error(Sha2Corrupted::BadParam))?;
+        other_info.extend_from_slice(&len.to_be_bytes());
+        other_info.extend_from_slice(field);
+    }
+    other_info.extend_from_slice(&key_data_len_bits.to_be_bytes());
+
+    Ok(other_info)
+}
+
+fn hash_kdf<P: Sha2Params>(
+    out: &mut [u8],
+    mut absorb: impl FnMut(&mut Sha2Context<P>, &[u8]) -> Result<()>,
+) -> Result<()> {
+    if out.is_empty() || (out.len() as u64) > (u32::MAX as u64) * (P::HASH_LEN_BYTES as u64) {
+        return Err(Error(Sha2Corrupted::BadParam));
+    }
+
+    let mut digest = P::new_digest();
+
+    let res = out
+        .chunks_mut(P::HASH_LEN_BYTES)
+        .enumerate()
+        .try_for_each(|(i, out_block)| {
+            let mut ctx: Sha2Context<P> = Sha2Context::new();
+            absorb(&mut ctx, &(i as u32 + 1).to_be_bytes())?;
+            ctx.result(&mut digest)?;
+
+            out_block.copy_from_slice(&digest.as_ref()[..out_block.len()]);
+            Ok(())
+        });
+
+    wipe_slice(digest.as_mut(), 0);
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{jose_other_info, one_step_kdf, x963_kdf};
+    use crate::error::Result;
+    use crate::params::{
+        Sha224Params, Sha256Params, Sha384Params, Sha512Params, Sha512_224Params, Sha512_256Params,
+    };
+
+    use hex_literal::hex;
+
+    const Z: [u8; 24] = hex!("96c05619d56c328ab95fe84b18264b08725b85e33fd34f08");
+
+    // RFC 7518, appendix C
+    #[test]
+    fn test_jose_ecdh_es() -> Result<()> {
+        let z = hex!("9e56d91d817135d372834283bf84269cfb316ea3da806a48f6daa7798cfe90c4");
+        let other_info = jose_other_info("A128GCM", b"Alice", b"Bob", 128)?;
+        assert_eq!(
+            other_info,
+            hex!("000000074131323847434d00000005416c69636500000003426f6200000080")
+        );
+
+        let mut key = [0u8; 16];
+        one_step_kdf::<Sha256Params>(&z, &other_info, &mut key)?;
+        assert_eq!(key, hex!("56aa8deaf8236d205c2228cd71a7101a"));
+        Ok(())
+    }
+
+    // the SHA-224 vector of OpenSSL's SSKDF tests and the SHA-256 KAT of its FIPS provider
+    // self-test, which share Z and OtherInfo
+    #[test]
+    fn test_one_step_published() -> Result<()> {
+        let z = hex!("6dbdc23f045488e4062757b06b9ebae183fc5a5946d80db93fec6f62ec07e3727f0126aed12ce4b262f47d48d54287f81d474c7c3b1850e9");
+        let other_info = hex!("a1b2c3d4e54341565369643c832e9849dcdba71e9a3139e606e095de3c264a66e98a165854cd07989b1ee0ec3f8dbe");
+
+        let mut out = [0u8; 14];
+        one_step_kdf::<Sha224Params>(&z, &other_info, &mut out)?;
+        assert_eq!(out, hex!("a462de16a89de8466ef5460b47b8"));
+
+        let mut out = [0u8; 16];
+        one_step_kdf::<Sha256Params>(&z, &other_info, &mut out)?;
+        assert_eq!(out, hex!("27ce57edb17e1ff2e4792e848b04f1ae"));
+        Ok(())
+    }
+
+    // no published vectors for these lengths and variants; cross-checked against OpenSSL's SSKDF
+    #[test]
+    fn test_one_step() -> Result<()> {
+        let mut out = [0u8; 128];
+        one_step_kdf::<Sha256Params>(&Z, &hex!("deadbeef"), &mut out)?;
+        assert_eq!(out, hex!("41469385581512444e4a0ec686be3cc15fc3a3e33206f2cddf11f856ecc199bf35df9af48fc79a45fbb0524bf0918f9a90fad21c8f1e7670c2a33346fbe82ecae8fce319e335405dd442186a58b95df54bcd587dc2205163cb5ee7ec76907cff35344cc341bd2bfd8292300a83d1f015063fac41101d94ef908a984a989dfe3d"));
+
+        let mut out = [0u8; 100];
+        one_step_kdf::<Sha512Params>(&Z, b"info", &mut out)?;
+        assert_eq!(out, hex!("d64cd37b0f04d9de01bb277e6cd181be908c0c8e91a344f981745fdc57a6e364b80a038b1a651033d9895a0d4c854938fec9446e0925e5738ba1458076428c24e690213ab8a4ebde8e4012b7975a99c0c7f4027aa8452c63fb5042844660ebbf50678657"));
+
+        let mut out = [0u8; 40];
+        one_step_kdf::<Sha224Params>(&Z, b"x", &mut out)?;
+        assert_eq!(
+            out,
+            hex!(
+                "6a0dea6538218cb47571f93de2581a3dea5a03fb14da8674e36e92e4d7cd1314834a3b9791158f06"
+            )
+        );
+
+        one_step_kdf::<Sha512_256Params>(&Z, b"abc", &mut out)?;
+        assert_eq!(
+            out,
+            hex!(
+                "41ef236587c903930282490a94ac3b08c778cb4f8deb638bcc6946c8d9d30188d8f6cd2c2f55bc6f"
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_x963() -> Result<()> {
+        // CAVP ANSI X9.63 KDF, SHA-256, no SharedInfo
+        let mut out = [0u8; 16];
+        x963_kdf::<Sha256Params>(&Z, &[], &mut out)?;
+        assert_eq!(out, hex!("443024c3dae66b95e6f5670601558f71"));
+
+        // CAVP ANSI X9.63 KDF, SHA-512, as carried in OpenSSL's X963KDF test
+        let mut out = [0u8; 128];
+        x963_kdf::<Sha512Params>(
+            &hex!("00aa5bb79b33e389fa58ceadc047197f14e73712f452caa9fc4c9adb369348b81507392f1a86ddfdb7c4ff8231c4bd0f44e44a1b55b1404747a9e2e753f55ef05a2d"),
+            &hex!("e3b5b4c1b0d5cf1d2b3a2f9937895d31"),
+            &mut out,
+        )?;
+        assert_eq!(out, hex!("4463f869f3cc18769b52264b0112b5858f7ad32a5a2d96d8cffabf7fa733633d6e4dd2a599acceb3ea54a6217ce0b50eef4f6b40a5c30250a5a8eeee208002267089dbf351f3f5022aa9638bf1ee419dea9c4ff745a25ac27bda33ca08bd56dd1a59b4106cf2dbbc0ab2aa8e2efa7b17902d34276951ceccab87f9661c3e8816"));
+
+        // the remaining variants were cross-checked against OpenSSL's X963KDF
+        let mut out = [0u8; 50];
+        x963_kdf::<Sha384Params>(&Z, &[], &mut out)?;
+        assert_eq!(out, hex!("913fb353930b0d05c1a99246c4f130ac23c2eda5d69fc21b96ebcf4bd9d7cc71ad3d491635ab2ca66a32134faf9316cbd1f1"));
+
+        let mut out = [0u8; 40];
+        x963_kdf::<Sha512_224Params>(&Z, b"abc", &mut out)?;
+        assert_eq!(
+            out,
+            hex!(
+                "d13862efa9491d0fbf6e0ccf5bba88cd4e33587038b17c02fc4ac43c5f9f706162458626b3619aee"
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_params() {
+        assert!(one_step_kdf::<Sha256Params>(&Z, &[], &mut []).is_err());
+        assert!(x963_kdf::<Sha256Params>(&Z, &[], &mut []).is_err());
+    }
+}
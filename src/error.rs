@@ -15,7 +15,7 @@ impl Sha2Corrupted {
 }
 
 #[derive(Debug)]
-pub struct Error(Sha2Corrupted);
+pub struct Error(pub(crate) Sha2Corrupted);
 
 impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
@@ -0,0 +1,315 @@
+use crate::context::Sha2Context;
+use crate::error::{Error, Result, Sha2Corrupted};
+use crate::params::Sha2Params;
+use crate::wipe::wipe_slice;
+
+/// Largest number of bytes a single generate request may return (2^19 bits).
+pub const HASH_DRBG_MAX_REQUEST_BYTES: usize = 1 << 16;
+
+/// Largest number of generate requests between reseeds allowed by SP 800-90A, and the default.
+pub const HASH_DRBG_RESEED_INTERVAL: u64 = 1 << 48;
+
+/// Hash_DRBG (SP 800-90A rev 1, section 10.1.1).
+///
+/// `V` and `C` are `seedlen` bits long: 440 bits for SHA-224, SHA-256, SHA-512/224 and
+/// SHA-512/256, 888 bits for SHA-384 and SHA-512. As with [`crate::HmacDrbg`], entropy input is
+/// supplied by the caller and generate requests fail with a `StateError` once the reseed interval
+/// is exhausted.
+pub struct HashDrbg<P: Sha2Params> {
+    v: Vec<u8>,
+    c: Vec<u8>,
+    reseed_counter: u64,
+    reseed_interval: u64,
+    _params: std::marker::PhantomData<P>,
+}
+
+impl<P: Sha2Params> HashDrbg<P> {
+    /// `seedlen` in bytes.
+    pub const SEED_LEN: usize = if P::HASH_LEN_BYTES > 32 { 111 } else { 55 };
+
+    /// Instantiates the DRBG. `entropy` must carry at least the security strength of the hash
+    /// function: 192 bits for SHA-224 and SHA-512/224, 256 bits for the others.
+    pub fn new(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> Result<Self> {
+        Self::check_entropy(entropy)?;
+
+        let mut drbg = HashDrbg {
+            v: hash_df::<P>(&[entropy, nonce, personalization], Self::SEED_LEN)?,
+            c: Vec::new(),
+            reseed_counter: 1,
+            reseed_interval: HASH_DRBG_RESEED_INTERVAL,
+            _params: std::marker::PhantomData,
+        };
+        drbg.derive_c()?;
+        Ok(drbg)
+    }
+
+    /// Lowers the number of generate requests allowed between reseeds.
+    pub fn with_reseed_interval(mut self, reseed_interval: u64) -> Result<Self> {
+        if reseed_interval == 0 || reseed_interval > HASH_DRBG_RESEED_INTERVAL {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        self.reseed_interval = reseed_interval;
+        Ok(self)
+    }
+
+    /// Number of generate requests since the last (re)seed, plus one.
+    pub fn reseed_counter(&self) -> u64 {
+        self.reseed_counter
+    }
+
+    pub fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) -> Result<()> {
+        Self::check_entropy(entropy)?;
+
+        let mut seed = hash_df::<P>(
+            &[&[0x01], &self.v, entropy, additional_input],
+            Self::SEED_LEN,
+        )?;
+        std::mem::swap(&mut self.v, &mut seed);
+        wipe_slice(&mut seed, 0);
+
+        self.derive_c()?;
+        self.reseed_counter = 1;
+        Ok(())
+    }
+
+    /// Fills `out`, which may be at most [`HASH_DRBG_MAX_REQUEST_BYTES`] long. An empty
+    /// `additional_input` is the same as none.
+    pub fn generate(&mut self, out: &mut [u8], additional_input: &[u8]) -> Result<()> {
+        if out.len() > HASH_DRBG_MAX_REQUEST_BYTES {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        if self.reseed_counter > self.reseed_interval {
+            return Err(Error(Sha2Corrupted::StateError));
+        }
+
+        let mut w = P::new_digest();
+
+        if !additional_input.is_empty() {
+            hash::<P>(&[&[0x02], &self.v, additional_input], &mut w)?;
+            add_mod(&mut self.v, w.as_ref());
+        }
+
+        // Hashgen
+        let mut data = self.v.clone();
+        for out_block in out.chunks_mut(P::HASH_LEN_BYTES) {
+            hash::<P>(&[&data], &mut w)?;
+            out_block.copy_from_slice(&w.as_ref()[..out_block.len()]);
+            add_mod(&mut data, &[0x01]);
+        }
+        wipe_slice(&mut data, 0);
+
+        hash::<P>(&[&[0x03], &self.v], &mut w)?;
+        add_mod(&mut self.v, w.as_ref());
+        add_mod(&mut self.v, &self.c);
+        add_mod(&mut self.v, &self.reseed_counter.to_be_bytes());
+        wipe_slice(w.as_mut(), 0);
+
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    /// A generate request with prediction resistance: the DRBG is reseeded with fresh `entropy`
+    /// and `additional_input` first, then generates without additional input.
+    pub fn generate_with_prediction_resistance(
+        &mut self,
+        entropy: &[u8],
+        out: &mut [u8],
+        additional_input: &[u8],
+    ) -> Result<()> {
+        self.reseed(entropy, additional_input)?;
+        self.generate(out, &[])
+    }
+
+    /// `C = Hash_df(0x00 || V, seedlen)`.
+    fn derive_c(&mut self) -> Result<()> {
+        let mut c = hash_df::<P>(&[&[0x00], &self.v], Self::SEED_LEN)?;
+        std::mem::swap(&mut self.c, &mut c);
+        wipe_slice(&mut c, 0);
+        Ok(())
+    }
+
+    fn check_entropy(entropy: &[u8]) -> Result<()> {
+        let security_strength = if P::HASH_LEN_BYTES < 32 { 24 } else { 32 };
+
+        if entropy.len() < security_strength {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<P: Sha2Params> Drop for HashDrbg<P> {
+    fn drop(&mut self) {
+        wipe_slice(&mut self.v, 0);
+        wipe_slice(&mut self.c, 0);
+    }
+}
+
+/// The derivation function `Hash_df(input, out_len * 8)` of SP 800-90A, section 10.3.1, with
+/// `input` given as the concatenation of its parts.
+fn hash_df<P: Sha2Params>(input: &[&[u8]], out_len: usize) -> Result<Vec<u8>> {
+    let mut out = vec![0u8; out_len];
+    let mut digest = P::new_digest();
+    let bits = ((out_len * 8) as u32).to_be_bytes();
+
+    for (i, out_block) in out.chunks_mut(P::HASH_LEN_BYTES).enumerate() {
+        let mut ctx: Sha2Context<P> = Sha2Context::new();
+        ctx.input(&[(i + 1) as u8])?;
+        ctx.input(&bits)?;
+        for part in input {
+            ctx.input(part)?;
+        }
+        ctx.result(&mut digest)?;
+
+        out_block.copy_from_slice(&digest.as_ref()[..out_block.len()]);
+    }
+
+    wipe_slice(digest.as_mut(), 0);
+    Ok(out)
+}
+
+fn hash<P: Sha2Params>(parts: &[&[u8]], digest: &mut P::Digest) -> Result<()> {
+    let mut ctx: Sha2Context<P> = Sha2Context::new();
+    for part in parts {
+        ctx.input(part)?;
+    }
+    ctx.result(digest)
+}
+
+/// `acc = (acc + x) mod 2^(8 * acc.len())`, both big-endian; `x` may be shorter than `acc`.
+fn add_mod(acc: &mut [u8], x: &[u8]) {
+    let mut carry = 0u16;
+    let mut x = x.iter().rev();
+
+    for a in acc.iter_mut().rev() {
+        let sum = *a as u16 + *x.next().unwrap_or(&0) as u16 + carry;
+        *a = sum as u8;
+        carry = sum >> 8;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add_mod, HashDrbg};
+    use crate::error::Result;
+    use crate::params::{Sha256Params, Sha384Params, Sha512Params};
+
+    use hex_literal::hex;
+
+    // Laid out like the CAVP Hash_DRBG tests: instantiate, optionally reseed, generate twice and
+    // compare the second output. Apart from the CAVS entry in test_cavs_prediction_resistance,
+    // the expected values were cross-checked against OpenSSL's HASH-DRBG fed through its
+    // TEST-RAND entropy source.
+    fn seq(start: u8, len: u8) -> Vec<u8> {
+        (start..start + len).collect()
+    }
+
+    #[test]
+    fn test_no_reseed() -> Result<()> {
+        let mut out = [0u8; 128];
+
+        let mut drbg: HashDrbg<Sha256Params> = HashDrbg::new(&seq(0, 32), &seq(0x20, 16), &[])?;
+        drbg.generate(&mut out, &[])?;
+        drbg.generate(&mut out, &[])?;
+        assert_eq!(out, hex!("27a3342a35d4bbb8e1dcd8ec0fc1a0d1a25cf906f0445d3b974dbddf4a3ba34e073302ab655234a703381741af7b15191a96164cc087ad1ef8360960b94dfba7451ade5f57ff6f74afeb737f8f539304c1ce58a98f3ad4b852b4cec0aceffb2bd5f153f9395b593dc8d890c6d9cc570107b36cfd4b7081c42102efd89752a1de"));
+
+        let mut drbg: HashDrbg<Sha256Params> =
+            HashDrbg::new(&seq(0, 32), &seq(0x20, 16), &seq(0x40, 32))?;
+        drbg.generate(&mut out, &[0x60; 32])?;
+        drbg.generate(&mut out, &[0x61; 32])?;
+        assert_eq!(out, hex!("fed9fc18bfb35794774d6e12725c3e9d9a8c4ac7bdac9876cc52050dd638fbfa0ae8852c92c11c134f0a95d8bffeb127866da62d9e0d11f853cc537de2f26bd3b02d20cae235eb77815508d567a8d080dd0fd8d19cf16d4ad8f4204b7ce3b55380f4d4e6512e0858a74d58b255949d3a586e294ee8df1b31031d04b2a07ec6fc"));
+
+        let mut out = [0u8; 100];
+        let mut drbg: HashDrbg<Sha384Params> = HashDrbg::new(&seq(0, 32), &seq(0x20, 16), &[])?;
+        drbg.generate(&mut out, &[])?;
+        drbg.generate(&mut out, &[])?;
+        assert_eq!(out, hex!("6d1a3982c522326f092486562179e6525c3711836f90dbfc3edb5887b7ddda08316891659c66e8e28d8db5683f23232c7aeadfbceb3ff27b22a55768839b6e0902c36bddc886a0754a3fdf40ea1a9d4ead1087e0b2c104751d3683cdeb88336813470333"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_reseed() -> Result<()> {
+        let mut out = [0u8; 128];
+
+        let mut drbg: HashDrbg<Sha256Params> = HashDrbg::new(&seq(0, 32), &seq(0x20, 16), &[])?;
+        drbg.reseed(&seq(0x80, 32), &[0x70; 32])?;
+        drbg.generate(&mut out, &[])?;
+        drbg.generate(&mut out, &[])?;
+        assert_eq!(out, hex!("cf38948413c1275fca708a81e617ba70b7e3cfd8739de87336594ef8e16665a76bf46b611a2499fdfc1322c88a258b9703f6adc62a2adc96e7bf4fe753214b7801a46ffcf0c41bbaf97742a7532037dc5f55e4362c9cb4b0c0ed5441fd8f0a9097ee82aa2527cabcfe2b1006f7f615856502e73a873cd8690c51859b2061d6f5"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_prediction_resistance() -> Result<()> {
+        let mut out = [0u8; 256];
+
+        let mut drbg: HashDrbg<Sha512Params> =
+            HashDrbg::new(&seq(0, 48), &seq(0x20, 16), &seq(0x40, 32))?;
+        drbg.generate_with_prediction_resistance(&seq(0x80, 48), &mut out, b"add1")?;
+        drbg.generate_with_prediction_resistance(&seq(0xb0, 48), &mut out, b"add2")?;
+        assert_eq!(out, hex!("0196155789a38befea417ca0aea7806610e1c0b60245efd02471dd935e6bdb44f5021a449229e50f191df25f0232b732b0334c9de0ad97cd0f83fd2f774cbdb9e3741ac95d71a6a02b5832e6ccc936acb2ddb294bf376b16c12eaab9ade25054127b7a619ecb27fc600e3e2d5de9b4da199d087ca1187682599fba9ee42e873529162f910aba4f205019c3da5b9a9e2fa7442493a26df386ee84db7981e9b6dd7b2903c1d74e3dccbb2af4f42212bb302f746d8d60a48916a177b2473310366d202a37b83976cb843496178fb9eceacc59e9a09b59cffb9d3c7ec3a67e8a98788eacc6dc232160878a123a4d6083a4f41be2b5b6551e6f6890f87201e8babfdc"));
+        Ok(())
+    }
+
+    // NIST CAVS 14.3 Hash_DRBG.rsp [SHA-256] [PredictionResistance = True] COUNT = 14, as carried
+    // in OpenSSL's FIPS provider self-test data
+    #[test]
+    fn test_cavs_prediction_resistance() -> Result<()> {
+        let mut out = [0u8; 128];
+
+        let mut drbg: HashDrbg<Sha256Params> = HashDrbg::new(
+            &hex!("066dc8ce75b28966a685163fe2a4d427fbdb616650616ba282fc332b4e6f1220"),
+            &hex!("559f7c64897083ec2d7370d9f0e5071f"),
+            &hex!("886f549aad1ac63d18cbcc6685daa2c2f79eb0894cb4aef1ac544fce57f15e11"),
+        )?;
+        drbg.generate_with_prediction_resistance(
+            &hex!("ff80b7d26a05bc8a7abe53286b0eeb733b715a205bfa4ff63703deadb6ea0ef4"),
+            &mut out,
+            &hex!("b7215f14ac7bafd0a91772ba22f719afbd20b311636c2b1e83e4a823353fc6ea"),
+        )?;
+        drbg.generate_with_prediction_resistance(
+            &hex!("c73832534681ede37e03846d3c841767297d246c689241d2e775be7ec996293d"),
+            &mut out,
+            &hex!("ced31f7e0dae5bb5c043e246b29473e2fd39512ead4569eee3e3803314aba7a3"),
+        )?;
+        assert_eq!(out, hex!("60c234cfafb468033bf195e578ce266e1465326a96a9e03f8b893670ef62754d5e80d553a1f84950208b9343079f2ef856e9c570618597b5dc82a2daeaa3fd9b2fd2a0d71bc62935ccb83da0679805a0e31efee4f0e513b08317faca935e382948d272db763e6df32510ff1b99fff8c60eb0dd292ebcbbc80a016ed3b00e4eab"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_seed_len() {
+        assert_eq!(HashDrbg::<Sha256Params>::SEED_LEN * 8, 440);
+        assert_eq!(HashDrbg::<Sha384Params>::SEED_LEN * 8, 888);
+        assert_eq!(HashDrbg::<Sha512Params>::SEED_LEN * 8, 888);
+    }
+
+    #[test]
+    fn test_add_mod() {
+        let mut acc = hex!("00ffff");
+        add_mod(&mut acc, &hex!("01"));
+        assert_eq!(acc, hex!("010000"));
+
+        let mut acc = hex!("ffffff");
+        add_mod(&mut acc, &hex!("0002"));
+        assert_eq!(acc, hex!("000001"));
+    }
+
+    #[test]
+    fn test_reseed_interval() -> Result<()> {
+        let mut out = [0u8; 16];
+        let mut drbg: HashDrbg<Sha256Params> =
+            HashDrbg::new(&seq(0, 32), &[], &[])?.with_reseed_interval(1)?;
+
+        drbg.generate(&mut out, &[])?;
+        assert!(drbg.generate(&mut out, &[]).is_err());
+
+        drbg.reseed(&seq(0x80, 32), &[])?;
+        drbg.generate(&mut out, &[])?;
+        assert!(HashDrbg::<Sha256Params>::new(&seq(0, 16), &[], &[]).is_err());
+        Ok(())
+    }
+}
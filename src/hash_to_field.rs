@@ -0,0 +1,278 @@
+use crate::context::Sha2Context;
+use crate::error::{Error, Result, Sha2Corrupted};
+use crate::params::Sha2Params;
+use crate::wipe::wipe_slice;
+
+/// `expand_message_xmd` (RFC 9380, section 5.3.1), filling `out` with up to `255 * HashLen`
+/// (and at most 65535) uniformly random bytes derived from `msg`.
+///
+/// Domain separation tags longer than 255 bytes are replaced by
+/// `H("H2C-OVERSIZE-DST-" || dst)` as required by section 5.3.3.
+pub fn expand_message_xmd<P: Sha2Params>(msg: &[u8], dst: &[u8], out: &mut [u8]) -> Result<()> {
+    let h_len = P::HASH_LEN_BYTES;
+    let ell = out.len().div_ceil(h_len);
+
+    if out.is_empty() || ell > 255 || out.len() > 0xffff || dst.is_empty() {
+        return Err(Error(Sha2Corrupted::BadParam));
+    }
+
+    let mut oversize = P::new_digest();
+    let dst = if dst.len() > 255 {
+        let mut ctx: Sha2Context<P> = Sha2Context::new();
+        ctx.input(b"H2C-OVERSIZE-DST-")?;
+        ctx.input(dst)?;
+        ctx.result(&mut oversize)?;
+        oversize.as_ref()
+    } else {
+        dst
+    };
+    let dst_len = [dst.len() as u8];
+
+    let mut b_0 = P::new_digest();
+    let mut ctx: Sha2Context<P> = Sha2Context::new();
+    ctx.input(&vec![0u8; P::MSG_BLOCK_SIZE])?;
+    ctx.input(msg)?;
+    ctx.input(&(out.len() as u16).to_be_bytes())?;
+    ctx.input(&[0])?;
+    ctx.input(dst)?;
+    ctx.input(&dst_len)?;
+    ctx.result(&mut b_0)?;
+
+    let mut b_i = P::new_digest();
+    let mut chained = P::new_digest();
+
+    let res = out
+        .chunks_mut(h_len)
+        .enumerate()
+        .try_for_each(|(i, out_block)| {
+            // b_1 = H(b_0 || 1 || DST'), b_i = H((b_0 xor b_(i-1)) || i || DST')
+            for ((c, x), y) in chained
+                .as_mut()
+                .iter_mut()
+                .zip(b_0.as_ref())
+                .zip(b_i.as_ref())
+            {
+                *c = x ^ y;
+            }
+
+            let mut ctx: Sha2Context<P> = Sha2Context::new();
+            ctx.input(chained.as_ref())?;
+            ctx.input(&[i as u8 + 1])?;
+            ctx.input(dst)?;
+            ctx.input(&dst_len)?;
+            ctx.result(&mut b_i)?;
+
+            out_block.copy_from_slice(&b_i.as_ref()[..out_block.len()]);
+            Ok(())
+        });
+
+    wipe_slice(b_0.as_mut(), 0);
+    wipe_slice(b_i.as_mut(), 0);
+    wipe_slice(chained.as_mut(), 0);
+    res
+}
+
+/// `hash_to_field` (RFC 9380, section 5.2) with `expand_message_xmd`, for the field of
+/// extension degree `degree` over the prime `modulus`, given as a big-endian byte string.
+///
+/// Returns `count * degree` elements of `GF(modulus)`, each as big-endian bytes as long as
+/// `modulus` without leading zeros: the `degree` coordinates of the first element, then those of
+/// the second, and so on. `security_bits` is the target security level `k` of the suite, which
+/// sets how many extra bytes are reduced to make the bias negligible.
+pub fn hash_to_field<P: Sha2Params>(
+    msg: &[u8],
+    dst: &[u8],
+    modulus: &[u8],
+    degree: usize,
+    count: usize,
+    security_bits: usize,
+) -> Result<Vec<Vec<u8>>> {
+    let p = strip_zeros(modulus);
+
+    if p.is_empty() || p == [1] || degree == 0 || count == 0 || security_bits == 0 {
+        return Err(Error(Sha2Corrupted::BadParam));
+    }
+
+    let p_bits = p.len() * 8 - p[0].leading_zeros() as usize;
+    let l = (p_bits + security_bits).div_ceil(8);
+    let len = count
+        .checked_mul(degree)
+        .and_then(|n| n.checked_mul(l))
+        .ok_or(Error(Sha2Corrupted::BadParam))?;
+
+    let mut uniform_bytes = vec![0u8; len];
+    expand_message_xmd::<P>(msg, dst, &mut uniform_bytes)?;
+
+    let elements = uniform_bytes.chunks(l).map(|tv| reduce(tv, p)).collect();
+
+    wipe_slice(&mut uniform_bytes, 0);
+    Ok(elements)
+}
+
+/// `x mod p` by binary long division, returned in `p.len()` bytes.
+fn reduce(x: &[u8], p: &[u8]) -> Vec<u8> {
+    // one byte of headroom, since the remainder is doubled before it is compared with p
+    let mut r = vec![0u8; p.len() + 1];
+
+    for bit in (0..x.len() * 8).map(|i| (x[i / 8] >> (7 - i % 8)) & 1) {
+        let mut carry = bit;
+        for byte in r.iter_mut().rev() {
+            let shifted = (*byte << 1) | carry;
+            carry = *byte >> 7;
+            *byte = shifted;
+        }
+
+        if r[0] != 0 || r[1..] >= *p {
+            let mut borrow = 0i16;
+            for (r_byte, p_byte) in r.iter_mut().rev().zip(p.iter().rev().chain([&0])) {
+                let diff = *r_byte as i16 - *p_byte as i16 - borrow;
+                *r_byte = diff as u8;
+                borrow = (diff < 0) as i16;
+            }
+        }
+    }
+
+    r.remove(0);
+    r
+}
+
+fn strip_zeros(mut b: &[u8]) -> &[u8] {
+    while let [0, rest @ ..] = b {
+        b = rest;
+    }
+    b
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{expand_message_xmd, hash_to_field};
+    use crate::error::Result;
+    use crate::params::{Sha256Params, Sha512Params};
+
+    use hex_literal::hex;
+
+    const DST_SHA256: &[u8] = b"QUUX-V01-CS02-with-expander-SHA256-128";
+    const DST_SHA512: &[u8] = b"QUUX-V01-CS02-with-expander-SHA512-256";
+
+    fn long_dst(prefix: &[u8]) -> Vec<u8> {
+        let mut dst = prefix.to_vec();
+        dst.extend_from_slice(b"-long-DST-");
+        dst.resize(256, b'1');
+        dst
+    }
+
+    // RFC 9380, appendix K.1
+    #[test]
+    fn test_xmd_sha256() -> Result<()> {
+        let mut out = [0u8; 0x20];
+        expand_message_xmd::<Sha256Params>(b"", DST_SHA256, &mut out)?;
+        assert_eq!(
+            out,
+            hex!("68a985b87eb6b46952128911f2a4412bbc302a9d759667f87f7a21d803f07235")
+        );
+
+        expand_message_xmd::<Sha256Params>(b"abc", DST_SHA256, &mut out)?;
+        assert_eq!(
+            out,
+            hex!("d8ccab23b5985ccea865c6c97b6e5b8350e794e603b4b97902f53a8a0d605615")
+        );
+
+        let mut out = [0u8; 0x80];
+        expand_message_xmd::<Sha256Params>(b"abcdef0123456789", DST_SHA256, &mut out)?;
+        assert_eq!(out, hex!("ef904a29bffc4cf9ee82832451c946ac3c8f8058ae97d8d629831a74c6572bd9ebd0df635cd1f208e2038e760c4994984ce73f0d55ea9f22af83ba4734569d4bc95e18350f740c07eef653cbb9f87910d833751825f0ebefa1abe5420bb52be14cf489b37fe1a72f7de2d10be453b2c9d9eb20c7e3f6edc5a60629178d9478df"));
+        Ok(())
+    }
+
+    // DSTs over 255 bytes are hashed first: RFC 9380, appendix K.2 for SHA-256; appendix K.3
+    // has no long-DST case, so the SHA-512 one is a regression value
+    #[test]
+    fn test_xmd_long_dst() -> Result<()> {
+        let mut out = [0u8; 0x20];
+        expand_message_xmd::<Sha256Params>(b"", &long_dst(DST_SHA256), &mut out)?;
+        assert_eq!(
+            out,
+            hex!("e8dc0c8b686b7ef2074086fbdd2f30e3f8bfbd3bdf177f73f04b97ce618a3ed3")
+        );
+
+        expand_message_xmd::<Sha512Params>(b"abc", &long_dst(DST_SHA512), &mut out)?;
+        assert_eq!(
+            out,
+            hex!("54b9c525c79ee116edfad67abc801f278168e0ecdb9c81e2e4f24ef6fa5b5124")
+        );
+        Ok(())
+    }
+
+    // RFC 9380, appendix K.3
+    #[test]
+    fn test_xmd_sha512() -> Result<()> {
+        let mut out = [0u8; 0x20];
+        expand_message_xmd::<Sha512Params>(b"", DST_SHA512, &mut out)?;
+        assert_eq!(
+            out,
+            hex!("6b9a7312411d92f921c6f68ca0b6380730a1a4d982c507211a90964c394179ba")
+        );
+
+        let mut out = [0u8; 0x80];
+        expand_message_xmd::<Sha512Params>(b"abc", DST_SHA512, &mut out)?;
+        assert_eq!(out, hex!("7f1dddd13c08b543f2e2037b14cefb255b44c83cc397c1786d975653e36a6b11bdd7732d8b38adb4a0edc26a0cef4bb45217135456e58fbca1703cd6032cb1347ee720b87972d63fbf232587043ed2901bce7f22610c0419751c065922b488431851041310ad659e4b23520e1772ab29dcdeb2002222a363f0c2b1c972b3efe1"));
+        Ok(())
+    }
+
+    // RFC 9380, appendix J.1.1 (P256_XMD:SHA-256_SSWU_RO_)
+    #[test]
+    fn test_hash_to_field_p256() -> Result<()> {
+        let p = hex!("ffffffff00000001000000000000000000000000ffffffffffffffffffffffff");
+        let dst = b"QUUX-V01-CS02-with-P256_XMD:SHA-256_SSWU_RO_";
+
+        let u = hash_to_field::<Sha256Params>(b"", dst, &p, 1, 2, 128)?;
+        assert_eq!(
+            u,
+            [
+                hex!("ad5342c66a6dd0ff080df1da0ea1c04b96e0330dd89406465eeba11582515009"),
+                hex!("8c0f1d43204bd6f6ea70ae8013070a1518b43873bcd850aafa0a9e220e2eea5a"),
+            ]
+        );
+
+        let u = hash_to_field::<Sha256Params>(b"abc", dst, &p, 1, 2, 128)?;
+        assert_eq!(
+            u,
+            [
+                hex!("afe47f2ea2b10465cc26ac403194dfb68b7f5ee865cda61e9f3e07a537220af1"),
+                hex!("379a27833b0bfe6f7bdca08e1e83c760bf9a338ab335542704edcd69ce9e46e0"),
+            ]
+        );
+        Ok(())
+    }
+
+    // RFC 9380, appendix J.10.1 (BLS12381G2_XMD:SHA-256_SSWU_RO_), elements of GF(p^2)
+    #[test]
+    fn test_hash_to_field_bls12_381_g2() -> Result<()> {
+        let p = hex!("1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaaab");
+        let dst = b"QUUX-V01-CS02-with-BLS12381G2_XMD:SHA-256_SSWU_RO_";
+
+        let u = hash_to_field::<Sha256Params>(b"", dst, &p, 2, 2, 128)?;
+        assert_eq!(
+            u,
+            [
+                hex!("03dbc2cce174e91ba93cbb08f26b917f98194a2ea08d1cce75b2b9cc9f21689d80bd79b594a613d0a68eb807dfdc1cf8"),
+                hex!("05a2acec64114845711a54199ea339abd125ba38253b70a92c876df10598bd1986b739cad67961eb94f7076511b3b39a"),
+                hex!("02f99798e8a5acdeed60d7e18e9120521ba1f47ec090984662846bc825de191b5b7641148c0dbc237726a334473eee94"),
+                hex!("145a81e418d4010cc027a68f14391b30074e89e60ee7a22f87217b2f6eb0c4b94c9115b436e6fa4607e95a98de30a435"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_params() {
+        let mut out = [0u8; 256 * 32];
+        assert!(expand_message_xmd::<Sha256Params>(b"", DST_SHA256, &mut out).is_err());
+        assert!(expand_message_xmd::<Sha256Params>(b"", DST_SHA256, &mut []).is_err());
+        assert!(expand_message_xmd::<Sha256Params>(b"", b"", &mut out[..32]).is_err());
+
+        assert!(hash_to_field::<Sha256Params>(b"", DST_SHA256, &[0, 1], 1, 1, 128).is_err());
+        assert!(hash_to_field::<Sha256Params>(b"", DST_SHA256, &[0xff; 32], 0, 1, 128).is_err());
+        // 300 elements of 48 bytes exceed what expand_message_xmd can produce
+        assert!(hash_to_field::<Sha256Params>(b"", DST_SHA256, &[0xff; 32], 1, 300, 128).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+use crate::error::{Error, Result, Sha2Corrupted};
+use crate::hmac::Hmac;
+use crate::params::Sha2Params;
+use crate::wipe::wipe_slice;
+
+/// HKDF extract-and-expand (RFC 5869) over any SHA-2 variant.
+///
+/// The pseudorandom key is kept as a keyed [`Hmac`], so repeated calls to [`Hkdf::expand`] with
+/// different `info` don't hash the key again.
+pub struct Hkdf<P: Sha2Params> {
+    prk: P::Digest,
+    hmac: Hmac<P>,
+}
+
+impl<P: Sha2Params> Hkdf<P> {
+    /// Computes `PRK = HMAC-Hash(salt, IKM)`. An empty `salt` is the same as the RFC's default
+    /// of `HashLen` zero bytes.
+    pub fn extract(salt: &[u8], ikm: &[u8]) -> Result<Self> {
+        let mut prk = P::new_digest();
+        let mut hmac: Hmac<P> = Hmac::new(salt)?;
+        hmac.input(ikm)?;
+        hmac.result(&mut prk)?;
+
+        let hkdf = Self::from_prk(prk.as_ref());
+        wipe_slice(prk.as_mut(), 0);
+        hkdf
+    }
+
+    /// Skips the extract step and starts from an already computed PRK, which must be exactly
+    /// `HashLen` bytes long so that [`Hkdf::prk`] returns the key in use.
+    pub fn from_prk(prk: &[u8]) -> Result<Self> {
+        if prk.len() != P::HASH_LEN_BYTES {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        let mut prk_digest = P::new_digest();
+        prk_digest.as_mut().copy_from_slice(prk);
+
+        Ok(Hkdf {
+            prk: prk_digest,
+            hmac: Hmac::new(prk)?,
+        })
+    }
+
+    /// The pseudorandom key.
+    pub fn prk(&self) -> &P::Digest {
+        &self.prk
+    }
+
+    /// Fills `okm` with output keying material. At most `255 * HashLen` bytes can be derived.
+    pub fn expand_into(&self, info: &[u8], okm: &mut [u8]) -> Result<()> {
+        if okm.len() > 255 * P::HASH_LEN_BYTES {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        let mut t = P::new_digest();
+        let mut t_len = 0;
+
+        for (i, okm_block) in okm.chunks_mut(P::HASH_LEN_BYTES).enumerate() {
+            let mut hmac = self.hmac.clone();
+            hmac.input(&t.as_ref()[..t_len])?;
+            hmac.input(info)?;
+            hmac.input(&[(i + 1) as u8])?;
+            hmac.result(&mut t)?;
+            t_len = P::HASH_LEN_BYTES;
+
+            okm_block.copy_from_slice(&t.as_ref()[..okm_block.len()]);
+        }
+
+        wipe_slice(t.as_mut(), 0);
+        Ok(())
+    }
+
+    pub fn expand(&self, info: &[u8], okm_len: usize) -> Result<Vec<u8>> {
+        let mut okm = vec![0u8; okm_len];
+        self.expand_into(info, &mut okm)?;
+        Ok(okm)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<P: Sha2Params> Drop for Hkdf<P> {
+    fn drop(&mut self) {
+        wipe_slice(self.prk.as_mut(), 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Hkdf;
+    use crate::error::Result;
+    use crate::params::{Sha256Params, Sha512Params};
+
+    use hex_literal::hex;
+
+    // RFC 5869, test case 1
+    #[test]
+    fn test_rfc5869_case1() -> Result<()> {
+        let ikm = [0x0b; 22];
+        let salt = hex!("000102030405060708090a0b0c");
+        let info = hex!("f0f1f2f3f4f5f6f7f8f9");
+
+        let hkdf: Hkdf<Sha256Params> = Hkdf::extract(&salt, &ikm)?;
+        assert_eq!(
+            *hkdf.prk(),
+            hex!("077709362c2e32df0ddc3f0dc47bba6390b6c73bb50f9c3122ec844ad7c2b3e5")
+        );
+        assert_eq!(
+            hkdf.expand(&info, 42)?,
+            hex!("3cb25f25faacd57a90434f64d0362f2a2d2d0a90cf1a5a4c5db02d56ecc4c5bf34007208d5b887185865")
+        );
+        Ok(())
+    }
+
+    // RFC 5869, test case 2
+    #[test]
+    fn test_rfc5869_case2() -> Result<()> {
+        let ikm: Vec<u8> = (0x00..=0x4f).collect();
+        let salt: Vec<u8> = (0x60..=0xaf).collect();
+        let info: Vec<u8> = (0xb0..=0xff).collect();
+
+        let hkdf: Hkdf<Sha256Params> = Hkdf::extract(&salt, &ikm)?;
+        assert_eq!(
+            *hkdf.prk(),
+            hex!("06a6b88c5853361a06104c9ceb35b45cef760014904671014a193f40c15fc244")
+        );
+        assert_eq!(
+            hkdf.expand(&info, 82)?,
+            hex!("b11e398dc80327a1c8e7f78c596a49344f012eda2d4efad8a050cc4c19afa97c59045a99cac7827271cb41c65e590e09da3275600c2f09b8367793a9aca3db71cc30c58179ec3e87c14c01d5c1f3434f1d87")
+        );
+        Ok(())
+    }
+
+    // RFC 5869, test case 3
+    #[test]
+    fn test_rfc5869_case3() -> Result<()> {
+        let ikm = [0x0b; 22];
+
+        let hkdf: Hkdf<Sha256Params> = Hkdf::extract(&[], &ikm)?;
+        assert_eq!(
+            *hkdf.prk(),
+            hex!("19ef24a32c717b167f33a91d6f648bdf96596776afdb6377ac434c1c293ccb04")
+        );
+        assert_eq!(
+            hkdf.expand(&[], 42)?,
+            hex!("8da4e775a563c18f715f802a063c5a31b8a11f5c5ee1879ec3454e5f3c738d2d9d201395faa4b61a96c8")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sha512_from_prk() -> Result<()> {
+        let prk = hex!("665799823737ded04a88e47e54a5890bb2c3d247c7a4254a8e61350723590a26c36238127d8661b88cf80ef802d57e2f7cebcf1e00e083848be19929c61b4237");
+        let info = hex!("f0f1f2f3f4f5f6f7f8f9");
+
+        let hkdf: Hkdf<Sha512Params> = Hkdf::from_prk(&prk)?;
+        assert_eq!(
+            hkdf.expand(&info, 42)?,
+            hex!("832390086cda71fb47625bb5ceb168e4c8e26a1a16ed34d9fc7fe92c1481579338da362cb8d9f925d7cb")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_lengths() -> Result<()> {
+        assert!(Hkdf::<Sha256Params>::from_prk(&[0u8; 31]).is_err());
+        assert!(Hkdf::<Sha256Params>::from_prk(&[0u8; 33]).is_err());
+
+        // a PRK taken from prk() gives back the same HKDF
+        let hkdf: Hkdf<Sha256Params> = Hkdf::extract(b"salt", b"ikm")?;
+        let copy: Hkdf<Sha256Params> = Hkdf::from_prk(hkdf.prk())?;
+        assert_eq!(copy.expand(b"info", 64)?, hkdf.expand(b"info", 64)?);
+
+        let hkdf: Hkdf<Sha256Params> = Hkdf::extract(&[], b"ikm")?;
+        assert_eq!(hkdf.expand(&[], 255 * 32)?.len(), 255 * 32);
+        assert!(hkdf.expand(&[], 255 * 32 + 1).is_err());
+        Ok(())
+    }
+}
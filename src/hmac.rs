@@ -0,0 +1,272 @@
+use crate::context::Sha2Context;
+use crate::error::{Error, Result, Sha2Corrupted};
+use crate::ops::ct_eq;
+use crate::params::Sha2Params;
+use crate::wipe::wipe_slice;
+
+const IPAD: u8 = 0x36;
+const OPAD: u8 = 0x5c;
+
+/// HMAC (RFC 2104, FIPS 198-1) over any SHA-2 variant.
+///
+/// The key is absorbed once into the inner and outer pad midstates, so [`Hmac::reset`] starts a
+/// new message under the same key without hashing the key again.
+pub struct Hmac<P: Sha2Params> {
+    inner: Sha2Context<P>,
+    inner_init: Sha2Context<P>,
+    outer_init: Sha2Context<P>,
+}
+
+impl<P: Sha2Params> Hmac<P> {
+    pub fn new(key: &[u8]) -> Result<Self> {
+        let mut key_block = P::new_msg_block();
+
+        if key.len() > P::MSG_BLOCK_SIZE {
+            let mut key_digest = P::new_digest();
+            let mut ctx: Sha2Context<P> = Sha2Context::new();
+            ctx.input(key)?;
+            ctx.result(&mut key_digest)?;
+
+            key_block.as_mut()[..P::HASH_LEN_BYTES].copy_from_slice(key_digest.as_ref());
+            wipe_slice(key_digest.as_mut(), 0);
+        } else {
+            key_block.as_mut()[..key.len()].copy_from_slice(key);
+        }
+
+        for b in key_block.as_mut() {
+            *b ^= IPAD;
+        }
+
+        let mut inner_init: Sha2Context<P> = Sha2Context::new();
+        inner_init.input(key_block.as_ref())?;
+
+        for b in key_block.as_mut() {
+            *b ^= IPAD ^ OPAD;
+        }
+
+        let mut outer_init: Sha2Context<P> = Sha2Context::new();
+        outer_init.input(key_block.as_ref())?;
+
+        wipe_slice(key_block.as_mut(), 0);
+
+        Ok(Hmac {
+            inner: inner_init.clone(),
+            inner_init,
+            outer_init,
+        })
+    }
+
+    /// Starts a new message under the same key.
+    pub fn reset(&mut self) -> Result<()> {
+        self.inner = self.inner_init.clone();
+        Ok(())
+    }
+
+    pub fn input(&mut self, msg_chunk: &[u8]) -> Result<()> {
+        self.inner.input(msg_chunk)
+    }
+
+    pub fn result(&mut self, dst: &mut P::Digest) -> Result<()> {
+        let mut inner_digest = P::new_digest();
+        self.inner.result(&mut inner_digest)?;
+
+        let mut outer = self.outer_init.clone();
+        let res = outer
+            .input(inner_digest.as_ref())
+            .and_then(|_| outer.result(dst));
+
+        wipe_slice(inner_digest.as_mut(), 0);
+        res
+    }
+
+    /// Writes the leftmost `dst.len()` bytes of the MAC, which may not exceed the digest length.
+    pub fn result_truncated(&mut self, dst: &mut [u8]) -> Result<()> {
+        if dst.is_empty() || dst.len() > P::HASH_LEN_BYTES {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        let mut mac = P::new_digest();
+        let res = self.result(&mut mac);
+
+        if res.is_ok() {
+            dst.copy_from_slice(&mac.as_ref()[..dst.len()]);
+        }
+
+        wipe_slice(mac.as_mut(), 0);
+        res
+    }
+
+    /// Compares `tag`, which may be truncated, against the MAC of the message in constant time.
+    pub fn verify(&mut self, tag: &[u8]) -> Result<bool> {
+        if tag.is_empty() || tag.len() > P::HASH_LEN_BYTES {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        let mut mac = P::new_digest();
+        self.result(&mut mac)?;
+
+        let equal = ct_eq(&mac.as_ref()[..tag.len()], tag);
+        wipe_slice(mac.as_mut(), 0);
+        Ok(equal)
+    }
+}
+
+impl<P: Sha2Params> Clone for Hmac<P> {
+    fn clone(&self) -> Self {
+        Hmac {
+            inner: self.inner.clone(),
+            inner_init: self.inner_init.clone(),
+            outer_init: self.outer_init.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Hmac;
+    use crate::error::Result;
+    use crate::params::{Sha224Params, Sha256Params, Sha2Params, Sha384Params, Sha512Params};
+
+    use hex_literal::hex;
+
+    fn hmac<P: Sha2Params>(key: &[u8], msg: &[u8]) -> Result<P::Digest> {
+        let mut mac = P::new_digest();
+        let mut hmac: Hmac<P> = Hmac::new(key)?;
+        hmac.input(msg)?;
+        hmac.result(&mut mac)?;
+        Ok(mac)
+    }
+
+    // RFC 4231, test case 1
+    #[test]
+    fn test_rfc4231_case1() -> Result<()> {
+        let key = [0x0b; 20];
+        let msg = b"Hi There";
+
+        assert_eq!(
+            hmac::<Sha224Params>(&key, msg)?,
+            hex!("896fb1128abbdf196832107cd49df33f47b4b1169912ba4f53684b22")
+        );
+        assert_eq!(
+            hmac::<Sha256Params>(&key, msg)?,
+            hex!("b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7")
+        );
+        assert_eq!(
+            hmac::<Sha384Params>(&key, msg)?,
+            hex!("afd03944d84895626b0825f4ab46907f15f9dadbe4101ec682aa034c7cebc59cfaea9ea9076ede7f4af152e8b2fa9cb6")
+        );
+        assert_eq!(
+            hmac::<Sha512Params>(&key, msg)?,
+            hex!("87aa7cdea5ef619d4ff0b4241a1d6cb02379f4e2ce4ec2787ad0b30545e17cdedaa833b7d6b8a702038b274eaea3f4e4be9d914eeb61f1702e696c203a126854")
+        );
+        Ok(())
+    }
+
+    // RFC 4231, test case 2
+    #[test]
+    fn test_rfc4231_case2() -> Result<()> {
+        let key = b"Jefe";
+        let msg = b"what do ya want for nothing?";
+
+        assert_eq!(
+            hmac::<Sha256Params>(key, msg)?,
+            hex!("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+        );
+        assert_eq!(
+            hmac::<Sha512Params>(key, msg)?,
+            hex!("164b7a7bfcf819e2e395fbe73b56e0a387bd64222e831fd610270cd7ea2505549758bf75c05a994a6d034f65f8f0e6fdcaeab1a34d4a6b4b636e070a38bce737")
+        );
+        Ok(())
+    }
+
+    // RFC 4231, test case 4
+    #[test]
+    fn test_rfc4231_case4() -> Result<()> {
+        let key = hex!("0102030405060708090a0b0c0d0e0f10111213141516171819");
+        let msg = [0xcd; 50];
+
+        assert_eq!(
+            hmac::<Sha384Params>(&key, &msg)?,
+            hex!("3e8a69b7783c25851933ab6290af6ca77a9981480850009cc5577c6e1f573b4e6801dd23c4a7d679ccf8a386c674cffb")
+        );
+        Ok(())
+    }
+
+    // RFC 4231, test case 5
+    #[test]
+    fn test_rfc4231_case5_truncated() -> Result<()> {
+        let key = [0x0c; 20];
+        let msg = b"Test With Truncation";
+
+        let mut mac = [0u8; 16];
+        let mut hmac: Hmac<Sha256Params> = Hmac::new(&key)?;
+        hmac.input(msg)?;
+        hmac.result_truncated(&mut mac)?;
+        assert_eq!(mac, hex!("a3b6167473100ee06e0c796c2955552b"));
+
+        let mut hmac: Hmac<Sha512Params> = Hmac::new(&key)?;
+        hmac.input(msg)?;
+        hmac.result_truncated(&mut mac)?;
+        assert_eq!(mac, hex!("415fad6271580a531d4179bc891d87a6"));
+        Ok(())
+    }
+
+    // RFC 4231, test cases 6 and 7
+    #[test]
+    fn test_rfc4231_long_key() -> Result<()> {
+        let key = [0xaa; 131];
+
+        assert_eq!(
+            hmac::<Sha256Params>(
+                &key,
+                b"Test Using Larger Than Block-Size Key - Hash Key First"
+            )?,
+            hex!("60e431591ee0b67f0d8a26aacbf5b77f8e0bc6213728c5140546040f0ee37f54")
+        );
+        assert_eq!(
+            hmac::<Sha512Params>(&key, b"This is a test using a larger than block-size key and a larger than block-size data. The key needs to be hashed before being used by the HMAC algorithm.")?,
+            hex!("e37b6a775dc87dbaa4dfa9f96e5e3ffddebd71f8867289865df5a32d20cdc944b6022cac3c4982b10d5eeb55c3e4de15134676fb6de0446065c97440fa8c6a58")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_reset_reuses_key() -> Result<()> {
+        let mut mac = [0u8; 32];
+        let mut hmac: Hmac<Sha256Params> = Hmac::new(b"Jefe")?;
+
+        hmac.input(b"something else")?;
+        hmac.result(&mut mac)?;
+
+        hmac.reset()?;
+        hmac.input(b"what do ya want ")?;
+        hmac.input(b"for nothing?")?;
+        hmac.result(&mut mac)?;
+        assert_eq!(
+            mac,
+            hex!("5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify() -> Result<()> {
+        let mut hmac: Hmac<Sha256Params> = Hmac::new(&[0x0b; 20])?;
+        hmac.input(b"Hi There")?;
+        assert!(hmac.verify(&hex!(
+            "b0344c61d8db38535ca8afceaf0bf12b881dc200c9833da726e9376c2e32cff7"
+        ))?);
+
+        hmac.reset()?;
+        hmac.input(b"Hi There")?;
+        assert!(hmac.verify(&hex!("b0344c61d8db38535ca8afceaf0bf12b"))?);
+
+        hmac.reset()?;
+        hmac.input(b"Hi There")?;
+        assert!(!hmac.verify(&hex!("b0344c61d8db38535ca8afceaf0bf12c"))?);
+
+        hmac.reset()?;
+        assert!(hmac.verify(&[0u8; 33]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,551 @@
+use crate::error::{Error, Result, Sha2Corrupted};
+use crate::hmac::Hmac;
+use crate::params::Sha2Params;
+#[cfg(feature = "zeroize")]
+use crate::wipe::wipe_slice;
+
+/// Largest number of bytes a single generate request may return (2^19 bits).
+pub const HMAC_DRBG_MAX_REQUEST_BYTES: usize = 1 << 16;
+
+/// Largest number of generate requests between reseeds allowed by SP 800-90A, and the default.
+pub const HMAC_DRBG_RESEED_INTERVAL: u64 = 1 << 48;
+
+/// HMAC_DRBG (SP 800-90A rev 1, section 10.1.2).
+///
+/// The DRBG has no entropy source of its own: entropy input is passed to [`HmacDrbg::new`],
+/// [`HmacDrbg::reseed`] and [`HmacDrbg::generate_with_prediction_resistance`] by the caller.
+/// Once the reseed interval is exhausted, generate requests fail with a `StateError` until the
+/// next reseed.
+pub struct HmacDrbg<P: Sha2Params> {
+    k: P::Digest,
+    v: P::Digest,
+    reseed_counter: u64,
+    reseed_interval: u64,
+}
+
+impl<P: Sha2Params> HmacDrbg<P> {
+    /// Instantiates the DRBG. `entropy` must carry at least the security strength of the hash
+    /// function: 192 bits for SHA-224 and SHA-512/224, 256 bits for the others.
+    pub fn new(entropy: &[u8], nonce: &[u8], personalization: &[u8]) -> Result<Self> {
+        Self::check_entropy(entropy)?;
+        Self::from_seed_material(&[entropy, nonce, personalization])
+    }
+
+    /// Instantiation without the entropy length check, for constructions such as RFC 6979 that
+    /// seed the DRBG with key material of their own.
+    pub(crate) fn from_seed_material(seed_material: &[&[u8]]) -> Result<Self> {
+        let mut v = P::new_digest();
+        v.as_mut().fill(0x01);
+
+        let mut drbg = HmacDrbg {
+            k: P::new_digest(),
+            v,
+            reseed_counter: 1,
+            reseed_interval: HMAC_DRBG_RESEED_INTERVAL,
+        };
+        drbg.update(seed_material)?;
+        Ok(drbg)
+    }
+
+    /// Lowers the number of generate requests allowed between reseeds.
+    pub fn with_reseed_interval(mut self, reseed_interval: u64) -> Result<Self> {
+        if reseed_interval == 0 || reseed_interval > HMAC_DRBG_RESEED_INTERVAL {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        self.reseed_interval = reseed_interval;
+        Ok(self)
+    }
+
+    /// Number of generate requests since the last (re)seed, plus one.
+    pub fn reseed_counter(&self) -> u64 {
+        self.reseed_counter
+    }
+
+    pub fn reseed(&mut self, entropy: &[u8], additional_input: &[u8]) -> Result<()> {
+        Self::check_entropy(entropy)?;
+        self.update(&[entropy, additional_input])?;
+        self.reseed_counter = 1;
+        Ok(())
+    }
+
+    /// Fills `out`, which may be at most [`HMAC_DRBG_MAX_REQUEST_BYTES`] long. An empty
+    /// `additional_input` is the same as none.
+    pub fn generate(&mut self, out: &mut [u8], additional_input: &[u8]) -> Result<()> {
+        if out.len() > HMAC_DRBG_MAX_REQUEST_BYTES {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        if self.reseed_counter > self.reseed_interval {
+            return Err(Error(Sha2Corrupted::StateError));
+        }
+
+        if !additional_input.is_empty() {
+            self.update(&[additional_input])?;
+        }
+
+        let mut mac: Hmac<P> = Hmac::new(self.k.as_ref())?;
+        for out_block in out.chunks_mut(P::HASH_LEN_BYTES) {
+            mac.reset()?;
+            mac.input(self.v.as_ref())?;
+            mac.result(&mut self.v)?;
+            out_block.copy_from_slice(&self.v.as_ref()[..out_block.len()]);
+        }
+
+        self.update(&[additional_input])?;
+        self.reseed_counter += 1;
+        Ok(())
+    }
+
+    /// A generate request with prediction resistance: the DRBG is reseeded with fresh `entropy`
+    /// and `additional_input` first, then generates without additional input.
+    pub fn generate_with_prediction_resistance(
+        &mut self,
+        entropy: &[u8],
+        out: &mut [u8],
+        additional_input: &[u8],
+    ) -> Result<()> {
+        self.reseed(entropy, additional_input)?;
+        self.generate(out, &[])
+    }
+
+    /// HMAC_DRBG_Update with `provided_data` given as the concatenation of its parts.
+    fn update(&mut self, provided_data: &[&[u8]]) -> Result<()> {
+        let has_data = provided_data.iter().any(|part| !part.is_empty());
+
+        for round in [0x00u8, 0x01] {
+            if round == 0x01 && !has_data {
+                break;
+            }
+
+            let mut mac: Hmac<P> = Hmac::new(self.k.as_ref())?;
+            mac.input(self.v.as_ref())?;
+            mac.input(&[round])?;
+            for part in provided_data {
+                mac.input(part)?;
+            }
+            mac.result(&mut self.k)?;
+
+            let mut mac: Hmac<P> = Hmac::new(self.k.as_ref())?;
+            mac.input(self.v.as_ref())?;
+            mac.result(&mut self.v)?;
+        }
+
+        Ok(())
+    }
+
+    fn check_entropy(entropy: &[u8]) -> Result<()> {
+        let security_strength = if P::HASH_LEN_BYTES < 32 { 24 } else { 32 };
+
+        if entropy.len() < security_strength {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<P: Sha2Params> Drop for HmacDrbg<P> {
+    fn drop(&mut self) {
+        wipe_slice(self.k.as_mut(), 0);
+        wipe_slice(self.v.as_mut(), 0);
+    }
+}
+
+/// Errors from the DRBG surface as this custom `rand_core` error code.
+#[cfg(feature = "rand_core")]
+const RAND_CORE_ERROR_CODE: std::num::NonZeroU32 =
+    match std::num::NonZeroU32::new(rand_core::Error::CUSTOM_START) {
+        Some(code) => code,
+        None => unreachable!(),
+    };
+
+/// Requests longer than [`HMAC_DRBG_MAX_REQUEST_BYTES`] are split into several generate calls.
+/// `fill_bytes` and the `next_*` methods panic when the DRBG needs a reseed.
+#[cfg(feature = "rand_core")]
+impl<P: Sha2Params> rand_core::RngCore for HmacDrbg<P> {
+    fn next_u32(&mut self) -> u32 {
+        let mut buf = [0u8; 4];
+        self.fill_bytes(&mut buf);
+        u32::from_le_bytes(buf)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut buf = [0u8; 8];
+        self.fill_bytes(&mut buf);
+        u64::from_le_bytes(buf)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        if let Err(err) = self.try_fill_bytes(dest) {
+            panic!("HMAC_DRBG failed: {err}");
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> std::result::Result<(), rand_core::Error> {
+        dest.chunks_mut(HMAC_DRBG_MAX_REQUEST_BYTES)
+            .try_for_each(|chunk| self.generate(chunk, &[]))
+            .map_err(|_| rand_core::Error::from(RAND_CORE_ERROR_CODE))
+    }
+}
+
+#[cfg(feature = "rand_core")]
+impl<P: Sha2Params> rand_core::CryptoRng for HmacDrbg<P> {}
+
+#[cfg(test)]
+mod tests {
+    use super::HmacDrbg;
+    use crate::error::Result;
+    use crate::params::{Sha256Params, Sha2Params, Sha512Params};
+
+    use hex_literal::hex;
+
+    fn seq(start: u8, len: u8) -> Vec<u8> {
+        (start..start + len).collect()
+    }
+
+    // NIST CAVS 14.3 HMAC_DRBG entries (drbgvectors_no_reseed and drbgvectors_pr_false /
+    // drbgvectors_pr_true), as carried in mbed TLS's test_suite_hmac_drbg data files. Each case
+    // instantiates, optionally reseeds, generates twice and checks the second ReturnedBits.
+    struct Case {
+        entropy_input: &'static [u8],
+        nonce: &'static [u8],
+        personalization_string: &'static [u8],
+        // EntropyInputReseed, or EntropyInputPR for each generate call
+        later_entropy: &'static [&'static [u8]],
+        // AdditionalInputReseed first, when the case reseeds
+        additional_input: &'static [&'static [u8]],
+        returned_bits: &'static [u8],
+    }
+
+    const NO_RESEED: [Case; 2] = [
+        Case {
+            entropy_input: &hex!(
+                "5d3286bc53a258a53ba781e2c4dcd79a790e43bbe0e89fb3eed39086be34174b"
+            ),
+            nonce: &hex!("c5422294b7318952ace7055ab7570abf"),
+            personalization_string: &hex!(
+                "2dba094d008e150d51c4135bb2f03dcde9cbf3468a12908a1b025c120c985b9d"
+            ),
+            later_entropy: &[],
+            additional_input: &[
+                &hex!("793a7ef8f6f0482beac542bb785c10f8b7b406a4de92667ab168ecc2cf7573c6"),
+                &hex!("2238cdb4e23d629fe0c2a83dd8d5144ce1a6229ef41dabe2a99ff722e510b530"),
+            ],
+            returned_bits: &hex!(
+                "d04678198ae7e1aeb435b45291458ffde0891560748b43330eaf866b5a6385e7"
+                "4c6fa5a5a44bdb284d436e98d244018d6acedcdfa2e9f499d8089e4db86ae89a"
+                "6ab2d19cb705e2f048f97fb597f04106a1fa6a1416ad3d859118e079a0c319eb"
+                "95686f4cbcce3b5101c7a0b010ef029c4ef6d06cdfac97efb9773891688c37cf"
+            ),
+        },
+        Case {
+            entropy_input: &hex!(
+                "35049f389a33c0ecb1293238fd951f8ffd517dfde06041d32945b3e26914ba15"
+            ),
+            nonce: &hex!("f7328760be6168e6aa9fb54784989a11"),
+            personalization_string: &[],
+            later_entropy: &[],
+            additional_input: &[&[], &[]],
+            returned_bits: &hex!(
+                "e76491b0260aacfded01ad39fbf1a66a88284caa5123368a2ad9330ee48335e3"
+                "c9c9ba90e6cbc9429962d60c1a6661edcfaa31d972b8264b9d4562cf18494128"
+                "a092c17a8da6f3113e8a7edfcd4427082bd390675e9662408144971717303d8d"
+                "c352c9e8b95e7f35fa2ac9f549b292bc7c4bc7f01ee0a577859ef6e82d79ef23"
+                "892d167c140d22aac32b64ccdfeee2730528a38763b24227f91ac3ffe47fb115"
+                "38e435307e77481802b0f613f370ffb0dbeab774fe1efbb1a80d01154a9459e7"
+                "3ad361108bbc86b0914f095136cbe634555ce0bb263618dc5c367291ce082551"
+                "8987154fe9ecb052b3f0a256fcc30cc14572531c9628973639beda456f2bddf6"
+            ),
+        },
+    ];
+
+    const PR_FALSE: [Case; 4] = [
+        Case {
+            entropy_input: &hex!(
+                "06032cd5eed33f39265f49ecb142c511da9aff2af71203bffaf34a9ca5bd9c0d"
+            ),
+            nonce: &hex!("0e66f71edc43e42a45ad3c6fc6cdc4df"),
+            personalization_string: &[],
+            later_entropy: &[&hex!(
+                "01920a4e669ed3a85ae8a33b35a74ad7fb2a6bb4cf395ce00334a9c9a5a5d552"
+            )],
+            additional_input: &[&[], &[], &[]],
+            returned_bits: &hex!(
+                "76fc79fe9b50beccc991a11b5635783a83536add03c157fb30645e611c2898bb"
+                "2b1bc215000209208cd506cb28da2a51bdb03826aaf2bd2335d576d519160842"
+                "e7158ad0949d1a9ec3e66ea1b1a064b005de914eac2e9d4f2d72a8616a802254"
+                "22918250ff66a41bd2f864a6a38cc5b6499dc43f7f2bd09e1e0f8f5885935124"
+            ),
+        },
+        Case {
+            entropy_input: &hex!(
+                "cdb0d9117cc6dbc9ef9dcb06a97579841d72dc18b2d46a1cb61e314012bdf416"
+            ),
+            nonce: &hex!("d0c0d01d156016d0eb6b7e9c7c3c8da8"),
+            personalization_string: &hex!(
+                "6f0fb9eab3f9ea7ab0a719bfa879bf0aaed683307fda0c6d73ce018b6e34faaa"
+            ),
+            later_entropy: &[&hex!(
+                "8ec6f7d5a8e2e88f43986f70b86e050d07c84b931bcf18e601c5a3eee3064c82"
+            )],
+            additional_input: &[
+                &hex!("1ab4ca9014fa98a55938316de8ba5a68c629b0741bdd058c4d70c91cda5099b3"),
+                &hex!("16e2d0721b58d839a122852abd3bf2c942a31c84d82fca74211871880d7162ff"),
+                &hex!("53686f042a7b087d5d2eca0d2a96de131f275ed7151189f7ca52deaa78b79fb2"),
+            ],
+            returned_bits: &hex!(
+                "dda04a2ca7b8147af1548f5d086591ca4fd951a345ce52b3cd49d47e84aa31a1"
+                "83e31fbc42a1ff1d95afec7143c8008c97bc2a9c091df0a763848391f68cb4a3"
+                "66ad89857ac725a53b303ddea767be8dc5f605b1b95f6d24c9f06be65a973a08"
+                "9320b3cc42569dcfd4b92b62a993785b0301b3fc452445656fce22664827b88f"
+            ),
+        },
+        Case {
+            entropy_input: &hex!(
+                "48c121b18733af15c27e1dd9ba66a9a81a5579cdba0f5b657ec53c2b9e90bbf6"
+            ),
+            nonce: &hex!("bbb7c777428068fad9970891f879b1af"),
+            personalization_string: &[],
+            later_entropy: &[&hex!(
+                "e0ffefdadb9ccf990504d568bdb4d862cbe17ccce6e22dfcab8b4804fd21421a"
+            )],
+            additional_input: &[&[], &[], &[]],
+            returned_bits: &hex!(
+                "05da6aac7d980da038f65f392841476d37fe70fbd3e369d1f80196e66e54b8fa"
+                "db1d60e1a0f3d4dc173769d75fc3410549d7a843270a54a068b4fe767d7d9a59"
+                "604510a875ad1e9731c8afd0fd50b825e2c50d062576175106a9981be37e02ec"
+                "7c5cd0a69aa0ca65bddaee1b0de532e10cfa1f5bf6a026e47379736a099d6750"
+                "ab121dbe3622b841baf8bdcbe875c85ba4b586b8b5b57b0fecbec08c12ff2a94"
+                "53c47c6e32a52103d972c62ab9affb8e728a31fcefbbccc556c0f0a35f4b10ac"
+                "e2d96b906e36cbb72233201e536d3e13b045187b417d2449cad1edd192e061f1"
+                "2d22147b0a176ea8d9c4c35404395b6502ef333a813b6586037479e0fa3c6a23"
+            ),
+        },
+        Case {
+            entropy_input: &hex!(
+                "da740cbc36057a8e282ae717fe7dfbb245e9e5d49908a0119c5dbcf0a1f2d5ab"
+            ),
+            nonce: &hex!("46561ff612217ba3ff91baa06d4b5440"),
+            personalization_string: &hex!(
+                "fc227293523ecb5b1e28c87863626627d958acc558a672b148ce19e2abd2dde4"
+            ),
+            later_entropy: &[&hex!(
+                "1d61d4d8a41c3254b92104fd555adae0569d1835bb52657ec7fbba0fe03579c5"
+            )],
+            additional_input: &[
+                &hex!("b9ed8e35ad018a375b61189c8d365b00507cb1b4510d21cac212356b5bbaa8b2"),
+                &hex!("b7998998eaf9e5d34e64ff7f03de765b31f407899d20535573e670c1b402c26a"),
+                &hex!("2089d49d63e0c4df58879d0cb1ba998e5b3d1a7786b785e7cf13ca5ea5e33cfd"),
+            ],
+            returned_bits: &hex!(
+                "5b70f3e4da95264233efbab155b828d4e231b67cc92757feca407cc9615a6608"
+                "71cb07ad1a2e9a99412feda8ee34dc9c57fa08d3f8225b30d29887d20907d123"
+                "30fffd14d1697ba0756d37491b0a8814106e46c8677d49d9157109c402ad0c24"
+                "7a2f50cd5d99e538c850b906937a05dbb8888d984bc77f6ca00b0e3bc97b16d6"
+                "d25814a54aa12143afddd8b2263690565d545f4137e593bb3ca88a37b0aadf79"
+                "726b95c61906257e6dc47acd5b6b7e4b534243b13c16ad5a0a1163c0099fce43"
+                "f428cd27c3e6463cf5e9a9621f4b3d0b3d4654316f4707675df39278d5783823"
+                "049477dcce8c57fdbd576711c91301e9bd6bb0d3e72dc46d480ed8f61fd63811"
+            ),
+        },
+    ];
+
+    const PR_TRUE: [Case; 4] = [
+        Case {
+            entropy_input: &hex!(
+                "9969e54b4703ff31785b879a7e5c0eae0d3e309559e9fe96b0676d49d591ea4d"
+            ),
+            nonce: &hex!("07d20d46d064757d3023cac2376127ab"),
+            personalization_string: &[],
+            later_entropy: &[
+                &hex!("c60f2999100f738c10f74792676a3fc4a262d13721798046e29a295181569f54"),
+                &hex!("c11d4524c9071bd3096015fcf7bc24a607f22fa065c937658a2a77a8699089f4"),
+            ],
+            additional_input: &[&[], &[]],
+            returned_bits: &hex!(
+                "abc015856094803a938dffd20da94843870ef935b82cfec17706b8f551b83850"
+                "44235dd44b599f94b39be78dd476e0cf11309c995a7334e0a78b37bc95862350"
+                "86fa3b637ba91cf8fb65efa22a589c137531aa7b2d4e2607aac27292b01c698e"
+                "6e01ae679eb87c01a89c7422d4372d6d754ababb4bf896fcb1cd09d692d0283f"
+            ),
+        },
+        Case {
+            entropy_input: &hex!(
+                "4294671d493dc085b5184607d7de2ff2b6aceb734a1b026f6cfee7c5a90f03da"
+            ),
+            nonce: &hex!("d071544e599235d5eb38b64b551d2a6e"),
+            personalization_string: &hex!(
+                "63bc769ae1d95a98bde870e4db7776297041d37c8a5c688d4e024b78d83f4d78"
+            ),
+            later_entropy: &[
+                &hex!("db9b4790b62336fbb9a684b82947065393eeef8f57bd2477141ad17e776dac34"),
+                &hex!("4a9abe80f6f522f29878bedf8245b27940a76471006fb4a4110beb4decb6c341"),
+            ],
+            additional_input: &[
+                &hex!("28848becd3f47696f124f4b14853a456156f69be583a7d4682cff8d44b39e1d3"),
+                &hex!("8bfce0b7132661c3cd78175d83926f643e36f7608eec2c5dac3ddcbacc8c2182"),
+            ],
+            returned_bits: &hex!(
+                "e580dc969194b2b18a97478aef9d1a72390aff14562747bf080d741527a6655c"
+                "e7fc135325b457483a9f9c70f91165a811cf4524b50d51199a0df3bd60d12aba"
+                "c27d0bf6618e6b114e05420352e23f3603dfe8a225dc19b3d1fff1dc245dc6b1"
+                "df24c741744bec3f9437dbbf222df84881a457a589e7815ef132f686b760f012"
+            ),
+        },
+        Case {
+            entropy_input: &hex!(
+                "64a8afb71975256b6196f3f93038ba8b7a4d7089f7f268134cb3f5926868e4d1"
+            ),
+            nonce: &hex!("04c60b44fbf3bc198f4bc58bf1260d12"),
+            personalization_string: &[],
+            later_entropy: &[
+                &hex!("3a5aaf8749136a86c4e5aba81692d587133d29d3b7a63fa6204ed84e93be6aeb"),
+                &hex!("f50472d313ef5797d1a290a7cae086052b57e8d5a20ed22ec7702dd424d935ea"),
+            ],
+            additional_input: &[&[], &[]],
+            returned_bits: &hex!(
+                "4f61f6b5d46ea351dc6f8ff55bcb915d998c8e871b5e122dd95196da241c49a1"
+                "170b1fc16ffa31a6dc4f0c4068ecc6e5cc0fa6966aedf72bcb19e666b191979f"
+                "22580b6505c09a784e76f58d30af3abcbe840497ad88621a893ffe13af6aef0f"
+                "8276f9540068943bb6bc51498a465129880df4c517f7fe70ec239c055102a78b"
+                "8b0f26d36bc2634a0e61a1431850980c258326197cc80d07c3cafc49a20316a0"
+                "fa2703f850b66ce274e839d6dddba4d3e744306d768b7437ec9c54ed864c7bca"
+                "4ea8d0987d815e64f685e0726eb4223aa5eac1a0979fb335248ee59819c36c7c"
+                "94dadf14474c7e2f10678da59f255474ea50c3ed5ccf86a399ba7f54ae96bff0"
+            ),
+        },
+        Case {
+            entropy_input: &hex!(
+                "3aca6b55561521007c9ece085e9a6635e346fa804335d6ad42ebd6814c017fa8"
+            ),
+            nonce: &hex!("aa7fd3c3dd5d03d9b8efc7f70574581f"),
+            personalization_string: &hex!(
+                "4bc9a485ec840d377ae4504aa1df41e444c4231687f3d7851c26c275bc687463"
+            ),
+            later_entropy: &[
+                &hex!("4cc19fae5a456f8a53a656d23a0b665d6ddf7f43020a5febbb552714e447565d"),
+                &hex!("637386b3ab33f78fd9751c7b7e67e1e15f6e50ddc548a1eb5813f6d0d48381bf"),
+            ],
+            additional_input: &[
+                &hex!("b39c43539fdc24343085cbb65b8d36c54732476d781104c355c391a951313a30"),
+                &hex!("b6850edd4622675ef5a507eab911e249d63fcf62f330cc8a16bb2ccc5858de5d"),
+            ],
+            returned_bits: &hex!(
+                "546664042bef33064da28a5718f2c2e5f72d7725e3fbe87ad2ee90fbfe6c114e"
+                "d36440fbbccf29698b4360bc4ad74650de13825838106adc53002bc389ee9006"
+                "91649b972f3187b84d05cecc8fd034497dd99c6c997d1914b4ef838d84abf23f"
+                "ae7f3ac9efdcdc04c003ac642c5126b00f9f24bf1431a4f19ef0b5f3d230aab3"
+                "fdf091ba31b7ddcacdf2566f2cfab30f55b3123e733829b697b7c8b248420ab9"
+                "8ba6f11b017175256368e8d8361102c9e6d57386becbeabda092dd57aec65bc2"
+                "0ebee78eea7294571e168c454066d256b81bb8b7bb469207a18ebedbb4348fbe"
+                "97a4d86d2bd095c41f6de59aa0800e131e98181886a2633cdcc550914d83b327"
+            ),
+        },
+    ];
+
+    fn instantiate<P: Sha2Params>(case: &Case) -> Result<HmacDrbg<P>> {
+        HmacDrbg::new(case.entropy_input, case.nonce, case.personalization_string)
+    }
+
+    fn check_no_reseed<P: Sha2Params>(case: &Case) -> Result<()> {
+        let mut drbg = instantiate::<P>(case)?;
+        let mut out = vec![0u8; case.returned_bits.len()];
+        drbg.generate(&mut out, case.additional_input[0])?;
+        drbg.generate(&mut out, case.additional_input[1])?;
+        assert_eq!(out, case.returned_bits);
+        Ok(())
+    }
+
+    fn check_pr_false<P: Sha2Params>(case: &Case) -> Result<()> {
+        let mut drbg = instantiate::<P>(case)?;
+        drbg.reseed(case.later_entropy[0], case.additional_input[0])?;
+        let mut out = vec![0u8; case.returned_bits.len()];
+        drbg.generate(&mut out, case.additional_input[1])?;
+        drbg.generate(&mut out, case.additional_input[2])?;
+        assert_eq!(out, case.returned_bits);
+        Ok(())
+    }
+
+    fn check_pr_true<P: Sha2Params>(case: &Case) -> Result<()> {
+        let mut drbg = instantiate::<P>(case)?;
+        let mut out = vec![0u8; case.returned_bits.len()];
+        for (entropy, additional_input) in case.later_entropy.iter().zip(case.additional_input) {
+            drbg.generate_with_prediction_resistance(entropy, &mut out, additional_input)?;
+        }
+        assert_eq!(out, case.returned_bits);
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_reseed() -> Result<()> {
+        check_no_reseed::<Sha256Params>(&NO_RESEED[0])?;
+        check_no_reseed::<Sha512Params>(&NO_RESEED[1])
+    }
+
+    #[test]
+    fn test_prediction_resistance_false() -> Result<()> {
+        check_pr_false::<Sha256Params>(&PR_FALSE[0])?;
+        check_pr_false::<Sha256Params>(&PR_FALSE[1])?;
+        check_pr_false::<Sha512Params>(&PR_FALSE[2])?;
+        check_pr_false::<Sha512Params>(&PR_FALSE[3])
+    }
+
+    #[test]
+    fn test_prediction_resistance_true() -> Result<()> {
+        check_pr_true::<Sha256Params>(&PR_TRUE[0])?;
+        check_pr_true::<Sha256Params>(&PR_TRUE[1])?;
+        check_pr_true::<Sha512Params>(&PR_TRUE[2])?;
+        check_pr_true::<Sha512Params>(&PR_TRUE[3])
+    }
+
+    #[test]
+    fn test_reseed_interval() -> Result<()> {
+        let mut out = [0u8; 16];
+        let mut drbg: HmacDrbg<Sha256Params> =
+            HmacDrbg::new(&seq(0, 32), &[], &[])?.with_reseed_interval(2)?;
+
+        drbg.generate(&mut out, &[])?;
+        drbg.generate(&mut out, &[])?;
+        assert_eq!(drbg.reseed_counter(), 3);
+        assert!(drbg.generate(&mut out, &[]).is_err());
+
+        drbg.reseed(&seq(0x80, 32), &[])?;
+        drbg.generate(&mut out, &[])?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_params() -> Result<()> {
+        assert!(HmacDrbg::<Sha256Params>::new(&seq(0, 31), &[], &[]).is_err());
+        assert!(HmacDrbg::<Sha256Params>::new(&seq(0, 32), &[], &[])?
+            .with_reseed_interval(0)
+            .is_err());
+
+        let mut drbg: HmacDrbg<Sha256Params> = HmacDrbg::new(&seq(0, 32), &[], &[])?;
+        assert!(drbg.reseed(&seq(0, 16), &[]).is_err());
+
+        let mut out = vec![0u8; super::HMAC_DRBG_MAX_REQUEST_BYTES + 1];
+        assert!(drbg.generate(&mut out, &[]).is_err());
+        Ok(())
+    }
+
+    #[cfg(feature = "rand_core")]
+    #[test]
+    fn test_rng_core() -> Result<()> {
+        use rand_core::RngCore;
+
+        let mut drbg: HmacDrbg<Sha256Params> = HmacDrbg::new(&seq(0, 32), &seq(0x20, 16), &[])?;
+        let mut expected: HmacDrbg<Sha256Params> = HmacDrbg::new(&seq(0, 32), &seq(0x20, 16), &[])?;
+
+        let mut out = [0u8; 8];
+        expected.generate(&mut out, &[])?;
+        assert_eq!(drbg.next_u64(), u64::from_le_bytes(out));
+
+        let mut big = vec![0u8; super::HMAC_DRBG_MAX_REQUEST_BYTES + 10];
+        drbg.fill_bytes(&mut big);
+        assert_eq!(drbg.reseed_counter(), 4);
+        Ok(())
+    }
+}
@@ -0,0 +1,434 @@
+use crate::error::{Error, Result, Sha2Corrupted};
+use crate::hmac::Hmac;
+use crate::params::Sha2Params;
+use crate::wipe::wipe_slice;
+
+/// The iteration modes of SP 800-108r1, section 4.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KbkdfMode {
+    /// `K(i) = PRF(K_IN, [i] || FixedInput)`.
+    Counter,
+    /// `K(i) = PRF(K_IN, K(i-1) || [i] || FixedInput)` with `K(0) = IV`.
+    Feedback,
+    /// `A(i) = PRF(K_IN, A(i-1))` with `A(0) = FixedInput`, and
+    /// `K(i) = PRF(K_IN, A(i) || [i] || FixedInput)`.
+    DoublePipeline,
+}
+
+/// Where the counter `[i]` goes in the PRF input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KbkdfCounterLocation {
+    /// In front of the iteration variable (`K(i-1)` or `A(i)`). Not available in counter mode.
+    BeforeIteration,
+    /// Right in front of the fixed input; after the iteration variable, if there is one.
+    BeforeFixed,
+    /// After the fixed input.
+    AfterFixed,
+    /// Inside the fixed input, at the given byte offset. Counter mode only.
+    MiddleFixed(usize),
+}
+
+/// Configuration of a [`kbkdf`] derivation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KbkdfParams {
+    mode: KbkdfMode,
+    counter_bits: u32,
+    location: KbkdfCounterLocation,
+    length_bits: u32,
+    iv: Vec<u8>,
+}
+
+impl KbkdfParams {
+    /// `counter_bits` is the width `r` of the counter: 8, 16, 24 or 32, or 0 to leave the counter
+    /// out, which only the feedback and double-pipeline modes allow. The length field `[L]` of
+    /// [`kbkdf`] defaults to 32 bits and the feedback IV to the empty string.
+    pub fn new(mode: KbkdfMode, counter_bits: u32, location: KbkdfCounterLocation) -> Result<Self> {
+        let valid = match (mode, location) {
+            (KbkdfMode::Counter, KbkdfCounterLocation::BeforeIteration) => false,
+            (KbkdfMode::Counter, _) => counter_bits != 0,
+            (_, KbkdfCounterLocation::MiddleFixed(_)) => false,
+            _ => true,
+        };
+
+        if !valid || counter_bits > 32 || !counter_bits.is_multiple_of(8) {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        Ok(KbkdfParams {
+            mode,
+            counter_bits,
+            location,
+            length_bits: 32,
+            iv: Vec::new(),
+        })
+    }
+
+    /// Sets the width in bits of the encoded output length `[L]` used by [`kbkdf`].
+    pub fn with_length_bits(mut self, length_bits: u32) -> Self {
+        self.length_bits = length_bits;
+        self
+    }
+
+    /// Sets `K(0)` for the feedback mode. It is ignored by the other modes.
+    pub fn with_iv(mut self, iv: &[u8]) -> Self {
+        self.iv = iv.to_vec();
+        self
+    }
+
+    pub fn mode(&self) -> KbkdfMode {
+        self.mode
+    }
+
+    pub fn counter_bits(&self) -> u32 {
+        self.counter_bits
+    }
+
+    pub fn location(&self) -> KbkdfCounterLocation {
+        self.location
+    }
+
+    pub fn length_bits(&self) -> u32 {
+        self.length_bits
+    }
+
+    pub fn iv(&self) -> &[u8] {
+        &self.iv
+    }
+}
+
+/// SP 800-108r1 key derivation with HMAC as the PRF, filling `out` from the key-derivation key
+/// `key`.
+///
+/// The fixed input is encoded as `Label || 0x00 || Context || [L]`, where `L` is the output
+/// length in bits written big-endian in [`KbkdfParams::length_bits`] bits.
+pub fn kbkdf<P: Sha2Params>(
+    params: &KbkdfParams,
+    key: &[u8],
+    label: &[u8],
+    context: &[u8],
+    out: &mut [u8],
+) -> Result<()> {
+    let length_bytes = match params.length_bits {
+        8 | 16 | 24 | 32 => (params.length_bits / 8) as usize,
+        _ => return Err(Error(Sha2Corrupted::BadParam)),
+    };
+
+    let l = (out.len() as u64)
+        .checked_mul(8)
+        .filter(|l| l >> params.length_bits == 0)
+        .ok_or(Error(Sha2Corrupted::BadParam))?;
+
+    let mut fixed_input = Vec::with_capacity(label.len() + 1 + context.len() + length_bytes);
+    fixed_input.extend_from_slice(label);
+    fixed_input.push(0x00);
+    fixed_input.extend_from_slice(context);
+    fixed_input.extend_from_slice(&l.to_be_bytes()[8 - length_bytes..]);
+
+    kbkdf_fixed_input::<P>(params, key, &fixed_input, out)
+}
+
+/// [`kbkdf`] with a caller-encoded fixed input, as used by the CAVP test vectors.
+/// [`KbkdfParams::length_bits`] is not used.
+pub fn kbkdf_fixed_input<P: Sha2Params>(
+    params: &KbkdfParams,
+    key: &[u8],
+    fixed_input: &[u8],
+    out: &mut [u8],
+) -> Result<()> {
+    let blocks = out.len().div_ceil(P::HASH_LEN_BYTES) as u64;
+    let counter_bytes = (params.counter_bits / 8) as usize;
+
+    if out.is_empty() || blocks > u32::MAX as u64 {
+        return Err(Error(Sha2Corrupted::BadParam));
+    }
+
+    // the counter must not wrap around
+    if counter_bytes != 0 && blocks >> params.counter_bits != 0 {
+        return Err(Error(Sha2Corrupted::BadParam));
+    }
+
+    let (fixed_head, fixed_tail) = match params.location {
+        KbkdfCounterLocation::MiddleFixed(offset) if offset > fixed_input.len() => {
+            return Err(Error(Sha2Corrupted::BadParam))
+        }
+        KbkdfCounterLocation::MiddleFixed(offset) => fixed_input.split_at(offset),
+        KbkdfCounterLocation::AfterFixed => (fixed_input, &[][..]),
+        _ => (&[][..], fixed_input),
+    };
+
+    let prf: Hmac<P> = Hmac::new(key)?;
+    let mut k = P::new_digest();
+    let mut a = P::new_digest();
+
+    let res = out
+        .chunks_mut(P::HASH_LEN_BYTES)
+        .enumerate()
+        .try_for_each(|(i, out_block)| {
+            let counter = (i as u32 + 1).to_be_bytes();
+            let counter = &counter[4 - counter_bytes..];
+
+            let iteration: &[u8] = match params.mode {
+                KbkdfMode::Counter => &[],
+                KbkdfMode::Feedback if i == 0 => &params.iv,
+                KbkdfMode::Feedback => k.as_ref(),
+                KbkdfMode::DoublePipeline => {
+                    let mut pipeline = prf.clone();
+                    pipeline.input(if i == 0 { fixed_input } else { a.as_ref() })?;
+                    pipeline.result(&mut a)?;
+                    a.as_ref()
+                }
+            };
+
+            let mut mac = prf.clone();
+            if params.location == KbkdfCounterLocation::BeforeIteration {
+                mac.input(counter)?;
+                mac.input(iteration)?;
+            } else {
+                mac.input(iteration)?;
+                mac.input(fixed_head)?;
+                mac.input(counter)?;
+            }
+            mac.input(fixed_tail)?;
+            mac.result(&mut k)?;
+
+            out_block.copy_from_slice(&k.as_ref()[..out_block.len()]);
+            Ok(())
+        });
+
+    wipe_slice(k.as_mut(), 0);
+    wipe_slice(a.as_mut(), 0);
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{kbkdf, kbkdf_fixed_input, KbkdfCounterLocation, KbkdfMode, KbkdfParams};
+    use crate::error::Result;
+    use crate::params::{Sha256Params, Sha384Params, Sha512Params};
+
+    use hex_literal::hex;
+
+    const KEY: [u8; 32] = hex!("dd1d91b7d90b2bd3138533ce92b272fbf8a369316aefe242e659cc0ae238afe0");
+    const FIXED: [u8; 16] = hex!("01322b96b30acd197979444e468e1ee5");
+
+    // RFC 8009 appendix A: the PRF outputs and the Kc/Ke/Ki derivations for
+    // aes128-cts-hmac-sha256-128 and aes256-cts-hmac-sha384-192
+    #[test]
+    fn test_rfc8009() -> Result<()> {
+        let params = KbkdfParams::new(KbkdfMode::Counter, 32, KbkdfCounterLocation::BeforeFixed)?;
+
+        let key = hex!("3705d96080c17728a0e800eab6e0d23c");
+        let mut out = [0u8; 32];
+        kbkdf::<Sha256Params>(&params, &key, b"prf", b"test", &mut out)?;
+        assert_eq!(
+            out,
+            hex!("9d188616f63852fe86915bb840b4a886ff3e6bb0f819b49b893393d393854295")
+        );
+        let mut out = [0u8; 16];
+        kbkdf::<Sha256Params>(&params, &key, &hex!("0000000299"), b"", &mut out)?;
+        assert_eq!(out, hex!("b31a018a48f54776f403e9a396325dc3"));
+        kbkdf::<Sha256Params>(&params, &key, &hex!("00000002aa"), b"", &mut out)?;
+        assert_eq!(out, hex!("9b197dd1e8c5609d6e67c3e37c62c72e"));
+        kbkdf::<Sha256Params>(&params, &key, &hex!("0000000255"), b"", &mut out)?;
+        assert_eq!(out, hex!("9fda0e56ab2d85e1569a688696c26a6c"));
+
+        let key = hex!("6d404d37faf79f9df0d33568d320669800eb4836472ea8a026d16b7182460c52");
+        let mut out = [0u8; 48];
+        kbkdf::<Sha384Params>(&params, &key, b"prf", b"test", &mut out)?;
+        assert_eq!(out, hex!("9801f69a368c2bf675e59521e177d9a07f67efe1cfde8d3c8d6f6a0256e3b17db3c1b62ad1b8553360d17367eb1514d2"));
+        let mut out = [0u8; 24];
+        kbkdf::<Sha384Params>(&params, &key, &hex!("0000000299"), b"", &mut out)?;
+        assert_eq!(
+            out,
+            hex!("ef5718be86cc84963d8bbb5031e9f5c4ba41f28faf69e73d")
+        );
+        kbkdf::<Sha384Params>(&params, &key, &hex!("0000000255"), b"", &mut out)?;
+        assert_eq!(
+            out,
+            hex!("69b16514e3cd8e56b82010d5c73012b622c4d00ffc23ed1f")
+        );
+        let mut out = [0u8; 32];
+        kbkdf::<Sha384Params>(&params, &key, &hex!("00000002aa"), b"", &mut out)?;
+        assert_eq!(
+            out,
+            hex!("56ab22bee63d82d7bc5227f6773f8ea7a5eb1c825160c38312980c442e5c7e49")
+        );
+        Ok(())
+    }
+
+    // CAVP KDFCTR, [PRF=HMAC_SHA256] [CTRLOCATION=BEFORE_FIXED] [RLEN=32_BITS], COUNT=0
+    #[test]
+    fn test_cavp_counter() -> Result<()> {
+        let params = KbkdfParams::new(KbkdfMode::Counter, 32, KbkdfCounterLocation::BeforeFixed)?;
+        let fixed = hex!("01322b96b30acd197979444e468e1c5c6859bf1b1cf951b7e725303e237e46b864a145fab25e517b08f8683d0315bb2911d80a0e8aba17f3b413faac");
+        let mut out = [0u8; 16];
+        kbkdf_fixed_input::<Sha256Params>(&params, &KEY, &fixed, &mut out)?;
+        assert_eq!(out, hex!("10621342bfb0fd40046c0e29f2cfdbf0"));
+        Ok(())
+    }
+
+    // The remaining tests reuse the CAVP KI with a shortened FixedInputData. The counter mode
+    // outputs were cross-checked with pyca/cryptography's KBKDFHMAC and the feedback mode outputs
+    // with OpenSSL's KBKDF, except where noted.
+    #[test]
+    fn test_counter() -> Result<()> {
+        let params = KbkdfParams::new(KbkdfMode::Counter, 32, KbkdfCounterLocation::BeforeFixed)?;
+        let mut out = [0u8; 32];
+        kbkdf_fixed_input::<Sha256Params>(&params, &KEY, &FIXED, &mut out)?;
+        assert_eq!(
+            out,
+            hex!("33117a8af9598c5b3392ebc768d12e3306623536a5ad144863833a45b93be51b")
+        );
+
+        let mut out = [0u8; 100];
+        kbkdf_fixed_input::<Sha384Params>(&params, &KEY, &FIXED, &mut out)?;
+        assert_eq!(out, hex!("b11185b5358cb15029e5433f50e4ae7ff49e82dd4464d06a9208f47505b283a6a16e7b067be3ca35be6a2e3248c7f3815add79bab390cc4cce1242a3454143496ef3e1a1a5c4276d5dced074bf9f3d666cd1e324e88fa2f519f00c88fe71215b32dc9e07"));
+
+        let params = KbkdfParams::new(KbkdfMode::Counter, 8, KbkdfCounterLocation::AfterFixed)?;
+        let mut out = [0u8; 40];
+        kbkdf_fixed_input::<Sha256Params>(&params, &KEY, &FIXED, &mut out)?;
+        assert_eq!(
+            out,
+            hex!(
+                "5d9e386ed64b148b263117fe27c7448bfcc49a33f17f8145bb3251d6758bd22e001595a045f4964e"
+            )
+        );
+
+        let params =
+            KbkdfParams::new(KbkdfMode::Counter, 16, KbkdfCounterLocation::MiddleFixed(5))?;
+        let mut out = [0u8; 64];
+        kbkdf_fixed_input::<Sha256Params>(&params, &KEY, &FIXED, &mut out)?;
+        assert_eq!(out, hex!("e6d767e6ec3782746d1cafb306fcb3a51de75512079b9493e398ffea948d55af86b25428da05a841a3a3f2194f4760da4996b9b5d47911755e582f330e08a8c0"));
+
+        let params = KbkdfParams::new(KbkdfMode::Counter, 24, KbkdfCounterLocation::BeforeFixed)?;
+        let mut out = [0u8; 80];
+        kbkdf_fixed_input::<Sha512Params>(&params, &KEY, &FIXED, &mut out)?;
+        assert_eq!(out, hex!("9a67d4597491718bf1441aac6e4a3b48ae0b50419aa34921452224ad4e53e2d039f4134880eec8258eff5f932c80871f883a2b63fe65e76151403ecd79c2f53ecbf323b13166cdc727ef911ff7f728fe"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_label_context() -> Result<()> {
+        let params = KbkdfParams::new(KbkdfMode::Counter, 32, KbkdfCounterLocation::BeforeFixed)?;
+        let mut out = [0u8; 42];
+        kbkdf::<Sha256Params>(&params, &KEY, b"label", b"context", &mut out)?;
+        assert_eq!(out, hex!("108823e7244030923f68ddabcfb72e2d07313e565c7832cd38fa8a996dccdd736d5814653405e2ea9b1a"));
+
+        let params = KbkdfParams::new(
+            KbkdfMode::DoublePipeline,
+            32,
+            KbkdfCounterLocation::AfterFixed,
+        )?
+        .with_length_bits(16);
+        let mut out = [0u8; 32];
+        kbkdf::<Sha256Params>(&params, &KEY, b"label", b"context", &mut out)?;
+        assert_eq!(
+            out,
+            hex!("a16086bf0f7cb65cd9900418b0bdf0cc4528a540b3e192b579a7b8834e3e629a")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_feedback() -> Result<()> {
+        let mut out = [0u8; 48];
+
+        let iv: Vec<u8> = (0u8..32).collect();
+        let params = KbkdfParams::new(KbkdfMode::Feedback, 32, KbkdfCounterLocation::BeforeFixed)?
+            .with_iv(&iv);
+        kbkdf_fixed_input::<Sha256Params>(&params, &KEY, &FIXED, &mut out)?;
+        assert_eq!(out, hex!("7d43b071285123de66b65d6f0c828635b3c56f5f0e5261ddaf02441deb8d72809d2ed1ca943e2f1bba3239ee8ee04ddc"));
+
+        let params = KbkdfParams::new(KbkdfMode::Feedback, 32, KbkdfCounterLocation::BeforeFixed)?;
+        kbkdf_fixed_input::<Sha256Params>(&params, &KEY, &FIXED, &mut out)?;
+        assert_eq!(out, hex!("33117a8af9598c5b3392ebc768d12e3306623536a5ad144863833a45b93be51b21374fd644b467ce240e4ae856532644"));
+
+        let mut out = [0u8; 100];
+        let params = KbkdfParams::new(KbkdfMode::Feedback, 8, KbkdfCounterLocation::BeforeFixed)?;
+        kbkdf_fixed_input::<Sha384Params>(&params, &KEY, &FIXED, &mut out)?;
+        assert_eq!(out, hex!("dfb87707e8b58d8b8cba7373c8c6e69f1e565d8344deb945f3388dddad0acb7fc60be4c4512e3c9dda2a6549da4e8de305a80a083c398ceaedd8526cc37313ce6a9e8013473f204d0694f29dd5a5731d183079c696e76c7a6af94d59a1745ebf14f3c4e2"));
+
+        let iv: Vec<u8> = (0u8..64).collect();
+        let params = KbkdfParams::new(KbkdfMode::Feedback, 16, KbkdfCounterLocation::BeforeFixed)?
+            .with_iv(&iv);
+        kbkdf_fixed_input::<Sha512Params>(&params, &KEY, &FIXED, &mut out)?;
+        assert_eq!(out, hex!("2f2b13d05bb4229ccf5b5d80b81575d05886359749687af647d3d1a08f22054d01d95bdf29de68d2f85f22ad710496e85490ad1d34ed8d53b24551da0cc7b2bccdf4aef161c99c7d26fc91437e88f36d9813d61db48377ad2dc7fd1b4b570e817dd15798"));
+        Ok(())
+    }
+
+    // OpenSSL has no counterless or counter-first feedback mode, so these are regression values
+    #[test]
+    fn test_feedback_counter_placement() -> Result<()> {
+        let iv: Vec<u8> = (0u8..16).collect();
+        let mut out = [0u8; 48];
+
+        let params = KbkdfParams::new(KbkdfMode::Feedback, 0, KbkdfCounterLocation::BeforeFixed)?;
+        kbkdf_fixed_input::<Sha256Params>(&params, &KEY, &FIXED, &mut out)?;
+        assert_eq!(out, hex!("c9a21c4ff0e9bf362a5e146a5d1aa5dae535781c9e20a17042f936b6835813e8bff0e5e7f0cb9966520d7adabcbe0eee"));
+
+        let params = KbkdfParams::new(
+            KbkdfMode::Feedback,
+            8,
+            KbkdfCounterLocation::BeforeIteration,
+        )?
+        .with_iv(&iv);
+        kbkdf_fixed_input::<Sha256Params>(&params, &KEY, &FIXED, &mut out)?;
+        assert_eq!(out, hex!("2d11cb5c85f8178b7f3eacb80701d2ce644b8025ebed33f988cd5d178488d1de378b342a4ce05584a291c27649f8775b"));
+        Ok(())
+    }
+
+    // neither pyca/cryptography nor OpenSSL implements double-pipeline mode, so these and the
+    // double-pipeline case in test_label_context are regression values
+    #[test]
+    fn test_double_pipeline() -> Result<()> {
+        let mut out = [0u8; 80];
+
+        let params = KbkdfParams::new(
+            KbkdfMode::DoublePipeline,
+            32,
+            KbkdfCounterLocation::BeforeFixed,
+        )?;
+        kbkdf_fixed_input::<Sha256Params>(&params, &KEY, &FIXED, &mut out)?;
+        assert_eq!(out, hex!("cb41f6df42c5aa9a27b0c5f553c1b2d3e217159cf707026e45d86e689bc420bba539bb6adca6a7fd28b1eff9e34c6bc5ebf9ae13a2ebc2d0ff82ef361ddb05a076e6d8df53bde02877798afc87a1087f"));
+
+        let params = KbkdfParams::new(
+            KbkdfMode::DoublePipeline,
+            0,
+            KbkdfCounterLocation::BeforeFixed,
+        )?;
+        kbkdf_fixed_input::<Sha512Params>(&params, &KEY, &FIXED, &mut out)?;
+        assert_eq!(out, hex!("48aabc6b1115f0e3d5e74de702d9a241679efff6db383ac2a5408544cdaaaf9428f2d46d89568b7ea66ca7e418f414a2a8f59645ef8f0b17a01f5596fb31682b2851b43c872785d532ba707bbea58683"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_params() -> Result<()> {
+        assert!(
+            KbkdfParams::new(KbkdfMode::Counter, 0, KbkdfCounterLocation::BeforeFixed).is_err()
+        );
+        assert!(
+            KbkdfParams::new(KbkdfMode::Counter, 12, KbkdfCounterLocation::BeforeFixed).is_err()
+        );
+        assert!(
+            KbkdfParams::new(KbkdfMode::Counter, 8, KbkdfCounterLocation::BeforeIteration).is_err()
+        );
+        assert!(
+            KbkdfParams::new(KbkdfMode::Feedback, 8, KbkdfCounterLocation::MiddleFixed(1)).is_err()
+        );
+
+        // an 8-bit counter covers at most 255 blocks
+        let params = KbkdfParams::new(KbkdfMode::Counter, 8, KbkdfCounterLocation::BeforeFixed)?;
+        let mut out = vec![0u8; 256 * 32];
+        assert!(kbkdf_fixed_input::<Sha256Params>(&params, &KEY, &FIXED, &mut out).is_err());
+
+        // L = 8 * 32 doesn't fit in 8 bits
+        let params = params.with_length_bits(8);
+        let mut out = [0u8; 32];
+        assert!(kbkdf::<Sha256Params>(&params, &KEY, b"label", b"", &mut out).is_err());
+
+        let params =
+            KbkdfParams::new(KbkdfMode::Counter, 8, KbkdfCounterLocation::MiddleFixed(17))?;
+        assert!(kbkdf_fixed_input::<Sha256Params>(&params, &KEY, &FIXED, &mut out).is_err());
+        Ok(())
+    }
+}
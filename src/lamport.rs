@@ -0,0 +1,311 @@
+use std::marker::PhantomData;
+
+use crate::context::Sha2Context;
+use crate::error::{Error, Result, Sha2Corrupted};
+use crate::hmac::Hmac;
+use crate::params::Sha2Params;
+use crate::wipe::wipe_slice;
+
+/// A Lamport one-time signing key over the hash function `P`.
+///
+/// The key signs the `8 * HashLen` bits of `H(msg)`, revealing one of two secret values per bit.
+/// The secret values are derived from a seed as `HMAC(seed, i || b)` with `i` the bit index as a
+/// 16-bit big-endian integer and `b` the bit value, so storing the seed is enough.
+///
+/// Signing twice with the same key lets anyone forge signatures, so [`LamportPrivateKey::sign`]
+/// marks the key as used and refuses a second request. The flag is part of the serialized key:
+/// persist the key after signing, before releasing the signature.
+pub struct LamportPrivateKey<P: Sha2Params> {
+    seed: Vec<u8>,
+    used: bool,
+    _params: PhantomData<P>,
+}
+
+/// The public half of a Lamport key pair: `H(sk[i][b])` for every bit position and value.
+pub struct LamportPublicKey<P: Sha2Params> {
+    hashes: Vec<u8>,
+    _params: PhantomData<P>,
+}
+
+impl<P: Sha2Params> LamportPrivateKey<P> {
+    /// Derives the key from `seed`, which must be at least `HashLen` bytes of secret randomness.
+    pub fn from_seed(seed: &[u8]) -> Result<Self> {
+        if seed.len() < P::HASH_LEN_BYTES {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        Ok(LamportPrivateKey {
+            seed: seed.to_vec(),
+            used: false,
+            _params: PhantomData,
+        })
+    }
+
+    /// Whether the key has already produced its signature.
+    pub fn is_used(&self) -> bool {
+        self.used
+    }
+
+    pub fn public_key(&self) -> Result<LamportPublicKey<P>> {
+        let n = P::HASH_LEN_BYTES;
+        let mut hashes = vec![0u8; 2 * 8 * n * n];
+        let mut secret = P::new_digest();
+
+        let res = hashes.chunks_mut(n).enumerate().try_for_each(|(j, out)| {
+            self.secret(j / 2, (j % 2) as u8, &mut secret)?;
+            hash::<P>(secret.as_ref(), out)
+        });
+
+        wipe_slice(secret.as_mut(), 0);
+        res?;
+
+        Ok(LamportPublicKey {
+            hashes,
+            _params: PhantomData,
+        })
+    }
+
+    /// Signs `msg`, returning [`LamportPublicKey::SIGNATURE_LEN`] bytes. Fails with a
+    /// `StateError` if the key has been used before.
+    pub fn sign(&mut self, msg: &[u8]) -> Result<Vec<u8>> {
+        if self.used {
+            return Err(Error(Sha2Corrupted::StateError));
+        }
+        self.used = true;
+
+        let n = P::HASH_LEN_BYTES;
+        let mut digest = P::new_digest();
+        hash::<P>(msg, digest.as_mut())?;
+
+        let mut signature = vec![0u8; 8 * n * n];
+        let mut secret = P::new_digest();
+
+        let res = signature
+            .chunks_mut(n)
+            .enumerate()
+            .try_for_each(|(i, out)| {
+                self.secret(i, bit(digest.as_ref(), i), &mut secret)?;
+                out.copy_from_slice(secret.as_ref());
+                Ok(())
+            });
+
+        wipe_slice(secret.as_mut(), 0);
+        res?;
+        Ok(signature)
+    }
+
+    /// Serializes the key as one byte for the used flag followed by the seed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(1 + self.seed.len());
+        out.push(self.used as u8);
+        out.extend_from_slice(&self.seed);
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let used = match bytes.first() {
+            Some(0) => false,
+            Some(1) => true,
+            _ => return Err(Error(Sha2Corrupted::BadParam)),
+        };
+
+        let mut key = Self::from_seed(&bytes[1..])?;
+        key.used = used;
+        Ok(key)
+    }
+
+    fn secret(&self, i: usize, b: u8, out: &mut P::Digest) -> Result<()> {
+        let mut hmac: Hmac<P> = Hmac::new(&self.seed)?;
+        hmac.input(&(i as u16).to_be_bytes())?;
+        hmac.input(&[b])?;
+        hmac.result(out)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<P: Sha2Params> Drop for LamportPrivateKey<P> {
+    fn drop(&mut self) {
+        wipe_slice(&mut self.seed, 0);
+    }
+}
+
+impl<P: Sha2Params> LamportPublicKey<P> {
+    /// Length of a serialized public key: two hashes per message bit.
+    pub const LEN: usize = 2 * 8 * P::HASH_LEN_BYTES * P::HASH_LEN_BYTES;
+
+    /// Length of a signature: one secret value per message bit.
+    pub const SIGNATURE_LEN: usize = 8 * P::HASH_LEN_BYTES * P::HASH_LEN_BYTES;
+
+    pub fn verify(&self, msg: &[u8], signature: &[u8]) -> Result<bool> {
+        let n = P::HASH_LEN_BYTES;
+
+        if signature.len() != Self::SIGNATURE_LEN {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        let mut digest = P::new_digest();
+        hash::<P>(msg, digest.as_mut())?;
+
+        let mut revealed = P::new_digest();
+        for (i, secret) in signature.chunks(n).enumerate() {
+            hash::<P>(secret, revealed.as_mut())?;
+
+            let j = 2 * i + bit(digest.as_ref(), i) as usize;
+            if revealed.as_ref() != &self.hashes[j * n..(j + 1) * n] {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// The hashes for bit 0 and bit 1 of each message bit position in turn, [`Self::LEN`] bytes.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.hashes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != Self::LEN {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        Ok(LamportPublicKey {
+            hashes: bytes.to_vec(),
+            _params: PhantomData,
+        })
+    }
+}
+
+impl<P: Sha2Params> Clone for LamportPublicKey<P> {
+    fn clone(&self) -> Self {
+        LamportPublicKey {
+            hashes: self.hashes.clone(),
+            _params: PhantomData,
+        }
+    }
+}
+
+impl<P: Sha2Params> PartialEq for LamportPublicKey<P> {
+    fn eq(&self, other: &Self) -> bool {
+        self.hashes == other.hashes
+    }
+}
+
+impl<P: Sha2Params> Eq for LamportPublicKey<P> {}
+
+fn hash<P: Sha2Params>(data: &[u8], out: &mut [u8]) -> Result<()> {
+    let mut ctx: Sha2Context<P> = Sha2Context::new();
+    let mut digest = P::new_digest();
+    ctx.input(data)?;
+    ctx.result(&mut digest)?;
+    out.copy_from_slice(digest.as_ref());
+    Ok(())
+}
+
+/// Bit `i` of `digest`, most significant bit first.
+fn bit(digest: &[u8], i: usize) -> u8 {
+    (digest[i / 8] >> (7 - i % 8)) & 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LamportPrivateKey, LamportPublicKey};
+    use crate::error::Result;
+    use crate::params::{Sha256Params, Sha512Params};
+    use crate::test_util::sha256;
+
+    use hex_literal::hex;
+
+    fn seed(len: u8) -> Vec<u8> {
+        (0..len).collect()
+    }
+
+    // Lamport signatures have no published test vectors, so these are regression values,
+    // given as SHA-256 of the key and signature to keep them short
+    #[test]
+    fn test_sha256() -> Result<()> {
+        let mut sk: LamportPrivateKey<Sha256Params> = LamportPrivateKey::from_seed(&seed(32))?;
+        let pk = sk.public_key()?;
+        assert_eq!(
+            sha256(pk.as_bytes())?,
+            hex!("21ab4a4a1aa46b1fd8504f07cad1339f47633001bab02d951edb998cb1aeefb3")
+        );
+
+        let signature = sk.sign(b"abc")?;
+        assert_eq!(
+            signature.len(),
+            LamportPublicKey::<Sha256Params>::SIGNATURE_LEN
+        );
+        assert_eq!(
+            signature[..32],
+            hex!("472a557f71f01e8d125da03ec21f6b770f62b36f2d7938df3fb55ab396079516")
+        );
+        assert_eq!(
+            sha256(&signature)?,
+            hex!("bb4689432d0aea2216c428360f6edd15a3f4f569940feee4024efec52d962a6e")
+        );
+
+        assert!(pk.verify(b"abc", &signature)?);
+        assert!(!pk.verify(b"abd", &signature)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sha512() -> Result<()> {
+        let mut sk: LamportPrivateKey<Sha512Params> = LamportPrivateKey::from_seed(&seed(64))?;
+        let pk = sk.public_key()?;
+        assert_eq!(
+            sha256(pk.as_bytes())?,
+            hex!("b0b0c93051f8182964e7c85a3ef2efd6792c2477d6c237c481c2472991b93607")
+        );
+
+        let signature = sk.sign(b"abc")?;
+        assert_eq!(
+            sha256(&signature)?,
+            hex!("34144e0692a6d4b0ef84c8160734a4626e1966ebc2d86411642a1cd911af4414")
+        );
+        assert!(pk.verify(b"abc", &signature)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sign_once() -> Result<()> {
+        let mut sk: LamportPrivateKey<Sha256Params> = LamportPrivateKey::from_seed(&seed(32))?;
+        assert!(!sk.is_used());
+        sk.sign(b"first")?;
+        assert!(sk.is_used());
+        assert!(sk.sign(b"second").is_err());
+
+        // the flag survives serialization
+        let mut restored: LamportPrivateKey<Sha256Params> =
+            LamportPrivateKey::from_bytes(&sk.to_bytes())?;
+        assert!(restored.sign(b"second").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialization() -> Result<()> {
+        let sk: LamportPrivateKey<Sha256Params> = LamportPrivateKey::from_seed(&seed(40))?;
+        let bytes = sk.to_bytes();
+        assert_eq!(bytes[0], 0);
+        assert_eq!(bytes[1..], seed(40));
+
+        let mut restored: LamportPrivateKey<Sha256Params> = LamportPrivateKey::from_bytes(&bytes)?;
+        let pk = LamportPublicKey::<Sha256Params>::from_bytes(sk.public_key()?.as_bytes())?;
+        assert!(pk == restored.public_key()?);
+
+        let mut signature = restored.sign(b"msg")?;
+        assert!(pk.verify(b"msg", &signature)?);
+        signature[100] ^= 1;
+        assert!(!pk.verify(b"msg", &signature)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_params() {
+        assert!(LamportPrivateKey::<Sha256Params>::from_seed(&seed(31)).is_err());
+        assert!(LamportPrivateKey::<Sha256Params>::from_bytes(&[]).is_err());
+        assert!(LamportPrivateKey::<Sha256Params>::from_bytes(&[2; 33]).is_err());
+        assert!(LamportPublicKey::<Sha256Params>::from_bytes(&[0; 100]).is_err());
+    }
+}
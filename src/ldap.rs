@@ -0,0 +1,181 @@
+use crate::algorithm::Sha2Algorithm;
+use crate::any::AnySha2;
+use crate::base64::STANDARD;
+use crate::error::{Error, Result, Sha2Corrupted};
+use crate::ops::ct_eq;
+use crate::wipe::wipe_slice;
+
+/// An LDAP `userPassword` value using one of the `{SHA256}`, `{SSHA256}`, `{SHA384}`,
+/// `{SSHA384}`, `{SHA512}` or `{SSHA512}` schemes.
+///
+/// The unsalted schemes store `base64(H(password))`, the salted ones
+/// `base64(H(password || salt) || salt)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LdapPassword {
+    pub algorithm: Sha2Algorithm,
+    pub salt: Option<Vec<u8>>,
+    pub digest: Vec<u8>,
+}
+
+impl LdapPassword {
+    /// Hashes `password`, using the salted scheme if `salt` is given.
+    pub fn encode(algorithm: Sha2Algorithm, password: &[u8], salt: Option<&[u8]>) -> Result<Self> {
+        Self::scheme_name(algorithm, false)?;
+
+        if salt.is_some_and(|salt| salt.is_empty()) {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        Ok(LdapPassword {
+            algorithm,
+            salt: salt.map(|salt| salt.to_vec()),
+            digest: Self::hash(algorithm, password, salt)?,
+        })
+    }
+
+    /// Compares the digest of `password` against the stored one in constant time.
+    pub fn verify(&self, password: &[u8]) -> Result<bool> {
+        let mut computed = Self::hash(self.algorithm, password, self.salt.as_deref())?;
+        let matches = ct_eq(&computed, &self.digest);
+        wipe_slice(&mut computed, 0);
+        Ok(matches)
+    }
+
+    fn hash(algorithm: Sha2Algorithm, password: &[u8], salt: Option<&[u8]>) -> Result<Vec<u8>> {
+        let mut ctx = AnySha2::new(algorithm);
+        ctx.input(password)?;
+        ctx.input(salt.unwrap_or_default())?;
+        ctx.result_vec()
+    }
+
+    fn scheme_name(algorithm: Sha2Algorithm, salted: bool) -> Result<&'static str> {
+        match (algorithm, salted) {
+            (Sha2Algorithm::Sha256, false) => Ok("SHA256"),
+            (Sha2Algorithm::Sha256, true) => Ok("SSHA256"),
+            (Sha2Algorithm::Sha384, false) => Ok("SHA384"),
+            (Sha2Algorithm::Sha384, true) => Ok("SSHA384"),
+            (Sha2Algorithm::Sha512, false) => Ok("SHA512"),
+            (Sha2Algorithm::Sha512, true) => Ok("SSHA512"),
+            _ => Err(Error(Sha2Corrupted::BadParam)),
+        }
+    }
+}
+
+impl std::str::FromStr for LdapPassword {
+    type Err = Error;
+
+    /// Parses a `{SCHEME}base64` value. Scheme names are case-insensitive.
+    fn from_str(s: &str) -> Result<Self> {
+        let (scheme, encoded) = s
+            .strip_prefix('{')
+            .and_then(|s| s.split_once('}'))
+            .ok_or(Error(Sha2Corrupted::BadParam))?;
+
+        let scheme = scheme.to_ascii_uppercase();
+        let (salted, bits) = match scheme.strip_prefix("SSHA") {
+            Some(bits) => (true, bits),
+            None => (
+                false,
+                scheme
+                    .strip_prefix("SHA")
+                    .ok_or(Error(Sha2Corrupted::BadParam))?,
+            ),
+        };
+
+        let algorithm = match bits {
+            "256" => Sha2Algorithm::Sha256,
+            "384" => Sha2Algorithm::Sha384,
+            "512" => Sha2Algorithm::Sha512,
+            _ => return Err(Error(Sha2Corrupted::BadParam)),
+        };
+
+        let mut decoded = STANDARD.decode(encoded.trim())?;
+        let digest_len = algorithm.digest_len();
+
+        // the salt is whatever follows the digest
+        let salt = match (salted, decoded.len().cmp(&digest_len)) {
+            (false, std::cmp::Ordering::Equal) => None,
+            (true, std::cmp::Ordering::Greater) => Some(decoded.split_off(digest_len)),
+            _ => return Err(Error(Sha2Corrupted::BadParam)),
+        };
+
+        Ok(LdapPassword {
+            algorithm,
+            salt,
+            digest: decoded,
+        })
+    }
+}
+
+impl std::fmt::Display for LdapPassword {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let scheme =
+            Self::scheme_name(self.algorithm, self.salt.is_some()).map_err(|_| std::fmt::Error)?;
+
+        let mut value = self.digest.clone();
+        value.extend_from_slice(self.salt.as_deref().unwrap_or_default());
+
+        write!(f, "{{{scheme}}}{}", STANDARD.encode(&value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LdapPassword;
+    use crate::algorithm::Sha2Algorithm;
+    use crate::error::Result;
+
+    #[test]
+    fn test_unsalted() -> Result<()> {
+        let s = "{SHA256}K7gNU3sdo+OL0wNhqoVWhr3g6s1xYv72ol/pe/Unols=";
+        let password: LdapPassword = s.parse()?;
+
+        assert_eq!(password.algorithm, Sha2Algorithm::Sha256);
+        assert!(password.salt.is_none());
+        assert!(password.verify(b"secret")?);
+        assert!(!password.verify(b"Secret")?);
+        assert_eq!(password.to_string(), s);
+
+        let encoded = LdapPassword::encode(Sha2Algorithm::Sha512, b"secret", None)?;
+        assert_eq!(encoded.to_string(), "{SHA512}vSsar3708Jvp9Szi2NWZZ02Bqp1qRCFpbcTZPdBhnWgs5WtNZKnvCXdhztmeD2cmW192CF5bDufKRpayrW/isg==");
+        Ok(())
+    }
+
+    #[test]
+    fn test_salted() -> Result<()> {
+        let s = "{SSHA256}oBmrdHcA6OZEkkCLeXh71YAerbvhXz1qqwjrPsXmEtNzYWx0c2FsdA==";
+        let password: LdapPassword = s.parse()?;
+
+        assert_eq!(password.salt.as_deref(), Some(&b"saltsalt"[..]));
+        assert!(password.verify(b"secret")?);
+        assert_eq!(password.to_string(), s);
+
+        let password: LdapPassword = "{ssha512}MKbQg3rPvz03V+1S0+/jlDdn0B0IiGGxl7kZMimdo1IHHWN6DtzxJMCchsX5U1lrRY7apW/oopOgERexYda1nAECAwQ=".parse()?;
+        assert_eq!(password.salt.as_deref(), Some(&[1u8, 2, 3, 4][..]));
+        assert!(password.verify(b"secret")?);
+
+        let encoded = LdapPassword::encode(Sha2Algorithm::Sha256, b"secret", Some(b"saltsalt"))?;
+        assert_eq!(encoded.to_string(), s);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!("{MD5}X03MO1qnZdYdgyfeuILPmQ=="
+            .parse::<LdapPassword>()
+            .is_err());
+        assert!("SHA256}K7gNU3sdo+OL0wNhqoVWhr3g6s1xYv72ol/pe/Unols="
+            .parse::<LdapPassword>()
+            .is_err());
+        // an unsalted digest can't be parsed as salted and vice versa
+        assert!("{SSHA256}K7gNU3sdo+OL0wNhqoVWhr3g6s1xYv72ol/pe/Unols="
+            .parse::<LdapPassword>()
+            .is_err());
+        assert!(
+            "{SHA256}oBmrdHcA6OZEkkCLeXh71YAerbvhXz1qqwjrPsXmEtNzYWx0c2FsdA=="
+                .parse::<LdapPassword>()
+                .is_err()
+        );
+        assert!(LdapPassword::encode(Sha2Algorithm::Sha224, b"secret", None).is_err());
+    }
+}
@@ -0,0 +1,544 @@
+use std::marker::PhantomData;
+
+use crate::context::Sha2Context;
+use crate::error::{Error, Result, Sha2Corrupted};
+use crate::params::Sha2Params;
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// An append-only Merkle tree as used by Certificate Transparency (RFC 9162, section 2.1).
+///
+/// Leaves are hashed as `HASH(0x00 || entry)` and interior nodes as `HASH(0x01 || left ||
+/// right)`, and a tree of `n` leaves splits at the largest power of two smaller than `n`. The
+/// tree keeps the leaf hashes only, so tree heads and proofs are recomputed from them.
+pub struct MerkleTree<P: Sha2Params> {
+    leaf_hashes: Vec<Vec<u8>>,
+    _params: PhantomData<P>,
+}
+
+impl<P: Sha2Params> MerkleTree<P> {
+    pub fn new() -> Self {
+        MerkleTree {
+            leaf_hashes: Vec::new(),
+            _params: PhantomData,
+        }
+    }
+
+    /// Appends `entry`, returning its leaf index.
+    pub fn push(&mut self, entry: &[u8]) -> Result<usize> {
+        self.leaf_hashes.push(merkle_leaf_hash::<P>(entry)?);
+        Ok(self.leaf_hashes.len() - 1)
+    }
+
+    pub fn len(&self) -> usize {
+        self.leaf_hashes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaf_hashes.is_empty()
+    }
+
+    pub fn leaf_hash(&self, index: usize) -> Option<&[u8]> {
+        self.leaf_hashes.get(index).map(Vec::as_slice)
+    }
+
+    /// The tree head of all entries.
+    pub fn root(&self) -> Result<Vec<u8>> {
+        self.root_at(self.len())
+    }
+
+    /// The tree head of the first `size` entries, `MTH(D[0:size])`. The empty tree's head is
+    /// the hash of the empty string.
+    pub fn root_at(&self, size: usize) -> Result<Vec<u8>> {
+        if size > self.len() {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        if size == 0 {
+            return hash::<P>(&[]);
+        }
+        self.subtree_root(0, size)
+    }
+
+    /// The audit path of leaf `index` in the tree of the first `size` entries, `PATH(index,
+    /// D[0:size])`, from the leaf upwards.
+    pub fn inclusion_proof(&self, index: usize, size: usize) -> Result<Vec<Vec<u8>>> {
+        if index >= size || size > self.len() {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        let mut proof = Vec::new();
+        self.path(index, 0, size, &mut proof)?;
+        Ok(proof)
+    }
+
+    /// The proof that the tree of the first `new_size` entries extends that of the first
+    /// `old_size`, `PROOF(old_size, D[0:new_size])`. It is empty when the sizes are equal.
+    pub fn consistency_proof(&self, old_size: usize, new_size: usize) -> Result<Vec<Vec<u8>>> {
+        if old_size == 0 || old_size > new_size || new_size > self.len() {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        let mut proof = Vec::new();
+        self.subproof(old_size, 0, new_size, true, &mut proof)?;
+        Ok(proof)
+    }
+
+    /// `MTH(D[start:end])` for a non-empty range.
+    fn subtree_root(&self, start: usize, end: usize) -> Result<Vec<u8>> {
+        if end - start == 1 {
+            return Ok(self.leaf_hashes[start].clone());
+        }
+
+        let mid = start + split(end - start);
+        node_hash::<P>(
+            &self.subtree_root(start, mid)?,
+            &self.subtree_root(mid, end)?,
+        )
+    }
+
+    /// `PATH(m, D[start:end])` with `m` relative to the whole tree.
+    fn path(&self, m: usize, start: usize, end: usize, proof: &mut Vec<Vec<u8>>) -> Result<()> {
+        if end - start == 1 {
+            return Ok(());
+        }
+
+        let mid = start + split(end - start);
+        if m < mid {
+            self.path(m, start, mid, proof)?;
+            proof.push(self.subtree_root(mid, end)?);
+        } else {
+            self.path(m, mid, end, proof)?;
+            proof.push(self.subtree_root(start, mid)?);
+        }
+
+        Ok(())
+    }
+
+    /// `SUBPROOF(m, D[start:end], complete)` with `m` relative to the whole tree.
+    fn subproof(
+        &self,
+        m: usize,
+        start: usize,
+        end: usize,
+        complete: bool,
+        proof: &mut Vec<Vec<u8>>,
+    ) -> Result<()> {
+        if m == end {
+            if !complete {
+                proof.push(self.subtree_root(start, end)?);
+            }
+            return Ok(());
+        }
+
+        let mid = start + split(end - start);
+        if m <= mid {
+            self.subproof(m, start, mid, complete, proof)?;
+            proof.push(self.subtree_root(mid, end)?);
+        } else {
+            self.subproof(m, mid, end, false, proof)?;
+            proof.push(self.subtree_root(start, mid)?);
+        }
+
+        Ok(())
+    }
+}
+
+impl<P: Sha2Params> Default for MerkleTree<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The leaf hash `HASH(0x00 || entry)`.
+pub fn merkle_leaf_hash<P: Sha2Params>(entry: &[u8]) -> Result<Vec<u8>> {
+    hash::<P>(&[&[LEAF_PREFIX], entry])
+}
+
+/// Verifies that `leaf_hash` is leaf `index` of the tree of `size` entries with head `root`
+/// (RFC 9162, section 2.1.3.2). Proofs that don't fit the sizes are reported as `Ok(false)`.
+pub fn merkle_verify_inclusion<P: Sha2Params>(
+    leaf_hash: &[u8],
+    index: u64,
+    size: u64,
+    proof: &[Vec<u8>],
+    root: &[u8],
+) -> Result<bool> {
+    if index >= size {
+        return Ok(false);
+    }
+
+    let (mut f_n, mut s_n) = (index, size - 1);
+    let mut r = leaf_hash.to_vec();
+
+    for p in proof {
+        if s_n == 0 {
+            return Ok(false);
+        }
+
+        if f_n & 1 == 1 || f_n == s_n {
+            r = node_hash::<P>(p, &r)?;
+            while f_n & 1 == 0 && f_n != 0 {
+                f_n >>= 1;
+                s_n >>= 1;
+            }
+        } else {
+            r = node_hash::<P>(&r, p)?;
+        }
+
+        f_n >>= 1;
+        s_n >>= 1;
+    }
+
+    Ok(s_n == 0 && r == root)
+}
+
+/// Verifies that the tree of `new_size` entries with head `new_root` extends the tree of
+/// `old_size` entries with head `old_root` (RFC 9162, section 2.1.4.2). Proofs that don't fit
+/// the sizes are reported as `Ok(false)`.
+pub fn merkle_verify_consistency<P: Sha2Params>(
+    old_size: u64,
+    new_size: u64,
+    old_root: &[u8],
+    new_root: &[u8],
+    proof: &[Vec<u8>],
+) -> Result<bool> {
+    if old_size == 0 || old_size > new_size {
+        return Ok(false);
+    }
+    if old_size == new_size {
+        return Ok(proof.is_empty() && old_root == new_root);
+    }
+
+    // when the old tree is a complete subtree, its head is the first node of the proof
+    let mut path = proof.iter().map(Vec::as_slice);
+    let first = if old_size.is_power_of_two() {
+        Some(old_root)
+    } else {
+        path.next()
+    };
+    let Some(first) = first else {
+        return Ok(false);
+    };
+
+    let (mut f_n, mut s_n) = (old_size - 1, new_size - 1);
+    while f_n & 1 == 1 {
+        f_n >>= 1;
+        s_n >>= 1;
+    }
+
+    let mut f_r = first.to_vec();
+    let mut s_r = first.to_vec();
+
+    for c in path {
+        if s_n == 0 {
+            return Ok(false);
+        }
+
+        if f_n & 1 == 1 || f_n == s_n {
+            f_r = node_hash::<P>(c, &f_r)?;
+            s_r = node_hash::<P>(c, &s_r)?;
+            while f_n & 1 == 0 && f_n != 0 {
+                f_n >>= 1;
+                s_n >>= 1;
+            }
+        } else {
+            s_r = node_hash::<P>(&s_r, c)?;
+        }
+
+        f_n >>= 1;
+        s_n >>= 1;
+    }
+
+    Ok(s_n == 0 && f_r == old_root && s_r == new_root)
+}
+
+/// The largest power of two smaller than `n`, for `n > 1`.
+fn split(n: usize) -> usize {
+    1 << (n - 1).ilog2()
+}
+
+fn node_hash<P: Sha2Params>(left: &[u8], right: &[u8]) -> Result<Vec<u8>> {
+    hash::<P>(&[&[NODE_PREFIX], left, right])
+}
+
+fn hash<P: Sha2Params>(parts: &[&[u8]]) -> Result<Vec<u8>> {
+    let mut ctx: Sha2Context<P> = Sha2Context::new();
+    for part in parts {
+        ctx.input(part)?;
+    }
+
+    let mut digest = P::new_digest();
+    ctx.result(&mut digest)?;
+    Ok(digest.as_ref().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        merkle_leaf_hash, merkle_verify_consistency, merkle_verify_inclusion, node_hash, MerkleTree,
+    };
+    use crate::error::Result;
+    use crate::params::{Sha256Params, Sha512Params};
+
+    use hex_literal::hex;
+
+    // the test data of the Certificate Transparency reference implementation
+    const ENTRIES: [&[u8]; 8] = [
+        &hex!(""),
+        &hex!("00"),
+        &hex!("10"),
+        &hex!("2021"),
+        &hex!("3031"),
+        &hex!("40414243"),
+        &hex!("5051525354555657"),
+        &hex!("606162636465666768696a6b6c6d6e6f"),
+    ];
+
+    const ROOTS: [[u8; 32]; 9] = [
+        hex!("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+        hex!("6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d"),
+        hex!("fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125"),
+        hex!("aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77"),
+        hex!("d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7"),
+        hex!("4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4"),
+        hex!("76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef"),
+        hex!("ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c"),
+        hex!("5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328"),
+    ];
+
+    fn tree(size: usize) -> Result<MerkleTree<Sha256Params>> {
+        let mut tree = MerkleTree::new();
+        for entry in &ENTRIES[..size] {
+            tree.push(entry)?;
+        }
+        Ok(tree)
+    }
+
+    #[test]
+    fn test_roots() -> Result<()> {
+        let tree = tree(8)?;
+        for (size, root) in ROOTS.iter().enumerate() {
+            assert_eq!(tree.root_at(size)?, root);
+        }
+        assert_eq!(tree.root()?, ROOTS[8]);
+        assert_eq!(tree.leaf_hash(0), Some(&ROOTS[1][..]));
+        assert!(MerkleTree::<Sha256Params>::new().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_inclusion_proof() -> Result<()> {
+        let tree = tree(8)?;
+
+        // from the same test data, which numbers leaves from 1
+        let proof = tree.inclusion_proof(0, 8)?;
+        assert_eq!(
+            proof,
+            [
+                hex!("96a296d224f285c67bee93c30f8a309157f0daa35dc5b87e410b78630a09cfc7"),
+                hex!("5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e"),
+                hex!("6b47aaf29ee3c2af9af889bc1fb9254dabd31177f16232dd6aab035ca39bf6e4"),
+            ]
+        );
+        let leaf = merkle_leaf_hash::<Sha256Params>(ENTRIES[0])?;
+        assert!(merkle_verify_inclusion::<Sha256Params>(
+            &leaf, 0, 8, &proof, &ROOTS[8]
+        )?);
+
+        assert_eq!(
+            tree.inclusion_proof(5, 8)?,
+            [
+                hex!("bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b"),
+                hex!("ca854ea128ed050b41b35ffc1b87b8eb2bde461e9e3b5596ece6b9d5975a0ae0"),
+                hex!("d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7"),
+            ]
+        );
+        assert_eq!(tree.inclusion_proof(2, 3)?, [ROOTS[2]]);
+        assert!(tree.inclusion_proof(0, 1)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_consistency_proof() -> Result<()> {
+        let tree = tree(8)?;
+
+        // from the CT reference test data
+        assert_eq!(
+            tree.consistency_proof(6, 8)?,
+            [
+                hex!("0ebc5d3437fbe2db158b9f126a1d118e308181031d0a949f8dededebc558ef6a"),
+                hex!("ca854ea128ed050b41b35ffc1b87b8eb2bde461e9e3b5596ece6b9d5975a0ae0"),
+                hex!("d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7"),
+            ]
+        );
+        assert_eq!(
+            tree.consistency_proof(2, 5)?,
+            [
+                hex!("5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e"),
+                hex!("bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b"),
+            ]
+        );
+        // a complete subtree needs no node for the old head
+        assert_eq!(tree.consistency_proof(1, 8)?, tree.inclusion_proof(0, 8)?);
+        assert!(tree.consistency_proof(8, 8)?.is_empty());
+        Ok(())
+    }
+
+    // the example tree of RFC 9162, section 2.1.5: seven leaves a b c d e f j, with g = (a, b),
+    // h = (c, d), i = (e, f), k = (g, h) and l = (i, j)
+    #[test]
+    fn test_rfc9162_example() -> Result<()> {
+        let tree = tree(7)?;
+        let leaf = |i: usize| tree.leaf_hash(i).unwrap().to_vec();
+        let [a, b, c, d, e, f, j] = [0, 1, 2, 3, 4, 5, 6].map(leaf);
+        let g = node_hash::<Sha256Params>(&a, &b)?;
+        let h = node_hash::<Sha256Params>(&c, &d)?;
+        let i = node_hash::<Sha256Params>(&e, &f)?;
+        let k = node_hash::<Sha256Params>(&g, &h)?;
+        let l = node_hash::<Sha256Params>(&i, &j)?;
+
+        assert_eq!(tree.root()?, node_hash::<Sha256Params>(&k, &l)?);
+
+        assert_eq!(tree.inclusion_proof(0, 7)?, [b, h.clone(), l.clone()]);
+        assert_eq!(
+            tree.inclusion_proof(3, 7)?,
+            [c.clone(), g.clone(), l.clone()]
+        );
+        assert_eq!(tree.inclusion_proof(4, 7)?, [f, j.clone(), k.clone()]);
+        assert_eq!(tree.inclusion_proof(6, 7)?, [i.clone(), k.clone()]);
+
+        assert_eq!(tree.consistency_proof(3, 7)?, [c, d, g, l.clone()]);
+        assert_eq!(tree.consistency_proof(4, 7)?, [l]);
+        assert_eq!(tree.consistency_proof(6, 7)?, [i, j, k]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_all_sizes() -> Result<()> {
+        let mut tree: MerkleTree<Sha512Params> = MerkleTree::new();
+        for i in 0..40u8 {
+            tree.push(&[i; 3])?;
+        }
+
+        for size in 1..=tree.len() {
+            let root = tree.root_at(size)?;
+
+            for index in 0..size {
+                let proof = tree.inclusion_proof(index, size)?;
+                let leaf = tree.leaf_hash(index).unwrap();
+                let (index, size) = (index as u64, size as u64);
+
+                assert!(merkle_verify_inclusion::<Sha512Params>(
+                    leaf, index, size, &proof, &root
+                )?);
+                // the neighbouring leaf, or the right leaf at the neighbouring index
+                if index ^ 1 < size {
+                    let other = tree.leaf_hash(index as usize ^ 1).unwrap();
+                    assert!(!merkle_verify_inclusion::<Sha512Params>(
+                        other, index, size, &proof, &root
+                    )?);
+                    assert!(!merkle_verify_inclusion::<Sha512Params>(
+                        leaf,
+                        index ^ 1,
+                        size,
+                        &proof,
+                        &root
+                    )?);
+                }
+            }
+
+            for old_size in 1..=size {
+                let old_root = tree.root_at(old_size)?;
+                let proof = tree.consistency_proof(old_size, size)?;
+                let (old_size, size) = (old_size as u64, size as u64);
+
+                assert!(merkle_verify_consistency::<Sha512Params>(
+                    old_size, size, &old_root, &root, &proof
+                )?);
+                if old_size < size {
+                    assert!(!merkle_verify_consistency::<Sha512Params>(
+                        old_size, size, &root, &root, &proof
+                    )?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_tampering() -> Result<()> {
+        let tree = tree(7)?;
+        let root = tree.root()?;
+        let leaf = tree.leaf_hash(3).unwrap().to_vec();
+        let proof = tree.inclusion_proof(3, 7)?;
+
+        for n in 0..proof.len() {
+            let mut bad = proof.clone();
+            bad[n][0] ^= 1;
+            assert!(!merkle_verify_inclusion::<Sha256Params>(
+                &leaf, 3, 7, &bad, &root
+            )?);
+        }
+        assert!(!merkle_verify_inclusion::<Sha256Params>(
+            &leaf, 2, 7, &proof, &root
+        )?);
+        assert!(!merkle_verify_inclusion::<Sha256Params>(
+            &leaf,
+            3,
+            7,
+            &proof[..2],
+            &root
+        )?);
+
+        let old_root = tree.root_at(3)?;
+        let proof = tree.consistency_proof(3, 7)?;
+        for n in 0..proof.len() {
+            let mut bad = proof.clone();
+            bad[n][31] ^= 1;
+            assert!(!merkle_verify_consistency::<Sha256Params>(
+                3, 7, &old_root, &root, &bad
+            )?);
+        }
+        assert!(!merkle_verify_consistency::<Sha256Params>(
+            3,
+            7,
+            &old_root,
+            &root,
+            &[]
+        )?);
+        assert!(!merkle_verify_consistency::<Sha256Params>(
+            4, 7, &old_root, &root, &proof
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_params() -> Result<()> {
+        let tree = tree(4)?;
+        assert!(tree.root_at(5).is_err());
+        assert!(tree.inclusion_proof(4, 4).is_err());
+        assert!(tree.inclusion_proof(0, 5).is_err());
+        assert!(tree.consistency_proof(0, 4).is_err());
+        assert!(tree.consistency_proof(3, 2).is_err());
+        assert!(tree.consistency_proof(2, 5).is_err());
+
+        let root = tree.root()?;
+        assert!(!merkle_verify_inclusion::<Sha256Params>(
+            &root,
+            4,
+            4,
+            &[],
+            &root
+        )?);
+        assert!(!merkle_verify_consistency::<Sha256Params>(
+            0,
+            4,
+            &root,
+            &root,
+            &[]
+        )?);
+        Ok(())
+    }
+}
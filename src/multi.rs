@@ -0,0 +1,136 @@
+use crate::algorithm::Sha2Algorithm;
+use crate::any::AnySha2;
+use crate::error::Result;
+
+use std::collections::BTreeMap;
+use std::io::Read;
+
+const READ_BUFFER_SIZE: usize = 8192;
+
+/// Feeds a single input stream into several SHA-2 contexts at once.
+///
+/// Every chunk passed to [`MultiHasher::input`] (or read by [`MultiHasher::read_from`]) is handed
+/// to each context in turn, so the input only has to be read once no matter how many digests are
+/// computed.
+pub struct MultiHasher {
+    contexts: Vec<AnySha2>,
+}
+
+impl MultiHasher {
+    /// Creates a hasher computing each of the given algorithms. Duplicates are ignored.
+    pub fn new(algorithms: &[Sha2Algorithm]) -> Self {
+        let mut contexts: Vec<AnySha2> = Vec::new();
+
+        for &algorithm in algorithms {
+            if !contexts.iter().any(|ctx| ctx.algorithm() == algorithm) {
+                contexts.push(AnySha2::new(algorithm));
+            }
+        }
+
+        MultiHasher { contexts }
+    }
+
+    pub fn algorithms(&self) -> impl Iterator<Item = Sha2Algorithm> + '_ {
+        self.contexts.iter().map(AnySha2::algorithm)
+    }
+
+    pub fn reset(&mut self) -> Result<()> {
+        for ctx in self.contexts.iter_mut() {
+            ctx.reset()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn input(&mut self, msg_chunk: &[u8]) -> Result<()> {
+        for ctx in self.contexts.iter_mut() {
+            ctx.input(msg_chunk)?;
+        }
+
+        Ok(())
+    }
+
+    /// Reads `reader` to the end through a single shared buffer, feeding every chunk to all
+    /// contexts. Returns the number of bytes read.
+    pub fn read_from<R: Read>(&mut self, mut reader: R) -> std::io::Result<u64> {
+        let mut buf = [0u8; READ_BUFFER_SIZE];
+        let mut total = 0u64;
+
+        loop {
+            let n = match reader.read(&mut buf) {
+                Ok(0) => return Ok(total),
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+
+            self.input(&buf[..n]).map_err(std::io::Error::other)?;
+            total += n as u64;
+        }
+    }
+
+    /// Finishes all contexts and returns the digests keyed by algorithm.
+    pub fn result(&mut self) -> Result<BTreeMap<Sha2Algorithm, Vec<u8>>> {
+        let mut digests = BTreeMap::new();
+
+        for ctx in self.contexts.iter_mut() {
+            digests.insert(ctx.algorithm(), ctx.result_vec()?);
+        }
+
+        Ok(digests)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MultiHasher;
+    use crate::algorithm::Sha2Algorithm;
+    use crate::error::Result;
+
+    use hex_literal::hex;
+
+    #[test]
+    fn test_multi_abc() -> Result<()> {
+        let mut hasher = MultiHasher::new(&[Sha2Algorithm::Sha512, Sha2Algorithm::Sha256]);
+
+        hasher.input(b"a")?;
+        hasher.input(b"bc")?;
+        let digests = hasher.result()?;
+
+        assert_eq!(digests.len(), 2);
+        assert_eq!(
+            digests[&Sha2Algorithm::Sha256],
+            hex!("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        assert_eq!(
+            digests[&Sha2Algorithm::Sha512],
+            hex!("ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_read_from() -> Result<()> {
+        let msg = [0x61u8; 1000];
+        let mut hasher = MultiHasher::new(&[
+            Sha2Algorithm::Sha256,
+            Sha2Algorithm::Sha512,
+            Sha2Algorithm::Sha256,
+        ]);
+
+        let read = hasher.read_from(&msg[..]).unwrap();
+        let digests = hasher.result()?;
+
+        assert_eq!(read, 1000);
+        assert_eq!(digests.len(), 2);
+        assert_eq!(
+            digests[&Sha2Algorithm::Sha256],
+            hex!("41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3")
+        );
+        assert_eq!(
+            digests[&Sha2Algorithm::Sha512],
+            hex!("67ba5535a46e3f86dbfbed8cbbaf0125c76ed549ff8b0b9e03e0c88cf90fa634fa7b12b47d77b694de488ace8d9a65967dc96df599727d3292a8d9d447709c97")
+        );
+        Ok(())
+    }
+}
@@ -8,7 +8,7 @@ impl Rotr<usize> for u32 {
     type Output = u32;
 
     fn rotr(self, by: usize) -> u32 {
-        (self >> by) | (self << (32 - by))
+        self.rotate_right(by as u32)
     }
 }
 
@@ -16,6 +16,20 @@ impl Rotr<usize> for u64 {
     type Output = u64;
 
     fn rotr(self, by: usize) -> u64 {
-        (self >> by) | (self << (64 - by))
+        self.rotate_right(by as u32)
     }
 }
+
+/// Compares two byte strings in time that depends only on their lengths.
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let diff = a
+        .iter()
+        .zip(b.iter())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y));
+
+    std::hint::black_box(diff) == 0
+}
@@ -1,3 +1,4 @@
+use crate::algorithm::Sha2Algorithm;
 use crate::ops::Rotr;
 use std::{fmt::LowerHex, ops::IndexMut};
 
@@ -22,20 +23,23 @@ pub trait Sha2Word:
 pub trait Sha2Params {
     type Word: Sha2Word;
 
+    const ALGORITHM: Sha2Algorithm;
+
     /* I didn't want to use min_const_generics because I wanted to avoid requiring nightly, but
      * doing so would allow us to just use arrays instead of
      * - implementing Index and IndexMut
      * - having a separate constant for the length. */
 
-    type IntermediateHash: AsMut<[Self::Word]>;
+    type IntermediateHash: AsMut<[Self::Word]> + Clone;
     type Constants: AsRef<[Self::Word]>;
-    type Digest;
+    type Digest: AsRef<[u8]> + AsMut<[u8]>;
     const MSG_BLOCK_SIZE: usize;
 
-    type MessageBlock: AsRef<[u8]> + AsMut<[u8]>;
+    type MessageBlock: AsRef<[u8]> + AsMut<[u8]> + Clone;
     type W: IndexMut<usize, Output = Self::Word>;
     fn new_msg_block() -> Self::MessageBlock;
     fn new_w() -> Self::W;
+    fn new_digest() -> Self::Digest;
 
     const HASH_LEN_BYTES: usize;
     const H0: Self::IntermediateHash;
@@ -64,6 +68,8 @@ pub struct Sha256Params;
 impl Sha2Params for Sha256Params {
     type Word = u32;
 
+    const ALGORITHM: Sha2Algorithm = Sha2Algorithm::Sha256;
+
     type IntermediateHash = [u32; 8];
     type Digest = [u8; 32];
     type W = [u32; 64];
@@ -108,6 +114,10 @@ impl Sha2Params for Sha256Params {
         [0u32; 64]
     }
 
+    fn new_digest() -> Self::Digest {
+        [0u8; 32]
+    }
+
     fn write_hash(dst: &mut Self::Digest, ihash: &Self::IntermediateHash) {
         for i in 0..Self::HASH_LEN_BYTES {
             dst[i] = (ihash[i >> 2] >> (8 * (3 - (i & 3)))) as u8;
@@ -131,11 +141,78 @@ impl Sha2Params for Sha256Params {
     }
 }
 
+pub struct Sha224Params;
+
+impl Sha2Params for Sha224Params {
+    type Word = u32;
+
+    const ALGORITHM: Sha2Algorithm = Sha2Algorithm::Sha224;
+
+    type IntermediateHash = [u32; 8];
+    type Digest = [u8; 28];
+    type W = [u32; 64];
+
+    type Constants = [u32; 64];
+
+    type MessageBlock = [u8; 64];
+
+    const W_LEN: usize = 64;
+    const MSG_BLOCK_SIZE: usize = 64;
+    const HASH_LEN_BYTES: usize = 28;
+
+    const H0: Self::IntermediateHash = [
+        0xC1059ED8, 0x367CD507, 0x3070DD17, 0xF70E5939, 0xFFC00B31, 0x68581511, 0x64F98FA7,
+        0xBEFA4FA4,
+    ];
+
+    const K: Self::Constants = Sha256Params::K;
+
+    fn parse_word(src: &[u8]) -> Self::Word {
+        Sha256Params::parse_word(src)
+    }
+
+    fn new_msg_block() -> Self::MessageBlock {
+        [0u8; 64]
+    }
+
+    fn new_w() -> Self::W {
+        [0u32; 64]
+    }
+
+    fn new_digest() -> Self::Digest {
+        [0u8; 28]
+    }
+
+    fn write_hash(dst: &mut Self::Digest, ihash: &Self::IntermediateHash) {
+        for i in 0..Self::HASH_LEN_BYTES {
+            dst[i] = (ihash[i >> 2] >> (8 * (3 - (i & 3)))) as u8;
+        }
+    }
+
+    fn upper_sigma0(word: Self::Word) -> Self::Word {
+        Sha256Params::upper_sigma0(word)
+    }
+
+    fn upper_sigma1(word: Self::Word) -> Self::Word {
+        Sha256Params::upper_sigma1(word)
+    }
+
+    fn lower_sigma0(word: Self::Word) -> Self::Word {
+        Sha256Params::lower_sigma0(word)
+    }
+
+    fn lower_sigma1(word: Self::Word) -> Self::Word {
+        Sha256Params::lower_sigma1(word)
+    }
+}
+
 pub struct Sha512Params;
 
 impl Sha2Params for Sha512Params {
     type Word = u64;
 
+    const ALGORITHM: Sha2Algorithm = Sha2Algorithm::Sha512;
+
     type IntermediateHash = [u64; 8];
     type Digest = [u8; 64];
     type W = [u64; 80];
@@ -263,6 +340,10 @@ impl Sha2Params for Sha512Params {
         [0u64; 80]
     }
 
+    fn new_digest() -> Self::Digest {
+        [0u8; 64]
+    }
+
     fn write_hash(dst: &mut Self::Digest, ihash: &Self::IntermediateHash) {
         for i in 0..Self::HASH_LEN_BYTES {
             dst[i] = (ihash[i >> 3] >> (8 * (7 - (i % 8)))) as u8;
@@ -285,3 +366,216 @@ impl Sha2Params for Sha512Params {
         word.rotr(19) ^ word.rotr(61) ^ (word >> 6)
     }
 }
+
+pub struct Sha384Params;
+
+impl Sha2Params for Sha384Params {
+    type Word = u64;
+
+    const ALGORITHM: Sha2Algorithm = Sha2Algorithm::Sha384;
+
+    type IntermediateHash = [u64; 8];
+    type Digest = [u8; 48];
+    type W = [u64; 80];
+
+    type Constants = [u64; 80];
+
+    type MessageBlock = [u8; 128];
+
+    const W_LEN: usize = 80;
+    const MSG_BLOCK_SIZE: usize = 128;
+    const HASH_LEN_BYTES: usize = 48;
+
+    const H0: Self::IntermediateHash = [
+        0xCBBB9D5DC1059ED8u64,
+        0x629A292A367CD507u64,
+        0x9159015A3070DD17u64,
+        0x152FECD8F70E5939u64,
+        0x67332667FFC00B31u64,
+        0x8EB44A8768581511u64,
+        0xDB0C2E0D64F98FA7u64,
+        0x47B5481DBEFA4FA4u64,
+    ];
+
+    const K: Self::Constants = Sha512Params::K;
+
+    fn parse_word(src: &[u8]) -> Self::Word {
+        Sha512Params::parse_word(src)
+    }
+
+    fn new_msg_block() -> Self::MessageBlock {
+        [0u8; 128]
+    }
+
+    fn new_w() -> Self::W {
+        [0u64; 80]
+    }
+
+    fn new_digest() -> Self::Digest {
+        [0u8; 48]
+    }
+
+    fn write_hash(dst: &mut Self::Digest, ihash: &Self::IntermediateHash) {
+        for i in 0..Self::HASH_LEN_BYTES {
+            dst[i] = (ihash[i >> 3] >> (8 * (7 - (i % 8)))) as u8;
+        }
+    }
+
+    fn upper_sigma0(word: Self::Word) -> Self::Word {
+        Sha512Params::upper_sigma0(word)
+    }
+
+    fn upper_sigma1(word: Self::Word) -> Self::Word {
+        Sha512Params::upper_sigma1(word)
+    }
+
+    fn lower_sigma0(word: Self::Word) -> Self::Word {
+        Sha512Params::lower_sigma0(word)
+    }
+
+    fn lower_sigma1(word: Self::Word) -> Self::Word {
+        Sha512Params::lower_sigma1(word)
+    }
+}
+
+pub struct Sha512_224Params;
+
+impl Sha2Params for Sha512_224Params {
+    type Word = u64;
+
+    const ALGORITHM: Sha2Algorithm = Sha2Algorithm::Sha512_224;
+
+    type IntermediateHash = [u64; 8];
+    type Digest = [u8; 28];
+    type W = [u64; 80];
+
+    type Constants = [u64; 80];
+
+    type MessageBlock = [u8; 128];
+
+    const W_LEN: usize = 80;
+    const MSG_BLOCK_SIZE: usize = 128;
+    const HASH_LEN_BYTES: usize = 28;
+
+    const H0: Self::IntermediateHash = [
+        0x8C3D37C819544DA2u64,
+        0x73E1996689DCD4D6u64,
+        0x1DFAB7AE32FF9C82u64,
+        0x679DD514582F9FCFu64,
+        0x0F6D2B697BD44DA8u64,
+        0x77E36F7304C48942u64,
+        0x3F9D85A86A1D36C8u64,
+        0x1112E6AD91D692A1u64,
+    ];
+
+    const K: Self::Constants = Sha512Params::K;
+
+    fn parse_word(src: &[u8]) -> Self::Word {
+        Sha512Params::parse_word(src)
+    }
+
+    fn new_msg_block() -> Self::MessageBlock {
+        [0u8; 128]
+    }
+
+    fn new_w() -> Self::W {
+        [0u64; 80]
+    }
+
+    fn new_digest() -> Self::Digest {
+        [0u8; 28]
+    }
+
+    fn write_hash(dst: &mut Self::Digest, ihash: &Self::IntermediateHash) {
+        for i in 0..Self::HASH_LEN_BYTES {
+            dst[i] = (ihash[i >> 3] >> (8 * (7 - (i % 8)))) as u8;
+        }
+    }
+
+    fn upper_sigma0(word: Self::Word) -> Self::Word {
+        Sha512Params::upper_sigma0(word)
+    }
+
+    fn upper_sigma1(word: Self::Word) -> Self::Word {
+        Sha512Params::upper_sigma1(word)
+    }
+
+    fn lower_sigma0(word: Self::Word) -> Self::Word {
+        Sha512Params::lower_sigma0(word)
+    }
+
+    fn lower_sigma1(word: Self::Word) -> Self::Word {
+        Sha512Params::lower_sigma1(word)
+    }
+}
+
+pub struct Sha512_256Params;
+
+impl Sha2Params for Sha512_256Params {
+    type Word = u64;
+
+    const ALGORITHM: Sha2Algorithm = Sha2Algorithm::Sha512_256;
+
+    type IntermediateHash = [u64; 8];
+    type Digest = [u8; 32];
+    type W = [u64; 80];
+
+    type Constants = [u64; 80];
+
+    type MessageBlock = [u8; 128];
+
+    const W_LEN: usize = 80;
+    const MSG_BLOCK_SIZE: usize = 128;
+    const HASH_LEN_BYTES: usize = 32;
+
+    const H0: Self::IntermediateHash = [
+        0x22312194FC2BF72Cu64,
+        0x9F555FA3C84C64C2u64,
+        0x2393B86B6F53B151u64,
+        0x963877195940EABDu64,
+        0x96283EE2A88EFFE3u64,
+        0xBE5E1E2553863992u64,
+        0x2B0199FC2C85B8AAu64,
+        0x0EB72DDC81C52CA2u64,
+    ];
+
+    const K: Self::Constants = Sha512Params::K;
+
+    fn parse_word(src: &[u8]) -> Self::Word {
+        Sha512Params::parse_word(src)
+    }
+
+    fn new_msg_block() -> Self::MessageBlock {
+        [0u8; 128]
+    }
+
+    fn new_w() -> Self::W {
+        [0u64; 80]
+    }
+
+    fn new_digest() -> Self::Digest {
+        [0u8; 32]
+    }
+
+    fn write_hash(dst: &mut Self::Digest, ihash: &Self::IntermediateHash) {
+        for i in 0..Self::HASH_LEN_BYTES {
+            dst[i] = (ihash[i >> 3] >> (8 * (7 - (i % 8)))) as u8;
+        }
+    }
+
+    fn upper_sigma0(word: Self::Word) -> Self::Word {
+        Sha512Params::upper_sigma0(word)
+    }
+
+    fn upper_sigma1(word: Self::Word) -> Self::Word {
+        Sha512Params::upper_sigma1(word)
+    }
+
+    fn lower_sigma0(word: Self::Word) -> Self::Word {
+        Sha512Params::lower_sigma0(word)
+    }
+
+    fn lower_sigma1(word: Self::Word) -> Self::Word {
+        Sha512Params::lower_sigma1(word)
+    }
+}
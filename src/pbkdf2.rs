@@ -0,0 +1,140 @@
+use crate::algorithm::Sha2Algorithm;
+use crate::error::{Error, Result, Sha2Corrupted};
+use crate::hmac::Hmac;
+use crate::params::{
+    Sha224Params, Sha256Params, Sha2Params, Sha384Params, Sha512Params, Sha512_224Params,
+    Sha512_256Params,
+};
+use crate::wipe::wipe_slice;
+
+/// PBKDF2 (RFC 8018, section 5.2) with HMAC-SHA2 as the pseudorandom function.
+///
+/// The password is absorbed into the HMAC pad midstates once; every iteration only restores the
+/// inner midstate instead of hashing the password again.
+pub fn pbkdf2<P: Sha2Params>(
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    out: &mut [u8],
+) -> Result<()> {
+    if iterations == 0 || out.is_empty() {
+        return Err(Error(Sha2Corrupted::BadParam));
+    }
+
+    if (out.len() as u64) > (u32::MAX as u64) * (P::HASH_LEN_BYTES as u64) {
+        return Err(Error(Sha2Corrupted::BadParam));
+    }
+
+    let mut prf: Hmac<P> = Hmac::new(password)?;
+    let mut u = P::new_digest();
+    let mut t = P::new_digest();
+
+    let res = out
+        .chunks_mut(P::HASH_LEN_BYTES)
+        .enumerate()
+        .try_for_each(|(i, out_block)| {
+            let block_idx = (i + 1) as u32;
+
+            prf.reset()?;
+            prf.input(salt)?;
+            prf.input(&block_idx.to_be_bytes())?;
+            prf.result(&mut u)?;
+            t.as_mut().copy_from_slice(u.as_ref());
+
+            for _ in 1..iterations {
+                prf.reset()?;
+                prf.input(u.as_ref())?;
+                prf.result(&mut u)?;
+
+                for (t_byte, u_byte) in t.as_mut().iter_mut().zip(u.as_ref()) {
+                    *t_byte ^= u_byte;
+                }
+            }
+
+            out_block.copy_from_slice(&t.as_ref()[..out_block.len()]);
+            Ok(())
+        });
+
+    wipe_slice(u.as_mut(), 0);
+    wipe_slice(t.as_mut(), 0);
+    res
+}
+
+/// [`pbkdf2`] with the hash function chosen at runtime.
+pub fn pbkdf2_dyn(
+    algorithm: Sha2Algorithm,
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+    out: &mut [u8],
+) -> Result<()> {
+    match algorithm {
+        Sha2Algorithm::Sha224 => pbkdf2::<Sha224Params>(password, salt, iterations, out),
+        Sha2Algorithm::Sha256 => pbkdf2::<Sha256Params>(password, salt, iterations, out),
+        Sha2Algorithm::Sha384 => pbkdf2::<Sha384Params>(password, salt, iterations, out),
+        Sha2Algorithm::Sha512 => pbkdf2::<Sha512Params>(password, salt, iterations, out),
+        Sha2Algorithm::Sha512_224 => pbkdf2::<Sha512_224Params>(password, salt, iterations, out),
+        Sha2Algorithm::Sha512_256 => pbkdf2::<Sha512_256Params>(password, salt, iterations, out),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pbkdf2;
+    use crate::error::Result;
+    use crate::params::{Sha256Params, Sha512Params};
+
+    use hex_literal::hex;
+
+    // RFC 7914, section 11
+    #[test]
+    fn test_rfc7914_sha256() -> Result<()> {
+        let mut out = [0u8; 64];
+
+        pbkdf2::<Sha256Params>(b"passwd", b"salt", 1, &mut out)?;
+        assert_eq!(out, hex!("55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783"));
+
+        pbkdf2::<Sha256Params>(b"Password", b"NaCl", 80000, &mut out)?;
+        assert_eq!(out, hex!("4ddcd8f60b98be21830cee5ef22701f9641a4418d04c0414aeff08876b34ab56a1d425a1225833549adb841b51c9b3176a272bdebba1d078478f62b397f33c8d"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sha256_vectors() -> Result<()> {
+        let mut out = [0u8; 32];
+        pbkdf2::<Sha256Params>(b"password", b"salt", 4096, &mut out)?;
+        assert_eq!(
+            out,
+            hex!("c5e478d59288c841aa530db6845c4c8d962893a001ce4e11a4963873aa98134a")
+        );
+
+        let mut out = [0u8; 16];
+        pbkdf2::<Sha256Params>(b"pass\0word", b"sa\0lt", 4096, &mut out)?;
+        assert_eq!(out, hex!("89b69d0516f829893c696226650a8687"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sha512_vectors() -> Result<()> {
+        let mut out = [0u8; 64];
+
+        pbkdf2::<Sha512Params>(b"password", b"salt", 1, &mut out)?;
+        assert_eq!(out, hex!("867f70cf1ade02cff3752599a3a53dc4af34c7a669815ae5d513554e1c8cf252c02d470a285a0501bad999bfe943c08f050235d7d68b1da55e63f73b60a57fce"));
+
+        pbkdf2::<Sha512Params>(
+            b"passwordPASSWORDpassword",
+            b"saltSALTsaltSALTsaltSALTsaltSALTsalt",
+            4096,
+            &mut out,
+        )?;
+        assert_eq!(out, hex!("8c0511f4c6e597c6ac6315d8f0362e225f3c501495ba23b868c005174dc4ee71115b59f9e60cd9532fa33e0f75aefe30225c583a186cd82bd4daea9724a3d3b8"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_params() {
+        let mut out = [0u8; 32];
+        assert!(pbkdf2::<Sha256Params>(b"password", b"salt", 0, &mut out).is_err());
+        assert!(pbkdf2::<Sha256Params>(b"password", b"salt", 1, &mut []).is_err());
+    }
+}
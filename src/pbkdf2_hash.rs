@@ -0,0 +1,328 @@
+use crate::algorithm::Sha2Algorithm;
+use crate::base64::{ADAPTED, STANDARD, STANDARD_NO_PAD};
+use crate::error::{Error, Result, Sha2Corrupted};
+use crate::ops::ct_eq;
+use crate::pbkdf2::pbkdf2_dyn;
+use crate::phc::PhcString;
+use crate::wipe::wipe_slice;
+
+/// The string encodings of a PBKDF2-SHA2 password hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pbkdf2Format {
+    /// Django: `pbkdf2_sha256$<iterations>$<salt>$<base64 hash>`. The salt is stored as text.
+    Django,
+    /// Passlib: `$pbkdf2-sha256$<rounds>$<ab64 salt>$<ab64 hash>`.
+    Passlib,
+    /// PHC string format: `$pbkdf2-sha256$i=<iterations>,l=<length>$<b64 salt>$<b64 hash>`.
+    Phc,
+}
+
+/// A parsed PBKDF2-SHA2 password hash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pbkdf2Hash {
+    pub format: Pbkdf2Format,
+    pub algorithm: Sha2Algorithm,
+    pub iterations: u32,
+    pub salt: Vec<u8>,
+    pub hash: Vec<u8>,
+}
+
+impl Pbkdf2Hash {
+    /// Hashes `password`, producing a derived key of the algorithm's digest length. Django salts
+    /// must be valid UTF-8 and can't contain `$`.
+    pub fn generate(
+        format: Pbkdf2Format,
+        algorithm: Sha2Algorithm,
+        password: &[u8],
+        salt: &[u8],
+        iterations: u32,
+    ) -> Result<Self> {
+        if format == Pbkdf2Format::Django {
+            match std::str::from_utf8(salt) {
+                Ok(salt) if !salt.is_empty() && !salt.contains('$') => (),
+                _ => return Err(Error(Sha2Corrupted::BadParam)),
+            }
+        }
+
+        let mut hash = vec![0u8; algorithm.digest_len()];
+        pbkdf2_dyn(algorithm, password, salt, iterations, &mut hash)?;
+
+        Ok(Pbkdf2Hash {
+            format,
+            algorithm,
+            iterations,
+            salt: salt.to_vec(),
+            hash,
+        })
+    }
+
+    /// Recomputes the hash of `password` with the stored parameters and compares it in constant
+    /// time.
+    pub fn verify(&self, password: &[u8]) -> Result<bool> {
+        if self.hash.is_empty() {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        let mut computed = vec![0u8; self.hash.len()];
+        pbkdf2_dyn(
+            self.algorithm,
+            password,
+            &self.salt,
+            self.iterations,
+            &mut computed,
+        )?;
+
+        let matches = ct_eq(&computed, &self.hash);
+        wipe_slice(&mut computed, 0);
+        Ok(matches)
+    }
+
+    /// Whether the hash should be recomputed on the next successful login because its iteration
+    /// count is below the current policy.
+    pub fn needs_rehash(&self, min_iterations: u32) -> bool {
+        self.iterations < min_iterations
+    }
+
+    fn parse_ident(ident: &str, sep: char) -> Result<Sha2Algorithm> {
+        ident
+            .strip_prefix("pbkdf2")
+            .and_then(|alg| alg.strip_prefix(sep))
+            .ok_or(Error(Sha2Corrupted::BadParam))?
+            .parse()
+    }
+
+    fn parse_iterations(iterations: &str) -> Result<u32> {
+        if iterations.is_empty() || !iterations.bytes().all(|c| c.is_ascii_digit()) {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        iterations
+            .parse()
+            .map_err(|_| Error(Sha2Corrupted::BadParam))
+    }
+
+    fn parse_django(s: &str) -> Result<Self> {
+        let fields: Vec<&str> = s.split('$').collect();
+
+        let [ident, iterations, salt, hash] = fields[..] else {
+            return Err(Error(Sha2Corrupted::BadParam));
+        };
+
+        if salt.is_empty() {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        Ok(Pbkdf2Hash {
+            format: Pbkdf2Format::Django,
+            algorithm: Self::parse_ident(ident, '_')?,
+            iterations: Self::parse_iterations(iterations)?,
+            salt: salt.as_bytes().to_vec(),
+            hash: STANDARD.decode(hash)?,
+        })
+    }
+
+    fn parse_passlib(s: &str) -> Result<Self> {
+        let fields: Vec<&str> = s.split('$').collect();
+
+        let ["", ident, rounds, salt, hash] = fields[..] else {
+            return Err(Error(Sha2Corrupted::BadParam));
+        };
+
+        Ok(Pbkdf2Hash {
+            format: Pbkdf2Format::Passlib,
+            algorithm: Self::parse_ident(ident, '-')?,
+            iterations: Self::parse_iterations(rounds)?,
+            salt: ADAPTED.decode(salt)?,
+            hash: ADAPTED.decode(hash)?,
+        })
+    }
+
+    fn parse_phc(s: &str) -> Result<Self> {
+        let phc: PhcString = s.parse()?;
+
+        let (salt, hash) = match (phc.salt.clone(), phc.hash.clone()) {
+            (Some(salt), Some(hash)) => (salt, hash),
+            _ => return Err(Error(Sha2Corrupted::BadParam)),
+        };
+
+        if phc.param("l").is_some() && phc.param_u32("l")? as usize != hash.len() {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        Ok(Pbkdf2Hash {
+            format: Pbkdf2Format::Phc,
+            algorithm: Self::parse_ident(&phc.id, '-')?,
+            iterations: phc.param_u32("i")?,
+            salt,
+            hash,
+        })
+    }
+}
+
+impl std::str::FromStr for Pbkdf2Hash {
+    type Err = Error;
+
+    /// Detects the format from the shape of the string.
+    fn from_str(s: &str) -> Result<Self> {
+        if !s.starts_with('$') {
+            return Self::parse_django(s);
+        }
+
+        let is_passlib = s
+            .split('$')
+            .nth(2)
+            .is_some_and(|rounds| rounds.bytes().all(|c| c.is_ascii_digit()));
+
+        if is_passlib {
+            Self::parse_passlib(s)
+        } else {
+            Self::parse_phc(s)
+        }
+    }
+}
+
+impl std::fmt::Display for Pbkdf2Hash {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let alg = self.algorithm.short_name();
+
+        match self.format {
+            Pbkdf2Format::Django => write!(
+                f,
+                "pbkdf2_{}${}${}${}",
+                alg.replace('-', "_"),
+                self.iterations,
+                String::from_utf8_lossy(&self.salt),
+                STANDARD.encode(&self.hash)
+            ),
+            Pbkdf2Format::Passlib => write!(
+                f,
+                "$pbkdf2-{}${}${}${}",
+                alg,
+                self.iterations,
+                ADAPTED.encode(&self.salt),
+                ADAPTED.encode(&self.hash)
+            ),
+            Pbkdf2Format::Phc => write!(
+                f,
+                "$pbkdf2-{}$i={},l={}${}${}",
+                alg,
+                self.iterations,
+                self.hash.len(),
+                STANDARD_NO_PAD.encode(&self.salt),
+                STANDARD_NO_PAD.encode(&self.hash)
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Pbkdf2Format, Pbkdf2Hash};
+    use crate::algorithm::Sha2Algorithm;
+    use crate::error::Result;
+
+    #[test]
+    fn test_django() -> Result<()> {
+        let s = "pbkdf2_sha256$1000$seasalt$aZOLUDnbVq4qfmIhIFCkAqvDNHspRzj9l43SgVe7GOM=";
+        let hash: Pbkdf2Hash = s.parse()?;
+
+        assert_eq!(hash.format, Pbkdf2Format::Django);
+        assert_eq!(hash.algorithm, Sha2Algorithm::Sha256);
+        assert_eq!(hash.iterations, 1000);
+        assert_eq!(hash.salt, b"seasalt");
+        assert!(hash.verify(b"hunter2")?);
+        assert!(!hash.verify(b"hunter3")?);
+        assert_eq!(hash.to_string(), s);
+
+        let generated = Pbkdf2Hash::generate(
+            Pbkdf2Format::Django,
+            Sha2Algorithm::Sha512,
+            b"hunter2",
+            b"seasalt",
+            1000,
+        )?;
+        assert_eq!(
+            generated.to_string(),
+            "pbkdf2_sha512$1000$seasalt$y6fSob7Y+1Es5Ji8suu7LyglaH8mcawf2y1YlMuDKCXrYtRonedkIqRT+WAeFeH03zJaD6mlTkC1zIylpluZjQ=="
+        );
+        Ok(())
+    }
+
+    // example from the passlib documentation
+    #[test]
+    fn test_passlib() -> Result<()> {
+        let s = "$pbkdf2-sha256$6400$0ZrzXitFSGltTQnBWOsdAw$Y11AchqV4b0sUisdZd0Xr97KWoymNE0LNNrnEgY4H9M";
+        let hash: Pbkdf2Hash = s.parse()?;
+
+        assert_eq!(hash.format, Pbkdf2Format::Passlib);
+        assert_eq!(hash.iterations, 6400);
+        assert!(hash.verify(b"password")?);
+        assert_eq!(hash.to_string(), s);
+
+        let generated = Pbkdf2Hash::generate(
+            Pbkdf2Format::Passlib,
+            Sha2Algorithm::Sha512,
+            b"password",
+            b"some salt bytes!",
+            1000,
+        )?;
+        assert_eq!(
+            generated.to_string(),
+            "$pbkdf2-sha512$1000$c29tZSBzYWx0IGJ5dGVzIQ$79heEZtUY3e5kUWdajYqljWBMSSYgmwIXvRbS5Wliz3tvFiN.9YqUFVDoHhzyu2woBDs./KSSQbZEYzmRUax9w"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_phc() -> Result<()> {
+        let s = "$pbkdf2-sha256$i=1000,l=32$c29tZSBzYWx0IGJ5dGVzIQ$tuG+1Gb1H0lRUKOYt9c2pVmbF4FP4MqchS/ejDXn1sg";
+        let hash: Pbkdf2Hash = s.parse()?;
+
+        assert_eq!(hash.format, Pbkdf2Format::Phc);
+        assert_eq!(hash.salt, b"some salt bytes!");
+        assert!(hash.verify(b"password")?);
+        assert_eq!(hash.to_string(), s);
+
+        let without_len: Pbkdf2Hash =
+            "$pbkdf2-sha256$i=1000$c29tZSBzYWx0IGJ5dGVzIQ$tuG+1Gb1H0lRUKOYt9c2pVmbF4FP4MqchS/ejDXn1sg"
+                .parse()?;
+        assert!(without_len.verify(b"password")?);
+
+        assert!(
+            "$pbkdf2-sha256$i=1000,l=16$c29tZSBzYWx0IGJ5dGVzIQ$tuG+1Gb1H0lRUKOYt9c2pVmbF4FP4MqchS/ejDXn1sg"
+                .parse::<Pbkdf2Hash>()
+                .is_err()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_needs_rehash() -> Result<()> {
+        let hash: Pbkdf2Hash =
+            "pbkdf2_sha256$1000$seasalt$aZOLUDnbVq4qfmIhIFCkAqvDNHspRzj9l43SgVe7GOM=".parse()?;
+
+        assert!(hash.needs_rehash(600_000));
+        assert!(!hash.needs_rehash(1000));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!("pbkdf2_sha1$1000$salt$aGFzaA=="
+            .parse::<Pbkdf2Hash>()
+            .is_err());
+        assert!("pbkdf2_sha256$x$salt$aGFzaA=="
+            .parse::<Pbkdf2Hash>()
+            .is_err());
+        assert!("pbkdf2_sha256$1000$salt".parse::<Pbkdf2Hash>().is_err());
+        assert!("$pbkdf2-sha256$1000$salt".parse::<Pbkdf2Hash>().is_err());
+        assert!(Pbkdf2Hash::generate(
+            Pbkdf2Format::Django,
+            Sha2Algorithm::Sha256,
+            b"pw",
+            b"a$b",
+            1000
+        )
+        .is_err());
+    }
+}
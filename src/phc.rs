@@ -0,0 +1,204 @@
+use crate::base64::STANDARD_NO_PAD;
+use crate::error::{Error, Result, Sha2Corrupted};
+
+/// A password hash in the PHC string format:
+/// `$<id>[$v=<version>][$<param>=<value>(,<param>=<value>)*][$<salt>[$<hash>]]`.
+///
+/// Salt and hash are stored decoded; they are written as unpadded standard base64.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhcString {
+    pub id: String,
+    pub version: Option<u32>,
+    pub params: Vec<(String, String)>,
+    pub salt: Option<Vec<u8>>,
+    pub hash: Option<Vec<u8>>,
+}
+
+impl PhcString {
+    pub fn new(id: &str) -> Self {
+        PhcString {
+            id: id.to_string(),
+            version: None,
+            params: Vec::new(),
+            salt: None,
+            hash: None,
+        }
+    }
+
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Looks up a parameter and parses it as a decimal integer.
+    pub fn param_u32(&self, name: &str) -> Result<u32> {
+        self.param(name)
+            .and_then(parse_decimal)
+            .ok_or(Error(Sha2Corrupted::BadParam))
+    }
+
+    pub fn push_param(&mut self, name: &str, value: impl ToString) {
+        self.params.push((name.to_string(), value.to_string()));
+    }
+
+    fn valid_id(id: &str) -> bool {
+        !id.is_empty()
+            && id.len() <= 32
+            && id
+                .bytes()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == b'-')
+    }
+
+    fn valid_param_name(name: &str) -> bool {
+        !name.is_empty()
+            && name.len() <= 32
+            && name
+                .bytes()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == b'-')
+    }
+
+    fn valid_param_value(value: &str) -> bool {
+        !value.is_empty()
+            && value.bytes().all(|c| {
+                c.is_ascii_alphanumeric() || c == b'/' || c == b'+' || c == b'.' || c == b'-'
+            })
+    }
+}
+
+/// Parses a decimal integer without sign or leading zeros, as the PHC format requires.
+fn parse_decimal(value: &str) -> Option<u32> {
+    if value.is_empty() || (value.len() > 1 && value.starts_with('0')) {
+        return None;
+    }
+
+    if !value.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    value.parse().ok()
+}
+
+fn decode_field(field: &str) -> Result<Vec<u8>> {
+    if field.is_empty() {
+        return Err(Error(Sha2Corrupted::BadParam));
+    }
+
+    STANDARD_NO_PAD.decode(field)
+}
+
+impl std::str::FromStr for PhcString {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut fields = s
+            .strip_prefix('$')
+            .ok_or(Error(Sha2Corrupted::BadParam))?
+            .split('$')
+            .peekable();
+
+        let id = fields.next().ok_or(Error(Sha2Corrupted::BadParam))?;
+        if !Self::valid_id(id) {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        let mut phc = PhcString::new(id);
+
+        if let Some(version) = fields.peek().and_then(|f| f.strip_prefix("v=")) {
+            phc.version = Some(parse_decimal(version).ok_or(Error(Sha2Corrupted::BadParam))?);
+            fields.next();
+        }
+
+        if fields.peek().is_some_and(|f| f.contains('=')) {
+            for param in fields.next().unwrap_or_default().split(',') {
+                let (name, value) = param
+                    .split_once('=')
+                    .ok_or(Error(Sha2Corrupted::BadParam))?;
+
+                if !Self::valid_param_name(name) || !Self::valid_param_value(value) {
+                    return Err(Error(Sha2Corrupted::BadParam));
+                }
+
+                phc.push_param(name, value);
+            }
+        }
+
+        phc.salt = fields.next().map(decode_field).transpose()?;
+        phc.hash = fields.next().map(decode_field).transpose()?;
+
+        if fields.next().is_some() {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        Ok(phc)
+    }
+}
+
+impl std::fmt::Display for PhcString {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "${}", self.id)?;
+
+        if let Some(version) = self.version {
+            write!(f, "$v={version}")?;
+        }
+
+        for (i, (name, value)) in self.params.iter().enumerate() {
+            let sep = if i == 0 { '$' } else { ',' };
+            write!(f, "{sep}{name}={value}")?;
+        }
+
+        if let Some(salt) = &self.salt {
+            write!(f, "${}", STANDARD_NO_PAD.encode(salt))?;
+
+            if let Some(hash) = &self.hash {
+                write!(f, "${}", STANDARD_NO_PAD.encode(hash))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PhcString;
+
+    #[test]
+    fn test_parse_full() {
+        let s = "$argon2id$v=19$m=65536,t=2,p=1$c29tZXNhbHQ$RdescudvJCsgt3ub+b+dWRWJTmaaJObG";
+        let phc: PhcString = s.parse().unwrap();
+
+        assert_eq!(phc.id, "argon2id");
+        assert_eq!(phc.version, Some(19));
+        assert_eq!(phc.param("m"), Some("65536"));
+        assert_eq!(phc.param_u32("t").unwrap(), 2);
+        assert_eq!(phc.salt.as_deref(), Some(&b"somesalt"[..]));
+        assert_eq!(phc.hash.as_ref().map(Vec::len), Some(24));
+        assert_eq!(phc.to_string(), s);
+    }
+
+    #[test]
+    fn test_parse_partial() {
+        let phc: PhcString = "$pbkdf2-sha256$i=1000".parse().unwrap();
+        assert_eq!(phc.version, None);
+        assert_eq!(phc.param_u32("i").unwrap(), 1000);
+        assert!(phc.salt.is_none());
+
+        let phc: PhcString = "$balloon-sha256".parse().unwrap();
+        assert!(phc.params.is_empty());
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!("argon2id$v=19".parse::<PhcString>().is_err());
+        assert!("$Argon2id".parse::<PhcString>().is_err());
+        assert!("$x$a=1,b$c29tZXNhbHQ".parse::<PhcString>().is_err());
+        assert!("$x$v=019".parse::<PhcString>().is_err());
+        assert!("$x$a=1$c29tZXNhbHQ$aGFzaA$extra"
+            .parse::<PhcString>()
+            .is_err());
+        assert!("$x$a=1$!!".parse::<PhcString>().is_err());
+        assert!("$x$a=1$".parse::<PhcString>().is_err());
+    }
+}
@@ -0,0 +1,463 @@
+use crate::algorithm::Sha2Algorithm;
+use crate::context::Sha2Context;
+use crate::error::{Error, Result, Sha2Corrupted};
+use crate::ops::ct_eq;
+use crate::params::Sha2Params;
+use crate::wipe::wipe_slice;
+
+/// The DER encoding of the `DigestInfo` header for `algorithm` (RFC 8017, section 9.2, note 1),
+/// i.e. everything before the digest itself.
+pub fn pkcs1_digest_info_prefix(algorithm: Sha2Algorithm) -> &'static [u8] {
+    match algorithm {
+        Sha2Algorithm::Sha224 => &[
+            0x30, 0x2d, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x04, 0x05, 0x00, 0x04, 0x1c,
+        ],
+        Sha2Algorithm::Sha256 => &[
+            0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x01, 0x05, 0x00, 0x04, 0x20,
+        ],
+        Sha2Algorithm::Sha384 => &[
+            0x30, 0x41, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x02, 0x05, 0x00, 0x04, 0x30,
+        ],
+        Sha2Algorithm::Sha512 => &[
+            0x30, 0x51, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x03, 0x05, 0x00, 0x04, 0x40,
+        ],
+        Sha2Algorithm::Sha512_224 => &[
+            0x30, 0x2d, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x05, 0x05, 0x00, 0x04, 0x1c,
+        ],
+        Sha2Algorithm::Sha512_256 => &[
+            0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x06, 0x05, 0x00, 0x04, 0x20,
+        ],
+    }
+}
+
+/// The mask generation function MGF1 (RFC 8017, appendix B.2.1), filling `mask`.
+pub fn mgf1<P: Sha2Params>(seed: &[u8], mask: &mut [u8]) -> Result<()> {
+    mask.fill(0);
+    mgf1_xor::<P>(seed, mask)
+}
+
+/// EMSA-PKCS1-v1_5 encoding (RFC 8017, section 9.2) of the message hash `m_hash` into `em`,
+/// which must be as long as the RSA modulus.
+pub fn emsa_pkcs1_v1_5_encode<P: Sha2Params>(m_hash: &[u8], em: &mut [u8]) -> Result<()> {
+    let prefix = pkcs1_digest_info_prefix(P::ALGORITHM);
+    let t_len = prefix.len() + P::HASH_LEN_BYTES;
+
+    if m_hash.len() != P::HASH_LEN_BYTES || em.len() < t_len + 11 {
+        return Err(Error(Sha2Corrupted::BadParam));
+    }
+
+    let ps_end = em.len() - t_len - 1;
+    em[0] = 0x00;
+    em[1] = 0x01;
+    em[2..ps_end].fill(0xff);
+    em[ps_end] = 0x00;
+    em[ps_end + 1..ps_end + 1 + prefix.len()].copy_from_slice(prefix);
+    em[ps_end + 1 + prefix.len()..].copy_from_slice(m_hash);
+    Ok(())
+}
+
+/// Checks an EMSA-PKCS1-v1_5 encoded message, as recovered from a signature, against `m_hash`
+/// by re-encoding and comparing.
+pub fn emsa_pkcs1_v1_5_verify<P: Sha2Params>(m_hash: &[u8], em: &[u8]) -> Result<bool> {
+    let mut expected = vec![0u8; em.len()];
+    emsa_pkcs1_v1_5_encode::<P>(m_hash, &mut expected)?;
+    Ok(ct_eq(&expected, em))
+}
+
+/// EMSA-PSS encoding (RFC 8017, section 9.1.1) of the message hash `m_hash` with MGF1 over the
+/// same hash function.
+///
+/// The salt length is the length of `salt`, which the caller draws from its random source
+/// (usually `HashLen` bytes). `em_bits` is one less than the bit length of the RSA modulus and
+/// `em` must be exactly `ceil(em_bits / 8)` bytes.
+pub fn emsa_pss_encode<P: Sha2Params>(
+    m_hash: &[u8],
+    salt: &[u8],
+    em_bits: usize,
+    em: &mut [u8],
+) -> Result<()> {
+    let h_len = P::HASH_LEN_BYTES;
+
+    if m_hash.len() != h_len || em.len() != em_bits.div_ceil(8) || em.len() < h_len + salt.len() + 2
+    {
+        return Err(Error(Sha2Corrupted::BadParam));
+    }
+
+    let clear_bits = 8 * em.len() - em_bits;
+    let db_len = em.len() - h_len - 1;
+    let (db, tail) = em.split_at_mut(db_len);
+    let (h, trailer) = tail.split_at_mut(h_len);
+
+    let mut ctx: Sha2Context<P> = Sha2Context::new();
+    ctx.input(&[0u8; 8])?;
+    ctx.input(m_hash)?;
+    ctx.input(salt)?;
+    let mut digest = P::new_digest();
+    ctx.result(&mut digest)?;
+    h.copy_from_slice(digest.as_ref());
+
+    let ps_len = db_len - salt.len() - 1;
+    db[..ps_len].fill(0);
+    db[ps_len] = 0x01;
+    db[ps_len + 1..].copy_from_slice(salt);
+    mgf1_xor::<P>(h, db)?;
+
+    db[0] &= 0xff >> clear_bits;
+    trailer[0] = 0xbc;
+    Ok(())
+}
+
+/// Checks an EMSA-PSS encoded message (RFC 8017, section 9.1.2) against `m_hash`.
+///
+/// With `salt_len` set, the salt must be exactly that long; with `None` it is recovered from the
+/// encoding, as most verifiers do. Returns `Ok(false)` for an inconsistent encoding and an error
+/// only when the parameters themselves are wrong.
+pub fn emsa_pss_verify<P: Sha2Params>(
+    m_hash: &[u8],
+    em: &[u8],
+    em_bits: usize,
+    salt_len: Option<usize>,
+) -> Result<bool> {
+    let h_len = P::HASH_LEN_BYTES;
+
+    if m_hash.len() != h_len || em.len() != em_bits.div_ceil(8) {
+        return Err(Error(Sha2Corrupted::BadParam));
+    }
+
+    if em.len() < h_len + salt_len.unwrap_or(0) + 2 || em[em.len() - 1] != 0xbc {
+        return Ok(false);
+    }
+
+    let db_len = em.len() - h_len - 1;
+    let h = &em[db_len..em.len() - 1];
+    let top_mask = 0xffu8 >> (8 * em.len() - em_bits);
+
+    if em[0] & !top_mask != 0 {
+        return Ok(false);
+    }
+
+    let mut db = em[..db_len].to_vec();
+    mgf1_xor::<P>(h, &mut db)?;
+    db[0] &= top_mask;
+
+    let Some(one) = db.iter().position(|&b| b != 0) else {
+        return Ok(false);
+    };
+
+    if db[one] != 0x01 || salt_len.is_some_and(|salt_len| db_len - one - 1 != salt_len) {
+        return Ok(false);
+    }
+
+    let mut ctx: Sha2Context<P> = Sha2Context::new();
+    ctx.input(&[0u8; 8])?;
+    ctx.input(m_hash)?;
+    ctx.input(&db[one + 1..])?;
+    let mut digest = P::new_digest();
+    ctx.result(&mut digest)?;
+
+    Ok(digest.as_ref() == h)
+}
+
+/// EME-OAEP encoding (RFC 8017, section 7.1.1) with MGF1 over the same hash function.
+///
+/// `seed` must be `HashLen` random bytes and `em` as long as the RSA modulus. The message may be
+/// at most `em.len() - 2 * HashLen - 2` bytes long.
+pub fn eme_oaep_encode<P: Sha2Params>(
+    msg: &[u8],
+    label: &[u8],
+    seed: &[u8],
+    em: &mut [u8],
+) -> Result<()> {
+    let h_len = P::HASH_LEN_BYTES;
+
+    if seed.len() != h_len || em.len() < 2 * h_len + 2 || msg.len() > em.len() - 2 * h_len - 2 {
+        return Err(Error(Sha2Corrupted::BadParam));
+    }
+
+    let (y, rest) = em.split_at_mut(1);
+    let (masked_seed, db) = rest.split_at_mut(h_len);
+
+    y[0] = 0x00;
+    label_hash::<P>(label, &mut db[..h_len])?;
+    let one = db.len() - msg.len() - 1;
+    db[h_len..one].fill(0);
+    db[one] = 0x01;
+    db[one + 1..].copy_from_slice(msg);
+
+    masked_seed.copy_from_slice(seed);
+    mgf1_xor::<P>(masked_seed, db)?;
+    mgf1_xor::<P>(db, masked_seed)
+}
+
+/// EME-OAEP decoding (RFC 8017, section 7.1.2) of an encoded message recovered by RSA
+/// decryption, returning the message.
+///
+/// All decoding failures give the same `BadParam` error, and the checks are done without
+/// branching on secret data, so that callers don't become a padding oracle.
+pub fn eme_oaep_decode<P: Sha2Params>(em: &[u8], label: &[u8]) -> Result<Vec<u8>> {
+    let h_len = P::HASH_LEN_BYTES;
+
+    if em.len() < 2 * h_len + 2 {
+        return Err(Error(Sha2Corrupted::BadParam));
+    }
+
+    let mut seed = em[1..1 + h_len].to_vec();
+    let mut db = em[1 + h_len..].to_vec();
+    mgf1_xor::<P>(&db, &mut seed)?;
+    mgf1_xor::<P>(&seed, &mut db)?;
+
+    let mut l_hash = vec![0u8; h_len];
+    label_hash::<P>(label, &mut l_hash)?;
+
+    // all-ones masks: `valid` while nothing is wrong, `looking` until the 0x01 separator
+    let mut valid = ct_mask(em[0] == 0) & ct_mask(ct_eq(&db[..h_len], &l_hash));
+    let mut looking = 0xffu8;
+    let mut msg_start = 0usize;
+
+    for (i, &b) in db.iter().enumerate().skip(h_len) {
+        let is_zero = ct_mask(b == 0);
+        let is_one = ct_mask(b == 1);
+        let found = looking & is_one;
+
+        msg_start |= (i + 1) & usize::from(found & 1).wrapping_neg();
+        valid &= !(looking & !is_zero & !is_one);
+        looking &= !is_one;
+    }
+    valid &= !looking;
+
+    let res = if std::hint::black_box(valid) == 0xff {
+        Ok(db[msg_start..].to_vec())
+    } else {
+        Err(Error(Sha2Corrupted::BadParam))
+    };
+
+    wipe_slice(&mut seed, 0);
+    wipe_slice(&mut db, 0);
+    res
+}
+
+fn mgf1_xor<P: Sha2Params>(seed: &[u8], out: &mut [u8]) -> Result<()> {
+    if (out.len() as u64) > (u32::MAX as u64 + 1) * (P::HASH_LEN_BYTES as u64) {
+        return Err(Error(Sha2Corrupted::BadParam));
+    }
+
+    let mut digest = P::new_digest();
+
+    let res = out
+        .chunks_mut(P::HASH_LEN_BYTES)
+        .enumerate()
+        .try_for_each(|(i, out_block)| {
+            let mut ctx: Sha2Context<P> = Sha2Context::new();
+            ctx.input(seed)?;
+            ctx.input(&(i as u32).to_be_bytes())?;
+            ctx.result(&mut digest)?;
+
+            for (o, m) in out_block.iter_mut().zip(digest.as_ref()) {
+                *o ^= m;
+            }
+            Ok(())
+        });
+
+    wipe_slice(digest.as_mut(), 0);
+    res
+}
+
+fn label_hash<P: Sha2Params>(label: &[u8], out: &mut [u8]) -> Result<()> {
+    let mut ctx: Sha2Context<P> = Sha2Context::new();
+    ctx.input(label)?;
+    let mut digest = P::new_digest();
+    ctx.result(&mut digest)?;
+    out.copy_from_slice(digest.as_ref());
+    Ok(())
+}
+
+fn ct_mask(b: bool) -> u8 {
+    (b as u8).wrapping_neg()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        eme_oaep_decode, eme_oaep_encode, emsa_pkcs1_v1_5_encode, emsa_pkcs1_v1_5_verify,
+        emsa_pss_encode, emsa_pss_verify, mgf1, pkcs1_digest_info_prefix,
+    };
+    use crate::algorithm::Sha2Algorithm;
+    use crate::error::Result;
+    use crate::params::{Sha224Params, Sha256Params, Sha384Params, Sha512Params};
+
+    use hex_literal::hex;
+
+    // the encoded messages below were recovered from signatures and ciphertexts made with
+    // pyca/cryptography (OpenSSL) by applying the raw RSA operation
+
+    #[test]
+    fn test_mgf1() -> Result<()> {
+        let mut mask = [0u8; 50];
+        mgf1::<Sha256Params>(b"foo", &mut mask)?;
+        assert_eq!(mask, hex!("3bdaba83cff13337b323ac383ca3995863e922f511b931b9efd4e0118cfc70f08678390d67e3c12dbeb2d7a78bdfa597b5a3"));
+
+        let mut mask = [0u8; 40];
+        mgf1::<Sha512Params>(b"bar", &mut mask)?;
+        assert_eq!(
+            mask,
+            hex!(
+                "8625c97145f50577911b25359975c8f942487e7aa0167e6db44239680d08547afcef6d3b7080cde5"
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_digest_info_prefix() {
+        for algorithm in Sha2Algorithm::ALL {
+            let prefix = pkcs1_digest_info_prefix(algorithm);
+            assert_eq!(prefix.len(), 19);
+            assert_eq!(prefix[1] as usize, 17 + algorithm.digest_len());
+            assert_eq!(prefix[18] as usize, algorithm.digest_len());
+        }
+    }
+
+    #[test]
+    fn test_pkcs1_v1_5() -> Result<()> {
+        // SHA-256("hello pkcs1"), 1024-bit modulus
+        let m_hash = hex!("674db2bd7bf2310996db2bd2474fb74cb9c48b0358f5eb179fdeb8df489777d9");
+        let em = hex!("0001ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff003031300d060960864801650304020105000420674db2bd7bf2310996db2bd2474fb74cb9c48b0358f5eb179fdeb8df489777d9");
+
+        let mut out = [0u8; 128];
+        emsa_pkcs1_v1_5_encode::<Sha256Params>(&m_hash, &mut out)?;
+        assert_eq!(out, em);
+        assert!(emsa_pkcs1_v1_5_verify::<Sha256Params>(&m_hash, &em)?);
+
+        let mut bad = em;
+        bad[20] = 0xfe;
+        assert!(!emsa_pkcs1_v1_5_verify::<Sha256Params>(&m_hash, &bad)?);
+
+        // same digest length, different DigestInfo
+        let mut out = [0u8; 128];
+        emsa_pkcs1_v1_5_encode::<Sha224Params>(&m_hash[..28], &mut out)?;
+        assert_eq!(
+            out[128 - 48..],
+            hex!("00302d300d06096086480165030402040500041c674db2bd7bf2310996db2bd2474fb74cb9c48b0358f5eb179fdeb8df")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_pss() -> Result<()> {
+        // SHA-256("hello pss"), 20-byte salt, 1024-bit modulus
+        let m_hash = hex!("5d1961d33b1b26035f275173addb3ed49f4f1912bf9b8ea2bcb6df88480c5057");
+        let salt = hex!("1223fad3b35d757d8543e5b15c2872dd5776d8da");
+        let em = hex!("2fb2ad96132b2cece63c20ad3395ebadf1bc4098e0907f4c0b53abff55ec06329588391a8b1929edd95a7c5bbf714c303a1fba9157818b5c5331d9474912bab465e70f5dfd6be18e90ebcd61019c0d1a40cb0e03ab636946b8b52741addfda930a9a53e0f4d126e4e624d21b7f8a9ad8b06183935c3d0ef1f3eb2285db2a96bc");
+
+        let mut out = [0u8; 128];
+        emsa_pss_encode::<Sha256Params>(&m_hash, &salt, 1023, &mut out)?;
+        assert_eq!(out, em);
+        assert!(emsa_pss_verify::<Sha256Params>(
+            &m_hash,
+            &em,
+            1023,
+            Some(20)
+        )?);
+        assert!(emsa_pss_verify::<Sha256Params>(&m_hash, &em, 1023, None)?);
+        assert!(!emsa_pss_verify::<Sha256Params>(
+            &m_hash,
+            &em,
+            1023,
+            Some(32)
+        )?);
+
+        let mut bad = em;
+        bad[5] ^= 1;
+        assert!(!emsa_pss_verify::<Sha256Params>(&m_hash, &bad, 1023, None)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pss_odd_modulus() -> Result<()> {
+        // SHA-384("hello pss"), 20-byte salt, 1030-bit modulus, so three top bits are cleared
+        let m_hash = hex!("87b4d12e86a977d5e1750718815c8cfb76ebe0f9861cceeae721295a49c31c254227b9390237c3a933852c703e8d7724");
+        let salt = hex!("f468d2d777c0c1f72bcecc38d9bc3e09ac7976ca");
+        let em = hex!("1114511a659364c05ac5d23f9c0171fc2fa8ed591ba13631bcb0c06c83e31101a07418f7da613a7b60dba807cd6c27a6afe81de6500676c51e02f4ca9837b23127b0ce265d7a9c3042cb05f7f13f063d8082c9d32106953b8836f90bd7378bc2d12dc6d382b309c5ddee1400201e9f6c91fef3c1cb02d315fefe828da1e04dfdbc");
+
+        let mut out = [0u8; 129];
+        emsa_pss_encode::<Sha384Params>(&m_hash, &salt, 1029, &mut out)?;
+        assert_eq!(out, em);
+        assert!(emsa_pss_verify::<Sha384Params>(&m_hash, &em, 1029, None)?);
+
+        let mut bad = em;
+        bad[0] |= 0x80;
+        assert!(!emsa_pss_verify::<Sha384Params>(&m_hash, &bad, 1029, None)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pss_empty_salt() -> Result<()> {
+        let m_hash = [0x5a; 64];
+        let mut em = [0u8; 256];
+        emsa_pss_encode::<Sha512Params>(&m_hash, &[], 2047, &mut em)?;
+        assert!(emsa_pss_verify::<Sha512Params>(
+            &m_hash,
+            &em,
+            2047,
+            Some(0)
+        )?);
+        assert!(emsa_pss_verify::<Sha512Params>(&m_hash, &em, 2047, None)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_oaep() -> Result<()> {
+        // SHA-256, label "label", 1024-bit modulus
+        let seed = hex!("2a47c024c604f3be9c8e1b6382821e6ab5ed2150e5017bb586dd91da5d9199c6");
+        let em = hex!("00168482bd09ec582fad51166d1e439d80a2e868c52ba2535967aa6ed3c8038938340866e1862c06f155bcade46136f2e1121f67bafb0f4f2f032eb938531222d9715173fa48378048e98c0128fbe5c199e6c6a51e5dcf40b80b5f643ebd08d89ebdfdd9a976e8d21194f0257a5ceb811ac171cb7edcb01171244b96d5b3f2c0");
+
+        assert_eq!(
+            eme_oaep_decode::<Sha256Params>(&em, b"label")?,
+            b"oaep message"
+        );
+
+        let mut out = [0u8; 128];
+        eme_oaep_encode::<Sha256Params>(b"oaep message", b"label", &seed, &mut out)?;
+        assert_eq!(out, em);
+
+        assert!(eme_oaep_decode::<Sha256Params>(&em, b"").is_err());
+        let mut bad = em;
+        bad[0] = 1;
+        assert!(eme_oaep_decode::<Sha256Params>(&bad, b"label").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_oaep_roundtrip() -> Result<()> {
+        let seed = [7u8; 64];
+        let mut em = [0u8; 256];
+
+        // longest message that fits, and the empty one
+        let msg = [0xabu8; 256 - 2 * 64 - 2];
+        eme_oaep_encode::<Sha512Params>(&msg, &[], &seed, &mut em)?;
+        assert_eq!(eme_oaep_decode::<Sha512Params>(&em, &[])?, msg);
+
+        eme_oaep_encode::<Sha512Params>(&[], b"l", &seed, &mut em)?;
+        assert!(eme_oaep_decode::<Sha512Params>(&em, b"l")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_params() {
+        let mut em = [0u8; 128];
+        assert!(emsa_pkcs1_v1_5_encode::<Sha256Params>(&[0; 31], &mut em).is_err());
+        assert!(emsa_pkcs1_v1_5_encode::<Sha512Params>(&[0; 64], &mut em[..93]).is_err());
+        assert!(emsa_pss_encode::<Sha256Params>(&[0; 32], &[], 1024, &mut em[..127]).is_err());
+        assert!(emsa_pss_encode::<Sha512Params>(&[0; 64], &[0; 63], 1023, &mut em).is_err());
+        assert!(emsa_pss_verify::<Sha256Params>(&[0; 32], &em, 1030, None).is_err());
+        assert!(eme_oaep_encode::<Sha256Params>(&[0; 63], &[], &[0; 32], &mut em).is_err());
+        assert!(eme_oaep_encode::<Sha256Params>(&[], &[], &[0; 20], &mut em).is_err());
+        assert!(eme_oaep_decode::<Sha512Params>(&em[..129 - 1 - 1], &[]).is_err());
+    }
+}
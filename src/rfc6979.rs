@@ -0,0 +1,269 @@
+use crate::error::{Error, Result, Sha2Corrupted};
+use crate::hmac_drbg::HmacDrbg;
+use crate::params::Sha2Params;
+use crate::wipe::wipe_slice;
+
+/// Deterministic generation of the per-signature value `k` for DSA and ECDSA (RFC 6979,
+/// section 3.2).
+///
+/// The generation process is HMAC_DRBG seeded with `int2octets(x) || bits2octets(h1)`, so this
+/// wraps [`HmacDrbg`]. All integers are big-endian byte strings; `k` comes out as `rlen` bytes,
+/// where `rlen` is the byte length of the group order `q`.
+pub struct Rfc6979<P: Sha2Params> {
+    drbg: HmacDrbg<P>,
+    q: Vec<u8>,
+    qlen: usize,
+}
+
+impl<P: Sha2Params> Rfc6979<P> {
+    /// `x` is the private key and `h1` the hash of the message, computed with any hash function.
+    pub fn new(q: &[u8], x: &[u8], h1: &[u8]) -> Result<Self> {
+        let q = strip_zeros(q);
+        let mut x = rfc6979_int2octets(q, x)?;
+        let mut h = rfc6979_bits2octets(q, h1)?;
+
+        let drbg = HmacDrbg::from_seed_material(&[&x, &h]);
+        wipe_slice(&mut x, 0);
+        wipe_slice(&mut h, 0);
+
+        Ok(Rfc6979 {
+            drbg: drbg?,
+            q: q.to_vec(),
+            qlen: bit_len(q),
+        })
+    }
+
+    /// The next candidate `k` in `[1, q - 1]`. The first call returns the nonce to use; later
+    /// calls give the values to retry with if the signature turned out invalid (`r = 0` or
+    /// `s = 0`).
+    pub fn next_k(&mut self) -> Result<Vec<u8>> {
+        let mut t = vec![0u8; self.q.len()];
+
+        loop {
+            // every generate call ends with K = HMAC_K(V || 0x00), V = HMAC_K(V), which is the
+            // state update of step h.3
+            self.drbg.generate(&mut t, &[])?;
+            let k = bits2int(self.qlen, &t);
+
+            if k.iter().any(|&b| b != 0) && k.as_slice() < self.q.as_slice() {
+                wipe_slice(&mut t, 0);
+                return Ok(k);
+            }
+        }
+    }
+}
+
+/// The first nonce produced by [`Rfc6979`].
+pub fn rfc6979_nonce<P: Sha2Params>(q: &[u8], x: &[u8], h1: &[u8]) -> Result<Vec<u8>> {
+    Rfc6979::<P>::new(q, x, h1)?.next_k()
+}
+
+/// `bits2int`: the leftmost `qlen` bits of `b` as an integer, returned in `rlen` bytes.
+pub fn rfc6979_bits2int(q: &[u8], b: &[u8]) -> Result<Vec<u8>> {
+    let q = strip_zeros(q);
+    check_order(q)?;
+    Ok(bits2int(bit_len(q), b))
+}
+
+/// `int2octets`: `x` as `rlen` bytes. `x` must be in `[1, q - 1]`.
+pub fn rfc6979_int2octets(q: &[u8], x: &[u8]) -> Result<Vec<u8>> {
+    let q = strip_zeros(q);
+    check_order(q)?;
+
+    let x = strip_zeros(x);
+    if x.is_empty() || x.len() > q.len() {
+        return Err(Error(Sha2Corrupted::BadParam));
+    }
+
+    let mut out = vec![0u8; q.len()];
+    out[q.len() - x.len()..].copy_from_slice(x);
+
+    if out.as_slice() >= q {
+        return Err(Error(Sha2Corrupted::BadParam));
+    }
+
+    Ok(out)
+}
+
+/// `bits2octets`: `bits2int(b) mod q` as `rlen` bytes.
+pub fn rfc6979_bits2octets(q: &[u8], b: &[u8]) -> Result<Vec<u8>> {
+    let q = strip_zeros(q);
+    let mut z = rfc6979_bits2int(q, b)?;
+
+    // z < 2^qlen < 2q, so one subtraction is enough
+    if z.as_slice() >= q {
+        let mut borrow = 0i16;
+        for (z_byte, q_byte) in z.iter_mut().rev().zip(q.iter().rev()) {
+            let diff = *z_byte as i16 - *q_byte as i16 - borrow;
+            *z_byte = diff as u8;
+            borrow = (diff < 0) as i16;
+        }
+    }
+
+    Ok(z)
+}
+
+fn bits2int(qlen: usize, b: &[u8]) -> Vec<u8> {
+    let rlen = qlen.div_ceil(8);
+    let mut out = vec![0u8; rlen];
+
+    if b.len() * 8 <= qlen {
+        out[rlen - b.len()..].copy_from_slice(b);
+        return out;
+    }
+
+    // drop whole bytes first, then the remaining bits
+    out.copy_from_slice(&b[..rlen]);
+    let shift = rlen * 8 - qlen;
+
+    if shift > 0 {
+        for i in (0..rlen).rev() {
+            let carry = if i > 0 { out[i - 1] << (8 - shift) } else { 0 };
+            out[i] = (out[i] >> shift) | carry;
+        }
+    }
+
+    out
+}
+
+fn check_order(q: &[u8]) -> Result<()> {
+    if q.is_empty() || q == [1] {
+        return Err(Error(Sha2Corrupted::BadParam));
+    }
+
+    Ok(())
+}
+
+fn strip_zeros(mut b: &[u8]) -> &[u8] {
+    while let [0, rest @ ..] = b {
+        b = rest;
+    }
+    b
+}
+
+fn bit_len(q: &[u8]) -> usize {
+    q.first()
+        .map_or(0, |&b| q.len() * 8 - b.leading_zeros() as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rfc6979_bits2int, rfc6979_bits2octets, rfc6979_nonce, Rfc6979};
+    use crate::context::Sha2Context;
+    use crate::error::Result;
+    use crate::params::{Sha256Params, Sha2Params, Sha384Params, Sha512Params};
+
+    use hex_literal::hex;
+
+    const P256_Q: [u8; 32] =
+        hex!("FFFFFFFF00000000FFFFFFFFFFFFFFFFBCE6FAADA7179E84F3B9CAC2FC632551");
+    const P256_X: [u8; 32] =
+        hex!("C9AFA9D845BA75166B5C215767B1D6934E50C3DB36E89B127B8A622B120F6721");
+    const P384_Q: [u8; 48] = hex!("FFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFFC7634D81F4372DDF581A0DB248B0A77AECEC196ACCC52973");
+    const P384_X: [u8; 48] = hex!("6B9D3DAD2E1B8C1C05B19875B6659F4DE23C3B667BF297BA9AA47740787137D896D5724E4C70A825F872C9EA60D2EDF5");
+
+    fn hash<P: Sha2Params>(msg: &[u8]) -> Result<P::Digest> {
+        let mut digest = P::new_digest();
+        let mut ctx: Sha2Context<P> = Sha2Context::new();
+        ctx.input(msg)?;
+        ctx.result(&mut digest)?;
+        Ok(digest)
+    }
+
+    fn nonce<P: Sha2Params>(q: &[u8], x: &[u8], msg: &[u8]) -> Result<Vec<u8>> {
+        rfc6979_nonce::<P>(q, x, hash::<P>(msg)?.as_ref())
+    }
+
+    // RFC 6979, appendix A.2.5
+    #[test]
+    fn test_p256() -> Result<()> {
+        assert_eq!(
+            nonce::<Sha256Params>(&P256_Q, &P256_X, b"sample")?,
+            hex!("A6E3C57DD01ABE90086538398355DD4C3B17AA873382B0F24D6129493D8AAD60")
+        );
+        assert_eq!(
+            nonce::<Sha384Params>(&P256_Q, &P256_X, b"sample")?,
+            hex!("09F634B188CEFD98E7EC88B1AA9852D734D0BC272F7D2A47DECC6EBEB375AAD4")
+        );
+        assert_eq!(
+            nonce::<Sha512Params>(&P256_Q, &P256_X, b"sample")?,
+            hex!("5FA81C63109BADB88C1F367B47DA606DA28CAD69AA22C4FE6AD7DF73A7173AA5")
+        );
+        assert_eq!(
+            nonce::<Sha256Params>(&P256_Q, &P256_X, b"test")?,
+            hex!("D16B6AE827F17175E040871A1C7EC3500192C4C92677336EC2537ACAEE0008E0")
+        );
+        assert_eq!(
+            nonce::<Sha512Params>(&P256_Q, &P256_X, b"test")?,
+            hex!("6915D11632ACA3C40D5D51C08DAF9C555933819548784480E93499000D9F0B7F")
+        );
+        Ok(())
+    }
+
+    // RFC 6979, appendix A.2.6
+    #[test]
+    fn test_p384() -> Result<()> {
+        assert_eq!(
+            nonce::<Sha256Params>(&P384_Q, &P384_X, b"sample")?,
+            hex!("180AE9F9AEC5438A44BC159A1FCB277C7BE54FA20E7CF404B490650A8ACC414E375572342863C899F9F2EDF9747A9B60")
+        );
+        assert_eq!(
+            nonce::<Sha384Params>(&P384_Q, &P384_X, b"sample")?,
+            hex!("94ED910D1A099DAD3254E9242AE85ABDE4BA15168EAF0CA87A555FD56D10FBCA2907E3E83BA95368623B8C4686915CF9")
+        );
+        assert_eq!(
+            nonce::<Sha512Params>(&P384_Q, &P384_X, b"sample")?,
+            hex!("92FC3C7183A883E24216D1141F1A8976C5B0DD797DFA597E3D7B32198BD35331A4E966532593A52980D0E3AAA5E10EC3")
+        );
+        assert_eq!(
+            nonce::<Sha384Params>(&P384_Q, &P384_X, b"test")?,
+            hex!("015EE46A5BF88773ED9123A5AB0807962D193719503C527B031B4C2D225092ADA71F4A459BC0DA98ADB95837DB8312EA")
+        );
+        Ok(())
+    }
+
+    // RFC 6979, appendix A.1: a 163-bit q, so bits2int has to shift within a byte
+    #[test]
+    fn test_a1_example() -> Result<()> {
+        let q = hex!("04000000000000000000020108A2E0CC0D99F8A5EF");
+        let x = hex!("009A4D6792295A7F730FC3F2B49CBC0F62E862272F");
+        let h1 = hash::<Sha256Params>(b"sample")?;
+
+        assert_eq!(
+            rfc6979_bits2int(&q, &h1)?,
+            hex!("05795EDF0D54DB760F156F0EB4A7A0FE38D418E813")
+        );
+        assert_eq!(
+            rfc6979_bits2octets(&q, &h1)?,
+            hex!("01795EDF0D54DB760F156D0DAC04C0322B3A204224")
+        );
+        assert_eq!(
+            rfc6979_nonce::<Sha256Params>(&q, &x, &h1)?,
+            hex!("023AF4074C90A02B3FE61D286D5C87F425E6BDD81B")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_retries_are_distinct() -> Result<()> {
+        let h1 = hash::<Sha256Params>(b"sample")?;
+        let mut gen: Rfc6979<Sha256Params> = Rfc6979::new(&P256_Q, &P256_X, &h1)?;
+
+        let first = gen.next_k()?;
+        let second = gen.next_k()?;
+        assert_eq!(
+            first,
+            hex!("A6E3C57DD01ABE90086538398355DD4C3B17AA873382B0F24D6129493D8AAD60")
+        );
+        assert_ne!(first, second);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_params() {
+        let h1 = [0u8; 32];
+        assert!(rfc6979_nonce::<Sha256Params>(&P256_Q, &[0u8; 32], &h1).is_err());
+        assert!(rfc6979_nonce::<Sha256Params>(&P256_Q, &P256_Q, &h1).is_err());
+        assert!(rfc6979_nonce::<Sha256Params>(&[0, 1], &[1], &h1).is_err());
+    }
+}
@@ -0,0 +1,241 @@
+use crate::error::{Error, Result, Sha2Corrupted};
+use crate::params::Sha256Params;
+use crate::pbkdf2::pbkdf2;
+use crate::wipe::wipe_slice;
+
+/// Upper bound on the memory used by [`scrypt`] unless overridden with
+/// [`ScryptParams::with_max_memory`].
+pub const SCRYPT_DEFAULT_MAX_MEMORY: usize = 1 << 30;
+
+/// Cost parameters for [`scrypt`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScryptParams {
+    log_n: u8,
+    r: u32,
+    p: u32,
+    max_memory: usize,
+}
+
+impl ScryptParams {
+    /// Validates the parameters as required by RFC 7914, section 2: `N = 2^log_n` must be
+    /// greater than 1 and less than `2^(128 * r / 8)`, and `r * p` must be less than `2^30`.
+    pub fn new(log_n: u8, r: u32, p: u32) -> Result<Self> {
+        if log_n == 0 || log_n >= 64 || r == 0 || p == 0 {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        if (log_n as u64) >= 16 * (r as u64) {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        if (r as u64) * (p as u64) >= 1 << 30 {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        Ok(ScryptParams {
+            log_n,
+            r,
+            p,
+            max_memory: SCRYPT_DEFAULT_MAX_MEMORY,
+        })
+    }
+
+    /// Sets the number of bytes [`scrypt`] may allocate before refusing to run.
+    pub fn with_max_memory(mut self, max_memory: usize) -> Self {
+        self.max_memory = max_memory;
+        self
+    }
+
+    pub fn log_n(&self) -> u8 {
+        self.log_n
+    }
+
+    pub fn r(&self) -> u32 {
+        self.r
+    }
+
+    pub fn p(&self) -> u32 {
+        self.p
+    }
+
+    /// Bytes needed for the ROMix table `V` plus the `p` blocks of `B`, or `None` on overflow.
+    pub fn memory_required(&self) -> Option<usize> {
+        let block_len = 128usize.checked_mul(self.r as usize)?;
+        let n = 1usize.checked_shl(self.log_n as u32)?;
+
+        block_len
+            .checked_mul(n)?
+            .checked_add(block_len.checked_mul(self.p as usize)?)
+    }
+}
+
+/// The scrypt password-based key derivation function (RFC 7914), with PBKDF2-HMAC-SHA256 as
+/// the outer layers.
+pub fn scrypt(password: &[u8], salt: &[u8], params: &ScryptParams, out: &mut [u8]) -> Result<()> {
+    match params.memory_required() {
+        Some(mem) if mem <= params.max_memory => (),
+        _ => return Err(Error(Sha2Corrupted::BadParam)),
+    }
+
+    let r = params.r as usize;
+    let n = 1usize << params.log_n;
+    let block_words = 32 * r;
+
+    let mut b = vec![0u8; 128 * r * params.p as usize];
+    pbkdf2::<Sha256Params>(password, salt, 1, &mut b)?;
+
+    let mut x = vec![0u32; block_words];
+    let mut y = vec![0u32; block_words];
+    let mut v = vec![0u32; block_words * n];
+
+    for b_block in b.chunks_mut(128 * r) {
+        for (word, bytes) in x.iter_mut().zip(b_block.chunks(4)) {
+            *word = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+
+        ro_mix(&mut x, &mut y, &mut v, n);
+
+        for (bytes, word) in b_block.chunks_mut(4).zip(x.iter()) {
+            bytes.copy_from_slice(&word.to_le_bytes());
+        }
+    }
+
+    let res = pbkdf2::<Sha256Params>(password, &b, 1, out);
+
+    wipe_slice(&mut b, 0);
+    wipe_slice(&mut x, 0);
+    wipe_slice(&mut y, 0);
+    wipe_slice(&mut v, 0);
+    res
+}
+
+fn ro_mix(x: &mut [u32], y: &mut [u32], v: &mut [u32], n: usize) {
+    let block_words = x.len();
+
+    for v_block in v.chunks_mut(block_words) {
+        v_block.copy_from_slice(x);
+        block_mix(x, y);
+    }
+
+    for _ in 0..n {
+        let j = integerify(x) & (n as u64 - 1);
+        let v_block = &v[(j as usize * block_words)..][..block_words];
+
+        for (x_word, v_word) in x.iter_mut().zip(v_block) {
+            *x_word ^= v_word;
+        }
+
+        block_mix(x, y);
+    }
+}
+
+fn integerify(x: &[u32]) -> u64 {
+    let last = &x[(x.len() - 16)..];
+    (last[0] as u64) | ((last[1] as u64) << 32)
+}
+
+/// scryptBlockMix with Salsa20/8 on `b`; `y` is scratch space of the same size.
+fn block_mix(b: &mut [u32], y: &mut [u32]) {
+    let r = b.len() / 32;
+    let mut x = [0u32; 16];
+    x.copy_from_slice(&b[(b.len() - 16)..]);
+
+    for (i, b_block) in b.chunks(16).enumerate() {
+        for (x_word, b_word) in x.iter_mut().zip(b_block) {
+            *x_word ^= b_word;
+        }
+
+        salsa20_8(&mut x);
+
+        // even blocks go to the first half of the output, odd blocks to the second half
+        let dst = (i / 2 + (i % 2) * r) * 16;
+        y[dst..(dst + 16)].copy_from_slice(&x);
+    }
+
+    b.copy_from_slice(y);
+}
+
+fn salsa20_8(block: &mut [u32; 16]) {
+    const COLUMNS: [[usize; 4]; 4] = [[0, 4, 8, 12], [5, 9, 13, 1], [10, 14, 2, 6], [15, 3, 7, 11]];
+    const ROWS: [[usize; 4]; 4] = [[0, 1, 2, 3], [5, 6, 7, 4], [10, 11, 8, 9], [15, 12, 13, 14]];
+
+    let mut x = *block;
+
+    for _ in 0..4 {
+        for [a, b, c, d] in COLUMNS.into_iter().chain(ROWS) {
+            x[b] ^= x[a].wrapping_add(x[d]).rotate_left(7);
+            x[c] ^= x[b].wrapping_add(x[a]).rotate_left(9);
+            x[d] ^= x[c].wrapping_add(x[b]).rotate_left(13);
+            x[a] ^= x[d].wrapping_add(x[c]).rotate_left(18);
+        }
+    }
+
+    for (block_word, x_word) in block.iter_mut().zip(x) {
+        *block_word = block_word.wrapping_add(x_word);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{scrypt, ScryptParams};
+    use crate::error::Result;
+
+    use hex_literal::hex;
+
+    // RFC 7914, section 12
+    #[test]
+    fn test_rfc7914_vector1() -> Result<()> {
+        let mut out = [0u8; 64];
+        scrypt(b"", b"", &ScryptParams::new(4, 1, 1)?, &mut out)?;
+        assert_eq!(out, hex!("77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442fcd0069ded0948f8326a753a0fc81f17e8d3e0fb2e0d3628cf35e20c38d18906"));
+        Ok(())
+    }
+
+    // RFC 7914, section 12
+    #[test]
+    fn test_rfc7914_vector2() -> Result<()> {
+        let mut out = [0u8; 64];
+        scrypt(
+            b"password",
+            b"NaCl",
+            &ScryptParams::new(10, 8, 16)?,
+            &mut out,
+        )?;
+        assert_eq!(out, hex!("fdbabe1c9d3472007856e7190d01e9fe7c6ad7cbc8237830e77376634b3731622eaf30d92e22a3886ff109279d9830dac727afb94a83ee6d8360cbdfa2cc0640"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parallel_short_output() -> Result<()> {
+        let mut out = [0u8; 40];
+        scrypt(b"pw", b"salt", &ScryptParams::new(1, 1, 3)?, &mut out)?;
+        assert_eq!(
+            out,
+            hex!(
+                "8a97c9b0b980eed67aacb552a3ab082ce004c70d55a2a201ca500e1790d7af6ce8e8b3ca27515b49"
+            )
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_param_validation() {
+        assert!(ScryptParams::new(0, 8, 1).is_err());
+        assert!(ScryptParams::new(10, 0, 1).is_err());
+        assert!(ScryptParams::new(10, 8, 0).is_err());
+        assert!(ScryptParams::new(16, 1, 1).is_err());
+        assert!(ScryptParams::new(15, 1, 1).is_ok());
+        assert!(ScryptParams::new(10, 1 << 15, 1 << 15).is_err());
+    }
+
+    #[test]
+    fn test_memory_limit() -> Result<()> {
+        let mut out = [0u8; 32];
+        let params = ScryptParams::new(14, 8, 1)?;
+        assert_eq!(params.memory_required(), Some(16 * 1024 * 1024 + 1024));
+
+        let params = params.with_max_memory(1024 * 1024);
+        assert!(scrypt(b"password", b"salt", &params, &mut out).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,366 @@
+use crate::algorithm::Sha2Algorithm;
+use crate::context::Sha2Context;
+use crate::error::{Error, Result, Sha2Corrupted};
+use crate::ops::ct_eq;
+use crate::params::{Sha256Params, Sha2Params, Sha512Params};
+use crate::wipe::wipe_slice;
+
+pub const SHA_CRYPT_ROUNDS_DEFAULT: u32 = 5000;
+pub const SHA_CRYPT_ROUNDS_MIN: u32 = 1000;
+pub const SHA_CRYPT_ROUNDS_MAX: u32 = 999_999_999;
+pub const SHA_CRYPT_SALT_MAX: usize = 16;
+
+const ROUNDS_PREFIX: &str = "rounds=";
+
+const CRYPT_ALPHABET: &[u8; 64] =
+    b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// Byte triples of the final digest, in output order, for `$5$`.
+const SHA256_ORDER: [(usize, usize, usize); 10] = [
+    (0, 10, 20),
+    (21, 1, 11),
+    (12, 22, 2),
+    (3, 13, 23),
+    (24, 4, 14),
+    (15, 25, 5),
+    (6, 16, 26),
+    (27, 7, 17),
+    (18, 28, 8),
+    (9, 19, 29),
+];
+
+/// Byte triples of the final digest, in output order, for `$6$`.
+const SHA512_ORDER: [(usize, usize, usize); 21] = [
+    (0, 21, 42),
+    (22, 43, 1),
+    (44, 2, 23),
+    (3, 24, 45),
+    (25, 46, 4),
+    (47, 5, 26),
+    (6, 27, 48),
+    (28, 49, 7),
+    (50, 8, 29),
+    (9, 30, 51),
+    (31, 52, 10),
+    (53, 11, 32),
+    (12, 33, 54),
+    (34, 55, 13),
+    (56, 14, 35),
+    (15, 36, 57),
+    (37, 58, 16),
+    (59, 17, 38),
+    (18, 39, 60),
+    (40, 61, 19),
+    (62, 20, 41),
+];
+
+/// The parsed `$5$[rounds=<n>$]<salt>` prefix of a SHA-crypt string.
+struct Setting<'a> {
+    algorithm: Sha2Algorithm,
+    rounds: Option<u32>,
+    salt: &'a [u8],
+    hash: Option<&'a str>,
+}
+
+impl<'a> Setting<'a> {
+    fn parse(s: &'a str) -> Result<Self> {
+        let (algorithm, rest) = if let Some(rest) = s.strip_prefix("$5$") {
+            (Sha2Algorithm::Sha256, rest)
+        } else if let Some(rest) = s.strip_prefix("$6$") {
+            (Sha2Algorithm::Sha512, rest)
+        } else {
+            return Err(Error(Sha2Corrupted::BadParam));
+        };
+
+        let (rounds, rest) = match rest.strip_prefix(ROUNDS_PREFIX) {
+            Some(rounds_rest) => {
+                let (rounds, rest) = rounds_rest
+                    .split_once('$')
+                    .ok_or(Error(Sha2Corrupted::BadParam))?;
+
+                if rounds.is_empty() || !rounds.bytes().all(|c| c.is_ascii_digit()) {
+                    return Err(Error(Sha2Corrupted::BadParam));
+                }
+
+                // out of range values are clamped, like glibc does
+                let rounds = rounds.parse::<u64>().unwrap_or(u64::MAX);
+                let rounds = rounds.clamp(SHA_CRYPT_ROUNDS_MIN as u64, SHA_CRYPT_ROUNDS_MAX as u64);
+                (Some(rounds as u32), rest)
+            }
+            None => (None, rest),
+        };
+
+        let (salt, hash) = match rest.split_once('$') {
+            Some((salt, hash)) => (salt, Some(hash)),
+            None => (rest, None),
+        };
+
+        let salt = salt.as_bytes();
+        let salt = &salt[..usize::min(salt.len(), SHA_CRYPT_SALT_MAX)];
+
+        Ok(Setting {
+            algorithm,
+            rounds,
+            salt,
+            hash,
+        })
+    }
+}
+
+/// Hashes `password` with the SHA-crypt algorithm (`$5$` for SHA-256, `$6$` for SHA-512) as
+/// specified by Ulrich Drepper and implemented by glibc's `crypt(3)`.
+///
+/// `setting` is a prefix such as `$6$rounds=10000$saltstring` or a complete hash, whose
+/// algorithm, rounds and salt are reused. Salts longer than 16 bytes are truncated and rounds
+/// are clamped to the allowed range.
+pub fn sha_crypt(password: &[u8], setting: &str) -> Result<String> {
+    let setting = Setting::parse(setting)?;
+
+    match setting.algorithm {
+        Sha2Algorithm::Sha256 => {
+            sha_crypt_with::<Sha256Params>(password, setting.salt, setting.rounds, &SHA256_ORDER)
+        }
+        Sha2Algorithm::Sha512 => {
+            sha_crypt_with::<Sha512Params>(password, setting.salt, setting.rounds, &SHA512_ORDER)
+        }
+        _ => Err(Error(Sha2Corrupted::BadParam)),
+    }
+}
+
+/// Checks `password` against a complete `$5$` or `$6$` hash string.
+pub fn sha_crypt_verify(password: &[u8], hash: &str) -> Result<bool> {
+    if Setting::parse(hash)?.hash.is_none() {
+        return Err(Error(Sha2Corrupted::BadParam));
+    }
+
+    let computed = sha_crypt(password, hash)?;
+    Ok(ct_eq(computed.as_bytes(), hash.as_bytes()))
+}
+
+fn sha_crypt_with<P: Sha2Params>(
+    password: &[u8],
+    salt: &[u8],
+    rounds: Option<u32>,
+    order: &[(usize, usize, usize)],
+) -> Result<String> {
+    let digest_len = P::HASH_LEN_BYTES;
+
+    // digest B = H(password || salt || password)
+    let mut alt = P::new_digest();
+    let mut ctx: Sha2Context<P> = Sha2Context::new();
+    ctx.input(password)?;
+    ctx.input(salt)?;
+    ctx.input(password)?;
+    ctx.result(&mut alt)?;
+
+    // digest A
+    let mut ctx: Sha2Context<P> = Sha2Context::new();
+    ctx.input(password)?;
+    ctx.input(salt)?;
+
+    for chunk in password.chunks(digest_len) {
+        ctx.input(&alt.as_ref()[..chunk.len()])?;
+    }
+
+    let mut len = password.len();
+    while len > 0 {
+        if len & 1 != 0 {
+            ctx.input(alt.as_ref())?;
+        } else {
+            ctx.input(password)?;
+        }
+        len >>= 1;
+    }
+
+    let mut a = P::new_digest();
+    ctx.result(&mut a)?;
+
+    // byte sequence P from digest DP
+    let mut dp = P::new_digest();
+    let mut ctx: Sha2Context<P> = Sha2Context::new();
+    for _ in 0..password.len() {
+        ctx.input(password)?;
+    }
+    ctx.result(&mut dp)?;
+
+    let mut p_bytes: Vec<u8> = dp
+        .as_ref()
+        .iter()
+        .copied()
+        .cycle()
+        .take(password.len())
+        .collect();
+
+    // byte sequence S from digest DS
+    let mut ds = P::new_digest();
+    let mut ctx: Sha2Context<P> = Sha2Context::new();
+    for _ in 0..(16 + a.as_ref()[0] as usize) {
+        ctx.input(salt)?;
+    }
+    ctx.result(&mut ds)?;
+
+    let s_bytes = &ds.as_ref()[..salt.len()];
+
+    // the rounds
+    let mut c = a;
+    for i in 0..rounds.unwrap_or(SHA_CRYPT_ROUNDS_DEFAULT) {
+        let mut ctx: Sha2Context<P> = Sha2Context::new();
+
+        if i & 1 != 0 {
+            ctx.input(&p_bytes)?;
+        } else {
+            ctx.input(c.as_ref())?;
+        }
+
+        if i % 3 != 0 {
+            ctx.input(s_bytes)?;
+        }
+
+        if i % 7 != 0 {
+            ctx.input(&p_bytes)?;
+        }
+
+        if i & 1 != 0 {
+            ctx.input(c.as_ref())?;
+        } else {
+            ctx.input(&p_bytes)?;
+        }
+
+        ctx.result(&mut c)?;
+    }
+
+    let mut out = String::new();
+    out.push_str(match P::ALGORITHM {
+        Sha2Algorithm::Sha256 => "$5$",
+        _ => "$6$",
+    });
+
+    if let Some(rounds) = rounds {
+        out.push_str(&format!("{ROUNDS_PREFIX}{rounds}$"));
+    }
+
+    // the salt may be arbitrary bytes, but in practice it is always ASCII
+    out.push_str(&String::from_utf8_lossy(salt));
+    out.push('$');
+
+    let c_bytes = c.as_ref();
+    for &(b2, b1, b0) in order {
+        encode_24bit(&mut out, c_bytes[b2], c_bytes[b1], c_bytes[b0], 4);
+    }
+
+    if digest_len == 32 {
+        encode_24bit(&mut out, 0, c_bytes[31], c_bytes[30], 3);
+    } else {
+        encode_24bit(&mut out, 0, 0, c_bytes[63], 2);
+    }
+
+    wipe_slice(alt.as_mut(), 0);
+    wipe_slice(c.as_mut(), 0);
+    wipe_slice(dp.as_mut(), 0);
+    wipe_slice(ds.as_mut(), 0);
+    wipe_slice(&mut p_bytes, 0);
+
+    Ok(out)
+}
+
+fn encode_24bit(out: &mut String, b2: u8, b1: u8, b0: u8, n: usize) {
+    let mut w = ((b2 as u32) << 16) | ((b1 as u32) << 8) | (b0 as u32);
+
+    for _ in 0..n {
+        out.push(CRYPT_ALPHABET[(w & 0x3f) as usize] as char);
+        w >>= 6;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sha_crypt, sha_crypt_verify};
+    use crate::error::Result;
+
+    // test vectors from the SHA-crypt specification
+    #[test]
+    fn test_sha256_crypt() -> Result<()> {
+        let vectors = [
+            (
+                "$5$saltstring",
+                "Hello world!",
+                "$5$saltstring$5B8vYYiY.CVt1RlTTf8KbXBH3hsxY/GNooZaBBGWEc5",
+            ),
+            (
+                "$5$rounds=10000$saltstringsaltstring",
+                "Hello world!",
+                "$5$rounds=10000$saltstringsaltst$3xv.VbSHBb41AL9AvLeujZkZRBAwqFMz2.opqey6IcA",
+            ),
+            (
+                "$5$rounds=5000$toolongsaltstring",
+                "This is just a test",
+                "$5$rounds=5000$toolongsaltstrin$Un/5jzAHMgOGZ5.mWJpuVolil07guHPvOW8mGRcvxa5",
+            ),
+            (
+                "$5$rounds=1400$anotherlongsaltstring",
+                "a very much longer text to encrypt.  This one even stretches over morethan one line.",
+                "$5$rounds=1400$anotherlongsalts$Rx.j8H.h8HjEDGomFU8bDkXm3XIUnzyxf12oP84Bnq1",
+            ),
+            (
+                "$5$rounds=77777$short",
+                "we have a short salt string but not a short password",
+                "$5$rounds=77777$short$JiO1O3ZpDAxGJeaDIuqCoEFysAe1mZNJRs3pw0KQRd/",
+            ),
+            (
+                "$5$rounds=10$roundstoolow",
+                "the minimum number is still observed",
+                "$5$rounds=1000$roundstoolow$yfvwcWrQ8l/K0DAWyuPMDNHpIVlTQebY9l/gL972bIC",
+            ),
+        ];
+
+        for (setting, password, expected) in vectors {
+            assert_eq!(sha_crypt(password.as_bytes(), setting)?, expected);
+            assert!(sha_crypt_verify(password.as_bytes(), expected)?);
+        }
+        Ok(())
+    }
+
+    // test vectors from the SHA-crypt specification
+    #[test]
+    fn test_sha512_crypt() -> Result<()> {
+        let vectors = [
+            (
+                "$6$saltstring",
+                "Hello world!",
+                "$6$saltstring$svn8UoSVapNtMuq1ukKS4tPQd8iKwSMHWjl/O817G3uBnIFNjnQJuesI68u4OTLiBFdcbYEdFCoEOfaS35inz1",
+            ),
+            (
+                "$6$rounds=10000$saltstringsaltstring",
+                "Hello world!",
+                "$6$rounds=10000$saltstringsaltst$OW1/O6BYHV6BcXZu8QVeXbDWra3Oeqh0sbHbbMCVNSnCM/UrjmM0Dp8vOuZeHBy/YTBmSK6H9qs/y3RnOaw5v.",
+            ),
+            (
+                "$6$rounds=1400$anotherlongsaltstring",
+                "a very much longer text to encrypt.  This one even stretches over morethan one line.",
+                "$6$rounds=1400$anotherlongsalts$POfYwTEok97VWcjxIiSOjiykti.o/pQs.wPvMxQ6Fm7I6IoYN3CmLs66x9t0oSwbtEW7o7UmJEiDwGqd8p4ur1",
+            ),
+            (
+                "$6$rounds=10$roundstoolow",
+                "the minimum number is still observed",
+                "$6$rounds=1000$roundstoolow$kUMsbe306n21p9R.FRkW3IGn.S9NPN0x50YhH1xhLsPuWGsUSklZt58jaTfF4ZEQpyUNGc0dqbpBYYBaHHrsX.",
+            ),
+        ];
+
+        for (setting, password, expected) in vectors {
+            assert_eq!(sha_crypt(password.as_bytes(), setting)?, expected);
+            assert!(sha_crypt_verify(password.as_bytes(), expected)?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify() -> Result<()> {
+        let hash = "$5$saltstring$5B8vYYiY.CVt1RlTTf8KbXBH3hsxY/GNooZaBBGWEc5";
+
+        assert!(!sha_crypt_verify(b"Hello world?", hash)?);
+        assert!(sha_crypt_verify(b"Hello world!", "$5$saltstring").is_err());
+        assert!(sha_crypt_verify(b"Hello world!", "$1$saltstring$abc").is_err());
+        assert!(sha_crypt_verify(b"Hello world!", "$5$rounds=x$salt$abc").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,1224 @@
+use crate::algorithm::Sha2Algorithm;
+use crate::any::AnySha2;
+use crate::context::Sha2Context;
+use crate::error::{Error, Result, Sha2Corrupted};
+use crate::hmac::Hmac;
+use crate::params::{Sha256Params, Sha2Params, Sha512Params};
+use crate::pkcs1::mgf1;
+use crate::wipe::wipe_slice;
+
+/// Maximum length of the context string of [`SlhDsaPrivateKey::sign`].
+pub const SLH_DSA_MAX_CONTEXT_LEN: usize = 255;
+
+const MAX_N: usize = 32;
+
+const ADRS_WOTS_HASH: u32 = 0;
+const ADRS_WOTS_PK: u32 = 1;
+const ADRS_TREE: u32 = 2;
+const ADRS_FORS_TREE: u32 = 3;
+const ADRS_FORS_ROOTS: u32 = 4;
+const ADRS_WOTS_PRF: u32 = 5;
+const ADRS_FORS_PRF: u32 = 6;
+
+/// The SHA-2 parameter sets of FIPS 205, section 11: `s` for small signatures, `f` for fast
+/// signing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SlhDsaType {
+    Sha2_128s,
+    Sha2_128f,
+    Sha2_192s,
+    Sha2_192f,
+    Sha2_256s,
+    Sha2_256f,
+}
+
+impl SlhDsaType {
+    pub const ALL: [SlhDsaType; 6] = [
+        SlhDsaType::Sha2_128s,
+        SlhDsaType::Sha2_128f,
+        SlhDsaType::Sha2_192s,
+        SlhDsaType::Sha2_192f,
+        SlhDsaType::Sha2_256s,
+        SlhDsaType::Sha2_256f,
+    ];
+
+    /// The name used by FIPS 205 and ACVP, e.g. `"SLH-DSA-SHA2-128s"`.
+    pub fn name(self) -> &'static str {
+        match self {
+            SlhDsaType::Sha2_128s => "SLH-DSA-SHA2-128s",
+            SlhDsaType::Sha2_128f => "SLH-DSA-SHA2-128f",
+            SlhDsaType::Sha2_192s => "SLH-DSA-SHA2-192s",
+            SlhDsaType::Sha2_192f => "SLH-DSA-SHA2-192f",
+            SlhDsaType::Sha2_256s => "SLH-DSA-SHA2-256s",
+            SlhDsaType::Sha2_256f => "SLH-DSA-SHA2-256f",
+        }
+    }
+
+    /// Length of seeds, hashes and tree nodes in bytes.
+    pub fn n(self) -> usize {
+        self.params().n
+    }
+
+    pub fn public_key_len(self) -> usize {
+        2 * self.n()
+    }
+
+    pub fn private_key_len(self) -> usize {
+        4 * self.n()
+    }
+
+    pub fn signature_len(self) -> usize {
+        self.params().signature_len()
+    }
+
+    fn params(self) -> Params {
+        let (n, h, d, a, k, m) = match self {
+            SlhDsaType::Sha2_128s => (16, 63, 7, 12, 14, 30),
+            SlhDsaType::Sha2_128f => (16, 66, 22, 6, 33, 34),
+            SlhDsaType::Sha2_192s => (24, 63, 7, 14, 17, 39),
+            SlhDsaType::Sha2_192f => (24, 66, 22, 8, 33, 42),
+            SlhDsaType::Sha2_256s => (32, 64, 8, 14, 22, 47),
+            SlhDsaType::Sha2_256f => (32, 68, 17, 9, 35, 49),
+        };
+
+        Params {
+            n,
+            h,
+            d,
+            hp: h / d,
+            a,
+            k,
+            m,
+        }
+    }
+}
+
+impl std::str::FromStr for SlhDsaType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.trim();
+
+        SlhDsaType::ALL
+            .into_iter()
+            .find(|t| t.name().eq_ignore_ascii_case(s))
+            .ok_or(Error(Sha2Corrupted::BadParam))
+    }
+}
+
+impl std::fmt::Display for SlhDsaType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// An SLH-DSA private key (FIPS 205): `SK.seed || SK.prf || PK.seed || PK.root`.
+///
+/// Signing is stateless, so unlike [`LmsPrivateKey`](crate::LmsPrivateKey) the key can sign any
+/// number of messages, but a signature with an `s` parameter set takes millions of hashes.
+pub struct SlhDsaPrivateKey {
+    slh_dsa_type: SlhDsaType,
+    bytes: Vec<u8>,
+}
+
+/// An SLH-DSA public key: `PK.seed || PK.root`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlhDsaPublicKey {
+    slh_dsa_type: SlhDsaType,
+    bytes: Vec<u8>,
+}
+
+impl SlhDsaPrivateKey {
+    /// Generates the key from its three `n`-byte seeds (`slh_keygen_internal`, FIPS 205
+    /// algorithm 18), which the caller draws from an approved random bit generator.
+    pub fn from_seeds(
+        slh_dsa_type: SlhDsaType,
+        sk_seed: &[u8],
+        sk_prf: &[u8],
+        pk_seed: &[u8],
+    ) -> Result<Self> {
+        let p = slh_dsa_type.params();
+
+        if [sk_seed, sk_prf, pk_seed].iter().any(|s| s.len() != p.n) {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        let instance = Instance::new(p, pk_seed)?;
+        let top = instance.xmss_tree(sk_seed, Adrs::new(p.d as u32 - 1, 0))?;
+
+        let mut bytes = Vec::with_capacity(4 * p.n);
+        bytes.extend_from_slice(sk_seed);
+        bytes.extend_from_slice(sk_prf);
+        bytes.extend_from_slice(pk_seed);
+        bytes.extend_from_slice(&top[p.n..2 * p.n]);
+
+        Ok(SlhDsaPrivateKey {
+            slh_dsa_type,
+            bytes,
+        })
+    }
+
+    pub fn slh_dsa_type(&self) -> SlhDsaType {
+        self.slh_dsa_type
+    }
+
+    pub fn public_key(&self) -> SlhDsaPublicKey {
+        let n = self.slh_dsa_type.n();
+
+        SlhDsaPublicKey {
+            slh_dsa_type: self.slh_dsa_type,
+            bytes: self.bytes[2 * n..].to_vec(),
+        }
+    }
+
+    /// Signs `msg` under the context string `ctx` (pure SLH-DSA, FIPS 205 algorithm 22).
+    ///
+    /// With `addrnd`, `n` fresh random bytes, the signature is hedged; without it, it is
+    /// deterministic.
+    pub fn sign(&self, msg: &[u8], ctx: &[u8], addrnd: Option<&[u8]>) -> Result<Vec<u8>> {
+        if ctx.len() > SLH_DSA_MAX_CONTEXT_LEN {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        self.sign_parts(&[&[0, ctx.len() as u8], ctx, msg], addrnd)
+    }
+
+    /// Signs the `ph` digest of `msg` under the context string `ctx` (HashSLH-DSA, FIPS 205
+    /// algorithm 23).
+    pub fn sign_prehash(
+        &self,
+        msg: &[u8],
+        ctx: &[u8],
+        ph: Sha2Algorithm,
+        addrnd: Option<&[u8]>,
+    ) -> Result<Vec<u8>> {
+        if ctx.len() > SLH_DSA_MAX_CONTEXT_LEN {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        let digest = prehash(msg, ph)?;
+        self.sign_parts(&[&[1, ctx.len() as u8], ctx, &oid_der(ph), &digest], addrnd)
+    }
+
+    /// Signs `msg` as is (`slh_sign_internal`, FIPS 205 algorithm 19), the interface ACVP tests.
+    pub fn sign_internal(&self, msg: &[u8], addrnd: Option<&[u8]>) -> Result<Vec<u8>> {
+        self.sign_parts(&[msg], addrnd)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn from_bytes(slh_dsa_type: SlhDsaType, bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != slh_dsa_type.private_key_len() {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        Ok(SlhDsaPrivateKey {
+            slh_dsa_type,
+            bytes: bytes.to_vec(),
+        })
+    }
+
+    fn sign_parts(&self, msg: &[&[u8]], addrnd: Option<&[u8]>) -> Result<Vec<u8>> {
+        let p = self.slh_dsa_type.params();
+        let n = p.n;
+
+        let (sk_seed, rest) = self.bytes.split_at(n);
+        let (sk_prf, public) = rest.split_at(n);
+        let (pk_seed, pk_root) = public.split_at(n);
+
+        let opt_rand = addrnd.unwrap_or(pk_seed);
+        if opt_rand.len() != n {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        let mut signature = vec![0u8; n];
+        p.prf_msg(sk_prf, opt_rand, msg, &mut signature)?;
+
+        let mut digest = vec![0u8; p.m];
+        p.h_msg(&signature, pk_seed, pk_root, msg, &mut digest)?;
+        let (md, tree, leaf) = p.split_digest(&digest);
+
+        let instance = Instance::new(p, pk_seed)?;
+        let adrs = Adrs::new(0, tree).with_type(ADRS_FORS_TREE, leaf);
+        let pk_fors = instance.fors_sign(md, sk_seed, adrs, &mut signature)?;
+        instance.ht_sign(&pk_fors, sk_seed, tree, leaf, &mut signature)?;
+
+        Ok(signature)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for SlhDsaPrivateKey {
+    fn drop(&mut self) {
+        wipe_slice(&mut self.bytes, 0);
+    }
+}
+
+impl SlhDsaPublicKey {
+    pub fn slh_dsa_type(&self) -> SlhDsaType {
+        self.slh_dsa_type
+    }
+
+    /// Verifies a pure SLH-DSA signature (FIPS 205 algorithm 24). Malformed signatures and
+    /// context strings that are too long are reported as `Ok(false)`.
+    pub fn verify(&self, msg: &[u8], ctx: &[u8], signature: &[u8]) -> Result<bool> {
+        if ctx.len() > SLH_DSA_MAX_CONTEXT_LEN {
+            return Ok(false);
+        }
+
+        self.verify_parts(&[&[0, ctx.len() as u8], ctx, msg], signature)
+    }
+
+    /// Verifies a HashSLH-DSA signature (FIPS 205 algorithm 25) over the `ph` digest of `msg`.
+    pub fn verify_prehash(
+        &self,
+        msg: &[u8],
+        ctx: &[u8],
+        ph: Sha2Algorithm,
+        signature: &[u8],
+    ) -> Result<bool> {
+        if ctx.len() > SLH_DSA_MAX_CONTEXT_LEN {
+            return Ok(false);
+        }
+
+        let digest = prehash(msg, ph)?;
+        self.verify_parts(
+            &[&[1, ctx.len() as u8], ctx, &oid_der(ph), &digest],
+            signature,
+        )
+    }
+
+    /// Verifies a signature over `msg` as is (`slh_verify_internal`, FIPS 205 algorithm 20).
+    pub fn verify_internal(&self, msg: &[u8], signature: &[u8]) -> Result<bool> {
+        self.verify_parts(&[msg], signature)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn from_bytes(slh_dsa_type: SlhDsaType, bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != slh_dsa_type.public_key_len() {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        Ok(SlhDsaPublicKey {
+            slh_dsa_type,
+            bytes: bytes.to_vec(),
+        })
+    }
+
+    fn verify_parts(&self, msg: &[&[u8]], signature: &[u8]) -> Result<bool> {
+        let p = self.slh_dsa_type.params();
+        let n = p.n;
+
+        if signature.len() != p.signature_len() {
+            return Ok(false);
+        }
+
+        let (pk_seed, pk_root) = self.bytes.split_at(n);
+        let (r, rest) = signature.split_at(n);
+        let (sig_fors, sig_ht) = rest.split_at(p.fors_signature_len());
+
+        let mut digest = vec![0u8; p.m];
+        p.h_msg(r, pk_seed, pk_root, msg, &mut digest)?;
+        let (md, tree, leaf) = p.split_digest(&digest);
+
+        let instance = Instance::new(p, pk_seed)?;
+        let adrs = Adrs::new(0, tree).with_type(ADRS_FORS_TREE, leaf);
+        let mut pk_fors = [0u8; MAX_N];
+        instance.fors_pk_from_sig(sig_fors, md, adrs, &mut pk_fors[..n])?;
+
+        instance.ht_verify(&pk_fors[..n], sig_ht, tree, leaf, pk_root)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Params {
+    n: usize,
+    h: usize,
+    d: usize,
+    hp: usize,
+    a: usize,
+    k: usize,
+    m: usize,
+}
+
+impl Params {
+    /// Number of WOTS+ chains: `2n` message digits and 3 checksum digits, with `w = 16`.
+    fn wots_len(self) -> usize {
+        2 * self.n + 3
+    }
+
+    fn xmss_signature_len(self) -> usize {
+        (self.wots_len() + self.hp) * self.n
+    }
+
+    fn fors_signature_len(self) -> usize {
+        self.k * (self.a + 1) * self.n
+    }
+
+    fn signature_len(self) -> usize {
+        self.n + self.fors_signature_len() + self.d * self.xmss_signature_len()
+    }
+
+    /// Security category 1 uses SHA-256 throughout; categories 3 and 5 use SHA-512 for
+    /// everything but `F` and `PRF`.
+    fn uses_sha512(self) -> bool {
+        self.n > 16
+    }
+
+    /// `PRF_msg(SK.prf, opt_rand, M)`: HMAC truncated to `n` bytes.
+    fn prf_msg(self, sk_prf: &[u8], opt_rand: &[u8], msg: &[&[u8]], out: &mut [u8]) -> Result<()> {
+        if self.uses_sha512() {
+            prf_msg::<Sha512Params>(sk_prf, opt_rand, msg, out)
+        } else {
+            prf_msg::<Sha256Params>(sk_prf, opt_rand, msg, out)
+        }
+    }
+
+    /// `H_msg(R, PK.seed, PK.root, M)`: MGF1 over `R || PK.seed || H(R || PK.seed || PK.root || M)`.
+    fn h_msg(
+        self,
+        r: &[u8],
+        pk_seed: &[u8],
+        pk_root: &[u8],
+        msg: &[&[u8]],
+        out: &mut [u8],
+    ) -> Result<()> {
+        if self.uses_sha512() {
+            h_msg::<Sha512Params>(r, pk_seed, pk_root, msg, out)
+        } else {
+            h_msg::<Sha256Params>(r, pk_seed, pk_root, msg, out)
+        }
+    }
+
+    /// Splits the message digest into the FORS message and the indices of the hypertree leaf
+    /// that signs it (FIPS 205 algorithm 19, lines 7 to 12).
+    fn split_digest(self, digest: &[u8]) -> (&[u8], u64, u32) {
+        let (md, rest) = digest.split_at((self.k * self.a).div_ceil(8));
+        let (tree, rest) = rest.split_at((self.h - self.hp).div_ceil(8));
+        let leaf = &rest[..self.hp.div_ceil(8)];
+
+        let tree = read_be(tree) & (u64::MAX >> (64 - (self.h - self.hp)));
+        let leaf = read_be(leaf) & ((1 << self.hp) - 1);
+        (md, tree, leaf as u32)
+    }
+}
+
+/// The 32-byte address of FIPS 205, section 4.2, kept as its fields.
+#[derive(Clone, Copy)]
+struct Adrs {
+    layer: u32,
+    tree: u64,
+    adrs_type: u32,
+    words: [u32; 3],
+}
+
+impl Adrs {
+    fn new(layer: u32, tree: u64) -> Self {
+        Adrs {
+            layer,
+            tree,
+            adrs_type: ADRS_WOTS_HASH,
+            words: [0; 3],
+        }
+    }
+
+    /// `setTypeAndClear` followed by `setKeyPairAddress`.
+    fn with_type(self, adrs_type: u32, key_pair: u32) -> Self {
+        Adrs {
+            adrs_type,
+            words: [key_pair, 0, 0],
+            ..self
+        }
+    }
+
+    fn key_pair(self) -> u32 {
+        self.words[0]
+    }
+
+    /// Sets the chain address or the tree height.
+    fn with_word1(mut self, value: u32) -> Self {
+        self.words[1] = value;
+        self
+    }
+
+    /// Sets the hash address or the tree index.
+    fn with_word2(mut self, value: u32) -> Self {
+        self.words[2] = value;
+        self
+    }
+
+    /// The 22-byte compressed address of the SHA-2 instantiation (FIPS 205, section 11.2).
+    fn compressed(self) -> [u8; 22] {
+        let mut out = [0u8; 22];
+        out[0] = self.layer as u8;
+        out[1..9].copy_from_slice(&self.tree.to_be_bytes());
+        out[9] = self.adrs_type as u8;
+        for (chunk, word) in out[10..].chunks_mut(4).zip(self.words) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        out
+    }
+}
+
+/// The tweakable hash functions `F`, `H`, `T_l` and `PRF` for one `PK.seed`.
+///
+/// All of them hash `PK.seed` padded to a full block first, so the contexts keep that state and
+/// each call only hashes the address and the message.
+struct Instance {
+    p: Params,
+    seeded_sha256: Sha2Context<Sha256Params>,
+    seeded_sha512: Option<Sha2Context<Sha512Params>>,
+}
+
+impl Instance {
+    fn new(p: Params, pk_seed: &[u8]) -> Result<Self> {
+        let mut seeded_sha256: Sha2Context<Sha256Params> = Sha2Context::new();
+        seeded_sha256.input(pk_seed)?;
+        seeded_sha256.input(&[0; 64][p.n..])?;
+
+        let seeded_sha512 = if p.uses_sha512() {
+            let mut ctx: Sha2Context<Sha512Params> = Sha2Context::new();
+            ctx.input(pk_seed)?;
+            ctx.input(&[0; 128][p.n..])?;
+            Some(ctx)
+        } else {
+            None
+        };
+
+        Ok(Instance {
+            p,
+            seeded_sha256,
+            seeded_sha512,
+        })
+    }
+
+    /// `F`, and `PRF` with `SK.seed` as the message.
+    fn f(&self, adrs: Adrs, msg: &[u8], out: &mut [u8]) -> Result<()> {
+        tweak(&self.seeded_sha256, adrs, &[msg], out)
+    }
+
+    /// `H` and `T_l`.
+    fn h(&self, adrs: Adrs, msg: &[&[u8]], out: &mut [u8]) -> Result<()> {
+        match &self.seeded_sha512 {
+            Some(seeded) => tweak(seeded, adrs, msg, out),
+            None => tweak(&self.seeded_sha256, adrs, msg, out),
+        }
+    }
+
+    /// The WOTS+ chaining function (FIPS 205 algorithm 5), in place.
+    fn chain(&self, x: &mut [u8], start: u32, steps: u32, adrs: Adrs) -> Result<()> {
+        let mut tmp = [0u8; MAX_N];
+
+        for j in start..start + steps {
+            self.f(adrs.with_word2(j), x, &mut tmp[..x.len()])?;
+            x.copy_from_slice(&tmp[..x.len()]);
+        }
+
+        Ok(())
+    }
+
+    /// Secret value `i` of the WOTS+ key at `adrs`.
+    fn wots_secret(&self, sk_seed: &[u8], adrs: Adrs, i: usize, out: &mut [u8]) -> Result<()> {
+        let sk_adrs = adrs
+            .with_type(ADRS_WOTS_PRF, adrs.key_pair())
+            .with_word1(i as u32);
+        self.f(sk_adrs, sk_seed, out)
+    }
+
+    /// Compresses the ends of the WOTS+ chains into the public key with `T_len`.
+    fn wots_compress(&self, ends: &[u8], adrs: Adrs, out: &mut [u8]) -> Result<()> {
+        let pk_adrs = adrs.with_type(ADRS_WOTS_PK, adrs.key_pair());
+        self.h(pk_adrs, &[ends], out)
+    }
+
+    /// FIPS 205 algorithm 6. `adrs` is a WOTS_HASH address with the key pair set.
+    fn wots_public_key(&self, sk_seed: &[u8], adrs: Adrs, out: &mut [u8]) -> Result<()> {
+        let n = self.p.n;
+        let mut ends = vec![0u8; self.p.wots_len() * n];
+
+        for (i, x) in ends.chunks_mut(n).enumerate() {
+            self.wots_secret(sk_seed, adrs, i, x)?;
+            self.chain(x, 0, 15, adrs.with_word1(i as u32))?;
+        }
+
+        self.wots_compress(&ends, adrs, out)
+    }
+
+    /// FIPS 205 algorithm 7, appending the signature to `signature`.
+    fn wots_sign(
+        &self,
+        msg: &[u8],
+        sk_seed: &[u8],
+        adrs: Adrs,
+        signature: &mut Vec<u8>,
+    ) -> Result<()> {
+        let n = self.p.n;
+        let mut x = [0u8; MAX_N];
+
+        for (i, digit) in wots_digits(msg).into_iter().enumerate() {
+            self.wots_secret(sk_seed, adrs, i, &mut x[..n])?;
+            self.chain(&mut x[..n], 0, digit, adrs.with_word1(i as u32))?;
+            signature.extend_from_slice(&x[..n]);
+        }
+
+        wipe_slice(&mut x, 0);
+        Ok(())
+    }
+
+    /// FIPS 205 algorithm 8.
+    fn wots_pk_from_sig(
+        &self,
+        signature: &[u8],
+        msg: &[u8],
+        adrs: Adrs,
+        out: &mut [u8],
+    ) -> Result<()> {
+        let mut ends = signature.to_vec();
+
+        for (i, (x, digit)) in ends.chunks_mut(self.p.n).zip(wots_digits(msg)).enumerate() {
+            self.chain(x, digit, 15 - digit, adrs.with_word1(i as u32))?;
+        }
+
+        self.wots_compress(&ends, adrs, out)
+    }
+
+    /// All nodes of the XMSS tree at `adrs` (layer and tree set), node `r` at `r * n`: the root
+    /// at 1, leaf `i` at `2^h' + i`.
+    fn xmss_tree(&self, sk_seed: &[u8], adrs: Adrs) -> Result<Vec<u8>> {
+        let n = self.p.n;
+        let leaves = 1usize << self.p.hp;
+        let mut nodes = vec![0u8; 2 * leaves * n];
+
+        for (i, leaf) in nodes[leaves * n..].chunks_mut(n).enumerate() {
+            let leaf_adrs = adrs.with_type(ADRS_WOTS_HASH, i as u32);
+            self.wots_public_key(sk_seed, leaf_adrs, leaf)?;
+        }
+
+        for r in (1..leaves).rev() {
+            let level = r.ilog2();
+            let node_adrs = adrs
+                .with_type(ADRS_TREE, 0)
+                .with_word1(self.p.hp as u32 - level)
+                .with_word2((r - (1 << level)) as u32);
+            self.hash_children(&mut nodes, r, node_adrs)?;
+        }
+
+        Ok(nodes)
+    }
+
+    /// Sets node `r` of a tree laid out as in [`Instance::xmss_tree`] to `H` of its children.
+    fn hash_children(&self, nodes: &mut [u8], r: usize, adrs: Adrs) -> Result<()> {
+        let n = self.p.n;
+        let (parents, children) = nodes.split_at_mut(2 * r * n);
+        self.h(
+            adrs,
+            &[&children[..2 * n]],
+            &mut parents[r * n..(r + 1) * n],
+        )
+    }
+
+    /// Signs `msg` with leaf `leaf` of the XMSS tree at `adrs` (FIPS 205 algorithm 10),
+    /// appending the signature and returning the root of the tree.
+    fn xmss_sign(
+        &self,
+        msg: &[u8],
+        sk_seed: &[u8],
+        leaf: u32,
+        adrs: Adrs,
+        signature: &mut Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        let n = self.p.n;
+        let nodes = self.xmss_tree(sk_seed, adrs)?;
+
+        self.wots_sign(
+            msg,
+            sk_seed,
+            adrs.with_type(ADRS_WOTS_HASH, leaf),
+            signature,
+        )?;
+
+        let mut r = (1 << self.p.hp) + leaf as usize;
+        while r > 1 {
+            signature.extend_from_slice(&nodes[(r ^ 1) * n..((r ^ 1) + 1) * n]);
+            r >>= 1;
+        }
+
+        Ok(nodes[n..2 * n].to_vec())
+    }
+
+    /// The root implied by an XMSS signature (FIPS 205 algorithm 11).
+    fn xmss_pk_from_sig(
+        &self,
+        leaf: u32,
+        signature: &[u8],
+        msg: &[u8],
+        adrs: Adrs,
+        out: &mut [u8],
+    ) -> Result<()> {
+        let (wots_signature, auth) = signature.split_at(self.p.wots_len() * self.p.n);
+        self.wots_pk_from_sig(
+            wots_signature,
+            msg,
+            adrs.with_type(ADRS_WOTS_HASH, leaf),
+            out,
+        )?;
+
+        self.climb(out, leaf, auth, adrs.with_type(ADRS_TREE, 0))
+    }
+
+    /// Replaces `node`, the leaf at `index` of the tree at `adrs`, with the root reached along
+    /// the authentication path `auth`.
+    fn climb(&self, node: &mut [u8], mut index: u32, auth: &[u8], adrs: Adrs) -> Result<()> {
+        let n = node.len();
+        let mut tmp = [0u8; MAX_N];
+
+        for (k, sibling) in auth.chunks(n).enumerate() {
+            let node_adrs = adrs.with_word1(k as u32 + 1).with_word2(index >> 1);
+            if index.is_multiple_of(2) {
+                self.h(node_adrs, &[node, sibling], &mut tmp[..n])?;
+            } else {
+                self.h(node_adrs, &[sibling, node], &mut tmp[..n])?;
+            }
+
+            node.copy_from_slice(&tmp[..n]);
+            index >>= 1;
+        }
+
+        Ok(())
+    }
+
+    /// Signs `msg` with the hypertree (FIPS 205 algorithm 12), appending the signature.
+    fn ht_sign(
+        &self,
+        msg: &[u8],
+        sk_seed: &[u8],
+        mut tree: u64,
+        mut leaf: u32,
+        signature: &mut Vec<u8>,
+    ) -> Result<()> {
+        let mut root = self.xmss_sign(msg, sk_seed, leaf, Adrs::new(0, tree), signature)?;
+
+        for layer in 1..self.p.d {
+            leaf = (tree & ((1 << self.p.hp) - 1)) as u32;
+            tree >>= self.p.hp;
+            root = self.xmss_sign(
+                &root,
+                sk_seed,
+                leaf,
+                Adrs::new(layer as u32, tree),
+                signature,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// FIPS 205 algorithm 13.
+    fn ht_verify(
+        &self,
+        msg: &[u8],
+        signature: &[u8],
+        mut tree: u64,
+        mut leaf: u32,
+        pk_root: &[u8],
+    ) -> Result<bool> {
+        let n = self.p.n;
+        let mut node = [0u8; MAX_N];
+        node[..n].copy_from_slice(msg);
+
+        for (layer, xmss_signature) in signature.chunks(self.p.xmss_signature_len()).enumerate() {
+            if layer > 0 {
+                leaf = (tree & ((1 << self.p.hp) - 1)) as u32;
+                tree >>= self.p.hp;
+            }
+
+            let msg = node;
+            self.xmss_pk_from_sig(
+                leaf,
+                xmss_signature,
+                &msg[..n],
+                Adrs::new(layer as u32, tree),
+                &mut node[..n],
+            )?;
+        }
+
+        Ok(node[..n] == *pk_root)
+    }
+
+    /// Signs the FORS message `md` (FIPS 205 algorithm 16), appending the signature and
+    /// returning the FORS public key. `adrs` is the FORS_TREE address of the key pair.
+    fn fors_sign(
+        &self,
+        md: &[u8],
+        sk_seed: &[u8],
+        adrs: Adrs,
+        signature: &mut Vec<u8>,
+    ) -> Result<Vec<u8>> {
+        let (n, a) = (self.p.n, self.p.a);
+        let leaves = 1usize << a;
+        let mut roots = vec![0u8; self.p.k * n];
+        let mut nodes = vec![0u8; 2 * leaves * n];
+        let mut sk = [0u8; MAX_N];
+
+        for (i, (index, root)) in base_2b(md, a, self.p.k)
+            .into_iter()
+            .zip(roots.chunks_mut(n))
+            .enumerate()
+        {
+            for (j, leaf) in nodes[leaves * n..].chunks_mut(n).enumerate() {
+                let leaf_index = ((i << a) + j) as u32;
+                let sk_adrs = adrs
+                    .with_type(ADRS_FORS_PRF, adrs.key_pair())
+                    .with_word2(leaf_index);
+                self.f(sk_adrs, sk_seed, &mut sk[..n])?;
+
+                if j == index as usize {
+                    signature.extend_from_slice(&sk[..n]);
+                }
+
+                self.f(adrs.with_word1(0).with_word2(leaf_index), &sk[..n], leaf)?;
+            }
+
+            // node r of tree i sits at height a - level(r) with index (i << level(r)) + the
+            // offset of r within its level
+            for r in (1..leaves).rev() {
+                let level = r.ilog2() as usize;
+                let node_adrs = adrs
+                    .with_word1((a - level) as u32)
+                    .with_word2(((i << level) + r - (1 << level)) as u32);
+                self.hash_children(&mut nodes, r, node_adrs)?;
+            }
+
+            let mut r = leaves + index as usize;
+            while r > 1 {
+                signature.extend_from_slice(&nodes[(r ^ 1) * n..((r ^ 1) + 1) * n]);
+                r >>= 1;
+            }
+            root.copy_from_slice(&nodes[n..2 * n]);
+        }
+
+        wipe_slice(&mut sk, 0);
+
+        let mut pk = vec![0u8; n];
+        self.h(
+            adrs.with_type(ADRS_FORS_ROOTS, adrs.key_pair()),
+            &[&roots],
+            &mut pk,
+        )?;
+        Ok(pk)
+    }
+
+    /// The FORS public key implied by a FORS signature (FIPS 205 algorithm 17).
+    fn fors_pk_from_sig(
+        &self,
+        signature: &[u8],
+        md: &[u8],
+        adrs: Adrs,
+        out: &mut [u8],
+    ) -> Result<()> {
+        let (n, a) = (self.p.n, self.p.a);
+        let mut roots = vec![0u8; self.p.k * n];
+
+        for (i, ((index, tree_signature), root)) in base_2b(md, a, self.p.k)
+            .into_iter()
+            .zip(signature.chunks((a + 1) * n))
+            .zip(roots.chunks_mut(n))
+            .enumerate()
+        {
+            let (sk, auth) = tree_signature.split_at(n);
+            let leaf_index = ((i << a) as u32) + index;
+            self.f(adrs.with_word1(0).with_word2(leaf_index), sk, root)?;
+            self.climb(root, leaf_index, auth, adrs)?;
+        }
+
+        self.h(
+            adrs.with_type(ADRS_FORS_ROOTS, adrs.key_pair()),
+            &[&roots],
+            out,
+        )
+    }
+}
+
+/// One of the hashes `F`, `H`, `T_l` or `PRF` (FIPS 205, section 11.2): the hash of
+/// `PK.seed || toByte(0, blocksize - n) || ADRSc || msg`, truncated to `n` bytes.
+fn tweak<P: Sha2Params>(
+    seeded: &Sha2Context<P>,
+    adrs: Adrs,
+    msg: &[&[u8]],
+    out: &mut [u8],
+) -> Result<()> {
+    let mut ctx = seeded.clone();
+    ctx.input(&adrs.compressed())?;
+    for part in msg {
+        ctx.input(part)?;
+    }
+
+    let mut digest = P::new_digest();
+    ctx.result(&mut digest)?;
+    out.copy_from_slice(&digest.as_ref()[..out.len()]);
+    Ok(())
+}
+
+fn prf_msg<P: Sha2Params>(
+    sk_prf: &[u8],
+    opt_rand: &[u8],
+    msg: &[&[u8]],
+    out: &mut [u8],
+) -> Result<()> {
+    let mut hmac: Hmac<P> = Hmac::new(sk_prf)?;
+    hmac.input(opt_rand)?;
+    for part in msg {
+        hmac.input(part)?;
+    }
+    hmac.result_truncated(out)
+}
+
+fn h_msg<P: Sha2Params>(
+    r: &[u8],
+    pk_seed: &[u8],
+    pk_root: &[u8],
+    msg: &[&[u8]],
+    out: &mut [u8],
+) -> Result<()> {
+    let mut ctx: Sha2Context<P> = Sha2Context::new();
+    for part in [r, pk_seed, pk_root].iter().chain(msg) {
+        ctx.input(part)?;
+    }
+
+    let mut digest = P::new_digest();
+    ctx.result(&mut digest)?;
+
+    let seed = [r, pk_seed, digest.as_ref()].concat();
+    mgf1::<P>(&seed, out)
+}
+
+fn prehash(msg: &[u8], ph: Sha2Algorithm) -> Result<Vec<u8>> {
+    let mut ctx = AnySha2::new(ph);
+    ctx.input(msg)?;
+    ctx.result_vec()
+}
+
+/// The DER encoding of the object identifier of `ph`, all of which sit under
+/// `2.16.840.1.101.3.4.2`.
+fn oid_der(ph: Sha2Algorithm) -> [u8; 11] {
+    let last = match ph {
+        Sha2Algorithm::Sha256 => 1,
+        Sha2Algorithm::Sha384 => 2,
+        Sha2Algorithm::Sha512 => 3,
+        Sha2Algorithm::Sha224 => 4,
+        Sha2Algorithm::Sha512_224 => 5,
+        Sha2Algorithm::Sha512_256 => 6,
+    };
+    [
+        0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, last,
+    ]
+}
+
+/// The base-16 digits of `msg` followed by those of its checksum (FIPS 205 algorithm 7, lines 1
+/// to 9).
+fn wots_digits(msg: &[u8]) -> Vec<u32> {
+    let mut digits = base_2b(msg, 4, 2 * msg.len());
+    let checksum: u32 = digits.iter().map(|d| 15 - d).sum();
+
+    // len_2 * lg(w) = 12 bits, shifted to fill two bytes
+    let checksum = ((checksum << 4) as u16).to_be_bytes();
+    digits.extend(base_2b(&checksum, 4, 3));
+    digits
+}
+
+/// The first `out_len` `b`-bit integers of `x`, most significant bits first (FIPS 205
+/// algorithm 4).
+fn base_2b(x: &[u8], b: usize, out_len: usize) -> Vec<u32> {
+    let mut bytes = x.iter();
+    let mut total = 0u32;
+    let mut bits = 0;
+
+    (0..out_len)
+        .map(|_| {
+            while bits < b {
+                total = (total << 8) | *bytes.next().expect("enough input bits") as u32;
+                bits += 8;
+            }
+            bits -= b;
+            (total >> bits) & ((1 << b) - 1)
+        })
+        .collect()
+}
+
+fn read_be(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |acc, &b| (acc << 8) | b as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SlhDsaPrivateKey, SlhDsaPublicKey, SlhDsaType, SLH_DSA_MAX_CONTEXT_LEN};
+    use crate::algorithm::Sha2Algorithm;
+    use crate::error::Result;
+    use crate::test_util::sha256;
+
+    use hex_literal::hex;
+
+    /// The key with `SK.seed`, `SK.prf` and `PK.seed` counting up from 0 through `3n - 1`.
+    fn key(slh_dsa_type: SlhDsaType) -> Result<SlhDsaPrivateKey> {
+        let n = slh_dsa_type.n() as u8;
+        let seeds: Vec<u8> = (0..3 * n).collect();
+        let (sk_seed, rest) = seeds.split_at(n as usize);
+        let (sk_prf, pk_seed) = rest.split_at(n as usize);
+        SlhDsaPrivateKey::from_seeds(slh_dsa_type, sk_seed, sk_prf, pk_seed)
+    }
+
+    // ACVP-Server SLH-DSA-keyGen-FIPS205 cases, one per SHA-2 parameter set, as carried in
+    // OpenSSL's test/slh_dsa.inc. Each private key is SK.seed || SK.prf || PK.seed || PK.root.
+    const KEYGEN: [(&str, &[u8]); 6] = [
+        (
+            "SLH-DSA-SHA2-128s",
+            &hex!(
+                "aa9cc7dca491fc86bcb15a709a15e9b3905c800b6e2fb9b54b6b050ee5e4de9a"
+                "fa5464d1c661fed38b2a51ca3eae71bacae3d1865215e3d3850e8c1b8292bf42"
+            ),
+        ),
+        (
+            "SLH-DSA-SHA2-128f",
+            &hex!(
+                "e2bdaa37c8cffe5e8d5676c23267890c31441758f573285881cdc82ab911dd84"
+                "72dc8d26df6ef708f4c41af9fd04b65a8927927229891d47a60d67ecef3d2c17"
+            ),
+        ),
+        (
+            "SLH-DSA-SHA2-192s",
+            &hex!(
+                "442e446e73330afb98704656328f4dd7334f8a9ab4d92cbe790f91c2e92a81af"
+                "ee0a7ec45a3d609346276f5a328755a17dd41608f59e492668d81d03441394fe"
+                "7a8e7e58705d7632ba8bc66d04c99ee5c721e4ff4bbe7815ccf923e81e10c475"
+            ),
+        ),
+        (
+            "SLH-DSA-SHA2-192f",
+            &hex!(
+                "942dd588b0e0636060b1023baf1df36002b9d3c7256904f948596f0f2f175055"
+                "85f022423433b3a3ef50977b98b01e8e3e1d7dcf880c2ea3c8872122a996d5d6"
+                "233bdfbd57fa641fad3c81bbe6778b1f788195131b3fca91854ff3b075bf0009"
+            ),
+        ),
+        (
+            "SLH-DSA-SHA2-256s",
+            &hex!(
+                "ae6ca8664ec0b9179d4e33c4defe01fcd589f60ba4502fe7416f2acd96e139f1"
+                "3fda2069147f44eabd5bbf29c74a20cb0f0cc2a12bab5834b773530af504900a"
+                "134d5ed3c60b46344a84a45d4683b1ac55fb22886ba9478ea9ca93f27b9aa2c2"
+                "7c5c9908f086e57956f85de84b438ef1f082cd176dff3c5b8be710bc8699a143"
+            ),
+        ),
+        (
+            "SLH-DSA-SHA2-256f",
+            &hex!(
+                "37d1806dd090353064a982276aebd20e7318f36b17ec5209d4006651760ca043"
+                "896630d51c45a8f7c1da31192f41204deb71bbc4fb47700a91ec47bb4acf3a38"
+                "dabbd00112520e60061da23f4c83a5c475a0ad3ab75f59e5c7b8ce2430deb480"
+                "c07849dd9f8d993dce5685d0840134bd11592055f74cde3aa9e7d25b33c31067"
+            ),
+        ),
+    ];
+
+    #[test]
+    fn test_acvp_keygen() -> Result<()> {
+        for (parameter_set, expected) in KEYGEN {
+            let slh_dsa_type: SlhDsaType = parameter_set.parse()?;
+            let n = slh_dsa_type.n();
+            let sk = SlhDsaPrivateKey::from_seeds(
+                slh_dsa_type,
+                &expected[..n],
+                &expected[n..2 * n],
+                &expected[2 * n..3 * n],
+            )?;
+            assert_eq!(sk.as_bytes(), expected);
+            assert_eq!(sk.public_key().as_bytes(), &expected[2 * n..]);
+        }
+        Ok(())
+    }
+
+    // ACVP-Server SLH-DSA-sigGen-FIPS205 cases (internal interface), one per security
+    // category, as carried in OpenSSL's test/slh_dsa.inc. That file only keeps the SHA-256 of
+    // each signature, so the signatures are checked through their digest.
+    struct SigGenCase {
+        parameter_set: &'static str,
+        sk: &'static [u8],
+        msg: &'static [u8],
+        addrnd: Option<&'static [u8]>,
+        signature_hash: [u8; 32],
+    }
+
+    const SIGGEN: [SigGenCase; 3] = [
+        SigGenCase {
+            parameter_set: "SLH-DSA-SHA2-128s",
+            sk: &hex!(
+                "62b1973a4de0963d74c1cb30fc8f5675cfc84880e4f0e1b446b4f5d13b2d31cc"
+                "cb23eb45529e00d5f5e951507a9b90e98b6e7a284ba3f63a69e69a789083bef6"
+            ),
+            msg: &hex!("9ddf"),
+            addrnd: None,
+            signature_hash: hex!(
+                "c7dff0ed253849ef511e90be0e2eb7716598912317529a61dae4329bf149ef8b"
+            ),
+        },
+        SigGenCase {
+            parameter_set: "SLH-DSA-SHA2-192f",
+            sk: &hex!(
+                "c10adc692b76ff6f34a6f0c8ffe2bb88e841ebd92da7ffecdd3bf6c10566a0cf"
+                "698e393dfb84587d3bc8c7de5869668c36f7aa6b6f82a7f3c109e88173a1e5f4"
+                "0fa529a29f485ccb40bb247b9cbada958392425bff8b20289b0f11aa3a514e54"
+            ),
+            msg: &hex!("0e54"),
+            addrnd: Some(&hex!("e6e71a97570775b35498287f818d8c2a96000576c85c0c71")),
+            signature_hash: hex!(
+                "d05eec26b12e219b83e29684ff88a82a49e4d2c52e438bcf15a5f61810516c2e"
+            ),
+        },
+        SigGenCase {
+            parameter_set: "SLH-DSA-SHA2-256f",
+            sk: &hex!(
+                "1a5a6a0c8aae359cdace456742c37123e8c5ad9cfeab751b1d92116c81d6f52c"
+                "b010086337e5fb27e674c7f6ee144cec10eef88780f10f0cf3f743c8b7db15ce"
+                "0390bf5ecc8ff5a007236c56bf9d9d46a3534e545836cf7adf200dd2862f911b"
+                "74095e73e63641fab26bd717ab29bb1ed8735c536132a19eac5daed8a07cf3b5"
+            ),
+            msg: &hex!("dc09"),
+            addrnd: None,
+            signature_hash: hex!(
+                "13b778cd29f45535cce456ad36ffad5054c314be5f59a59acfa518b16a6f57f0"
+            ),
+        },
+    ];
+
+    fn check_siggen(case: &SigGenCase) -> Result<()> {
+        let slh_dsa_type: SlhDsaType = case.parameter_set.parse()?;
+        let sk = SlhDsaPrivateKey::from_bytes(slh_dsa_type, case.sk)?;
+        let signature = sk.sign_internal(case.msg, case.addrnd)?;
+        assert_eq!(signature.len(), slh_dsa_type.signature_len());
+        assert_eq!(sha256(&signature)?, case.signature_hash);
+
+        // and the same signature as a sigVer case, before and after a bit flip
+        let pk = SlhDsaPublicKey::from_bytes(slh_dsa_type, &case.sk[2 * slh_dsa_type.n()..])?;
+        assert!(pk.verify_internal(case.msg, &signature)?);
+        let mut bad = signature;
+        bad[slh_dsa_type.n()] ^= 1;
+        assert!(!pk.verify_internal(case.msg, &bad)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_acvp_siggen_128s() -> Result<()> {
+        check_siggen(&SIGGEN[0])
+    }
+
+    #[test]
+    fn test_acvp_siggen_192f() -> Result<()> {
+        check_siggen(&SIGGEN[1])
+    }
+
+    #[test]
+    fn test_acvp_siggen_256f() -> Result<()> {
+        check_siggen(&SIGGEN[2])
+    }
+
+    // The external interface. The ACVP cases above only exercise the internal one, so these
+    // are regression values.
+    #[test]
+    fn test_context_and_prehash() -> Result<()> {
+        let sk = key(SlhDsaType::Sha2_128f)?;
+        let pk = sk.public_key();
+
+        let signature = sk.sign(b"message", b"context", Some(&[0xaa; 16]))?;
+        assert_eq!(
+            sha256(&signature)?,
+            hex!("c0cac062228835648bba4deec5da86151ae1c3bdf087ab083bfdc12b2ba6f8e3")
+        );
+        assert!(pk.verify(b"message", b"context", &signature)?);
+        assert!(!pk.verify(b"message", b"other", &signature)?);
+        assert!(!pk.verify_internal(b"message", &signature)?);
+
+        let signature = sk.sign_prehash(b"message", b"", Sha2Algorithm::Sha256, None)?;
+        assert_eq!(
+            sha256(&signature)?,
+            hex!("6102c55389e09ae798e6300391b9f67fd375a8074caa18322bd16d743efef054")
+        );
+        assert!(pk.verify_prehash(b"message", b"", Sha2Algorithm::Sha256, &signature)?);
+        assert!(!pk.verify_prehash(b"message", b"", Sha2Algorithm::Sha512, &signature)?);
+        assert!(!pk.verify(b"message", b"", &signature)?);
+
+        let sk = key(SlhDsaType::Sha2_192f)?;
+        let signature = sk.sign_prehash(b"message", b"", Sha2Algorithm::Sha512, None)?;
+        assert_eq!(
+            sha256(&signature)?,
+            hex!("4005c5d9b1f50ee6c03248d5bd13d0bff5b3e49908049ee0f0d9ce82b66b82dd")
+        );
+        assert!(sk.public_key().verify_prehash(
+            b"message",
+            b"",
+            Sha2Algorithm::Sha512,
+            &signature
+        )?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tampering() -> Result<()> {
+        let sk = key(SlhDsaType::Sha2_128f)?;
+        let pk = SlhDsaPublicKey::from_bytes(SlhDsaType::Sha2_128f, sk.public_key().as_bytes())?;
+        let signature = sk.sign(b"msg", b"", None)?;
+
+        // the randomizer, FORS, and the bottom and top XMSS signatures
+        for i in [0, 16, 3000, 8000, 17087] {
+            let mut bad = signature.clone();
+            bad[i] ^= 1;
+            assert!(!pk.verify(b"msg", b"", &bad)?);
+        }
+        assert!(!pk.verify(b"msg", b"", &signature[1..])?);
+        assert!(!pk.verify(b"msg", &[0; SLH_DSA_MAX_CONTEXT_LEN + 1], &signature)?);
+
+        let restored = SlhDsaPrivateKey::from_bytes(SlhDsaType::Sha2_128f, sk.as_bytes())?;
+        assert_eq!(restored.sign(b"msg", b"", None)?, signature);
+        Ok(())
+    }
+
+    #[test]
+    fn test_names() -> Result<()> {
+        for slh_dsa_type in SlhDsaType::ALL {
+            assert_eq!(
+                slh_dsa_type.to_string().parse::<SlhDsaType>()?,
+                slh_dsa_type
+            );
+            assert_eq!(
+                slh_dsa_type.private_key_len(),
+                2 * slh_dsa_type.public_key_len()
+            );
+        }
+        assert_eq!(
+            "slh-dsa-sha2-256f".parse::<SlhDsaType>()?,
+            SlhDsaType::Sha2_256f
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_params() -> Result<()> {
+        let n16 = [0u8; 16];
+        assert!(SlhDsaPrivateKey::from_seeds(SlhDsaType::Sha2_128f, &n16, &n16, &[0; 15]).is_err());
+        assert!(SlhDsaPrivateKey::from_seeds(SlhDsaType::Sha2_192f, &n16, &n16, &n16).is_err());
+        assert!(SlhDsaPrivateKey::from_bytes(SlhDsaType::Sha2_128f, &[0; 63]).is_err());
+        assert!(SlhDsaPublicKey::from_bytes(SlhDsaType::Sha2_256s, &[0; 32]).is_err());
+        assert!("SLH-DSA-SHAKE-128s".parse::<SlhDsaType>().is_err());
+
+        let sk = key(SlhDsaType::Sha2_128f)?;
+        assert!(sk
+            .sign(b"msg", &[0; SLH_DSA_MAX_CONTEXT_LEN + 1], None)
+            .is_err());
+        assert!(sk.sign(b"msg", b"", Some(&[0; 15])).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,14 @@
+//! Helpers shared by the unit tests.
+
+use crate::context::Sha2Context;
+use crate::error::Result;
+use crate::params::Sha256Params;
+
+/// SHA-256 of `data`, for condensing long expected outputs.
+pub(crate) fn sha256(data: &[u8]) -> Result<[u8; 32]> {
+    let mut ctx: Sha2Context<Sha256Params> = Sha2Context::new();
+    let mut digest = [0u8; 32];
+    ctx.input(data)?;
+    ctx.result(&mut digest)?;
+    Ok(digest)
+}
@@ -0,0 +1,278 @@
+use crate::error::{Error, Result, Sha2Corrupted};
+use crate::hmac::Hmac;
+use crate::params::Sha2Params;
+use crate::wipe::wipe_slice;
+
+/// Length of the TLS 1.2 master secret.
+pub const TLS12_MASTER_SECRET_LEN: usize = 48;
+
+/// Length of the `verify_data` field of a TLS 1.2 Finished message for the standard cipher
+/// suites.
+pub const TLS12_VERIFY_DATA_LEN: usize = 12;
+
+/// The endpoint a Finished message is sent by, selecting the PRF label.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tls12Sender {
+    Client,
+    Server,
+}
+
+impl Tls12Sender {
+    fn finished_label(self) -> &'static [u8] {
+        match self {
+            Tls12Sender::Client => b"client finished",
+            Tls12Sender::Server => b"server finished",
+        }
+    }
+}
+
+/// The data expansion function `P_hash(secret, seed)` (RFC 5246, section 5), filling `out`.
+pub fn tls12_p_hash<P: Sha2Params>(secret: &[u8], seed: &[u8], out: &mut [u8]) -> Result<()> {
+    p_hash::<P>(secret, &[seed], out)
+}
+
+/// The TLS 1.2 `PRF(secret, label, seed)`, i.e. `P_hash(secret, label || seed)`. TLS 1.2 cipher
+/// suites use SHA-256 unless they specify otherwise, usually SHA-384.
+pub fn tls12_prf<P: Sha2Params>(
+    secret: &[u8],
+    label: &[u8],
+    seed: &[u8],
+    out: &mut [u8],
+) -> Result<()> {
+    p_hash::<P>(secret, &[label, seed], out)
+}
+
+/// Derives the master secret from the pre-master secret and the hello randoms.
+pub fn tls12_master_secret<P: Sha2Params>(
+    pre_master_secret: &[u8],
+    client_random: &[u8; 32],
+    server_random: &[u8; 32],
+) -> Result<[u8; TLS12_MASTER_SECRET_LEN]> {
+    let mut master_secret = [0u8; TLS12_MASTER_SECRET_LEN];
+    p_hash::<P>(
+        pre_master_secret,
+        &[b"master secret", client_random, server_random],
+        &mut master_secret,
+    )?;
+    Ok(master_secret)
+}
+
+/// Derives the master secret with the extended master secret extension (RFC 7627, section 4).
+/// `session_hash` is the transcript hash up to and including the ClientKeyExchange message.
+pub fn tls12_extended_master_secret<P: Sha2Params>(
+    pre_master_secret: &[u8],
+    session_hash: &[u8],
+) -> Result<[u8; TLS12_MASTER_SECRET_LEN]> {
+    let mut master_secret = [0u8; TLS12_MASTER_SECRET_LEN];
+    p_hash::<P>(
+        pre_master_secret,
+        &[b"extended master secret", session_hash],
+        &mut master_secret,
+    )?;
+    Ok(master_secret)
+}
+
+/// Fills `key_block` with keying material for the record layer. Note that the server random
+/// comes first here, unlike in the master secret derivation.
+pub fn tls12_key_block<P: Sha2Params>(
+    master_secret: &[u8],
+    server_random: &[u8; 32],
+    client_random: &[u8; 32],
+    key_block: &mut [u8],
+) -> Result<()> {
+    p_hash::<P>(
+        master_secret,
+        &[b"key expansion", server_random, client_random],
+        key_block,
+    )
+}
+
+/// Computes the `verify_data` of a Finished message over `handshake_hash`, the transcript hash
+/// of all handshake messages sent before it.
+pub fn tls12_verify_data<P: Sha2Params>(
+    master_secret: &[u8],
+    sender: Tls12Sender,
+    handshake_hash: &[u8],
+) -> Result<[u8; TLS12_VERIFY_DATA_LEN]> {
+    let mut verify_data = [0u8; TLS12_VERIFY_DATA_LEN];
+    p_hash::<P>(
+        master_secret,
+        &[sender.finished_label(), handshake_hash],
+        &mut verify_data,
+    )?;
+    Ok(verify_data)
+}
+
+/// `P_hash` with the seed given as the concatenation of `seed`.
+///
+/// A(0) = seed, A(i) = HMAC(secret, A(i-1)), and each output block is
+/// HMAC(secret, A(i) || seed).
+fn p_hash<P: Sha2Params>(secret: &[u8], seed: &[&[u8]], out: &mut [u8]) -> Result<()> {
+    if out.is_empty() {
+        return Err(Error(Sha2Corrupted::BadParam));
+    }
+
+    let mut mac: Hmac<P> = Hmac::new(secret)?;
+    let mut a = P::new_digest();
+    let mut block = P::new_digest();
+
+    for part in seed {
+        mac.input(part)?;
+    }
+    mac.result(&mut a)?;
+
+    let res = out.chunks_mut(P::HASH_LEN_BYTES).try_for_each(|out_block| {
+        mac.reset()?;
+        mac.input(a.as_ref())?;
+        for part in seed {
+            mac.input(part)?;
+        }
+        mac.result(&mut block)?;
+        out_block.copy_from_slice(&block.as_ref()[..out_block.len()]);
+
+        mac.reset()?;
+        mac.input(a.as_ref())?;
+        mac.result(&mut a)
+    });
+
+    wipe_slice(a.as_mut(), 0);
+    wipe_slice(block.as_mut(), 0);
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        tls12_extended_master_secret, tls12_key_block, tls12_master_secret, tls12_prf,
+        tls12_verify_data, Tls12Sender,
+    };
+    use crate::context::Sha2Context;
+    use crate::error::Result;
+    use crate::params::{Sha256Params, Sha384Params};
+
+    use hex_literal::hex;
+
+    // test vectors published on the IETF TLS mailing list
+    #[test]
+    fn test_prf_sha256() -> Result<()> {
+        let mut out = [0u8; 100];
+        tls12_prf::<Sha256Params>(
+            &hex!("9bbe436ba940f017b17652849a71db35"),
+            b"test label",
+            &hex!("a0ba9f936cda311827a6f796ffd5198c"),
+            &mut out,
+        )?;
+        assert_eq!(out, hex!("e3f229ba727be17b8d122620557cd453c2aab21d07c3d495329b52d4e61edb5a6b301791e90d35c9c9a46b4e14baf9af0fa022f7077def17abfd3797c0564bab4fbc91666e9def9b97fce34f796789baa48082d122ee42c5a72e5a5110fff70187347b66"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_prf_sha384() -> Result<()> {
+        let mut out = [0u8; 148];
+        tls12_prf::<Sha384Params>(
+            &hex!("b80b733d6ceefcdc71566ea48e5567df"),
+            b"test label",
+            &hex!("cd665cf6a8447dd6ff8b27555edb7465"),
+            &mut out,
+        )?;
+        assert_eq!(out, hex!("7b0c18e9ced410ed1804f2cfa34a336a1c14dffb4900bb5fd7942107e81c83cde9ca0faa60be9fe34f82b1233c9146a0e534cb400fed2700884f9dc236f80edd8bfa961144c9e8d792eca722a7b32fc3d416d473ebc2c5fd4abfdad05d9184259b5bf8cd4d90fa0d31e2dec479e4f1a26066f2eea9a69236a3e52655c9e9aee691c8f3a26854308d5eaa3be85e0990703d73e56f"));
+        Ok(())
+    }
+
+    // pre-master secret, randoms and handshake transcript from the pion/dtls
+    // test suite, which follows "The Illustrated TLS 1.2 Connection"
+    const ILLUSTRATED_PRE_MASTER_SECRET: [u8; 32] =
+        hex!("df4a291baa1eb7cfa6934b29b474baad2697e29f1f920dcc77c8a0a088447624");
+    const ILLUSTRATED_CLIENT_RANDOM: [u8; 32] =
+        hex!("000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f");
+    const ILLUSTRATED_SERVER_RANDOM: [u8; 32] =
+        hex!("707172737475767778797a7b7c7d7e7f808182838485868788898a8b8c8d8e8f");
+    const ILLUSTRATED_MASTER_SECRET: [u8; 48] = hex!("916abf9da55973e13614ae0a3f5d3f37b023ba129aee02cc9134338127cd7049781c8e19fc1eb2a7387ac06ae237344c");
+
+    #[test]
+    fn test_master_secret() -> Result<()> {
+        assert_eq!(
+            tls12_master_secret::<Sha256Params>(
+                &ILLUSTRATED_PRE_MASTER_SECRET,
+                &ILLUSTRATED_CLIENT_RANDOM,
+                &ILLUSTRATED_SERVER_RANDOM,
+            )?,
+            ILLUSTRATED_MASTER_SECRET
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_block() -> Result<()> {
+        // AES-128-GCM: client key, server key, client IV, server IV
+        let mut key_block = [0u8; 40];
+        tls12_key_block::<Sha256Params>(
+            &ILLUSTRATED_MASTER_SECRET,
+            &ILLUSTRATED_SERVER_RANDOM,
+            &ILLUSTRATED_CLIENT_RANDOM,
+            &mut key_block,
+        )?;
+        assert_eq!(
+            key_block,
+            hex!(
+                "1b7d117c7d5f690bc263cae8ef60af0f1878acc22ad8bdd8c601a617126f63540eb20906f781fad2"
+            )
+        );
+
+        // NIST CAVS TLS 1.2 KDF "key expansion" vector used by OpenSSL's FIPS
+        // provider self-test
+        let mut key_block = [0u8; 128];
+        tls12_key_block::<Sha256Params>(
+            &hex!("202c88c00f84a17a20027079604787461176455539e705be730890602c289a5001e34eeb3a043e5d52a65e66125188bf"),
+            &hex!("ae6c806f8ad4d80784549dff28a4b58fd837681a51d928c3e30ee5ff14f39868"),
+            &hex!("62e1fd91f23f558a605f28478c58cf72637b89784d959df7e946d3f07bd1b616"),
+            &mut key_block,
+        )?;
+        assert_eq!(key_block, hex!("d06139889fffac1e3a71865f504aa5d0d2a2e89506c6f2279b670c3e1b74f531016a2530c51a3a0f7e1d6590d0f0566b2f387f8d11fd4f731cdd572d2eae927f6f2f81410b25e6960be68985add6c38445ad9f8c64bf8068bf9a6679485d966f1ad6f68b43495b10a683755ea2b858d70ccac7ec8b053c6bd41ca299d4e51928"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_data() -> Result<()> {
+        // SHA-256 of the Illustrated TLS 1.2 handshake messages from
+        // ClientHello through ClientKeyExchange
+        let handshake_hash =
+            hex!("061dda04b3c2217ff73bd79b9cf88a2bb6ec505404aac8722db03ef417b54cb4");
+        assert_eq!(
+            tls12_verify_data::<Sha256Params>(
+                &ILLUSTRATED_MASTER_SECRET,
+                Tls12Sender::Client,
+                &handshake_hash
+            )?,
+            hex!("cf919626f1360c536aaad73a")
+        );
+        Ok(())
+    }
+
+    // no published trace covers these, so they are regression values
+    #[test]
+    fn test_extended_master_secret() -> Result<()> {
+        let pre_master_secret: Vec<u8> = (0u8..48).collect();
+
+        let mut session_hash = [0u8; 32];
+        let mut ctx: Sha2Context<Sha256Params> = Sha2Context::new();
+        ctx.input(b"handshake messages")?;
+        ctx.result(&mut session_hash)?;
+
+        assert_eq!(
+            tls12_extended_master_secret::<Sha256Params>(&pre_master_secret, &session_hash)?,
+            hex!("837a95d7e29469176bdd9f3b31a73809d8b5b68724a52db14fcdcd6b3371fe7b0a1db91b4f40b82ceb19ead85773987b")
+        );
+
+        assert_eq!(
+            tls12_master_secret::<Sha384Params>(&pre_master_secret, &[1u8; 32], &[2u8; 32])?,
+            hex!("01e0ef697d40d05b279489200008a0ed864276c0cfff5af17d5d19b40854ca7bd98a06618dff88445471bb314daa3294")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_params() {
+        assert!(tls12_prf::<Sha256Params>(b"secret", b"label", b"seed", &mut []).is_err());
+    }
+}
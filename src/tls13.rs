@@ -0,0 +1,499 @@
+use crate::context::Sha2Context;
+use crate::error::{Error, Result, Sha2Corrupted};
+use crate::hkdf::Hkdf;
+use crate::hmac::Hmac;
+use crate::params::Sha2Params;
+use crate::wipe::wipe_slice;
+
+/// Length of the per-record nonce derived for the AEAD cipher suites of TLS 1.3.
+pub const TLS13_IV_LEN: usize = 12;
+
+const LABEL_PREFIX: &[u8] = b"tls13 ";
+
+/// `HKDF-Expand-Label(secret, label, context, out.len())` (RFC 8446, section 7.1). `label` is
+/// given without the `"tls13 "` prefix.
+pub fn tls13_hkdf_expand_label<P: Sha2Params>(
+    secret: &[u8],
+    label: &[u8],
+    context: &[u8],
+    out: &mut [u8],
+) -> Result<()> {
+    let full_label_len = LABEL_PREFIX.len() + label.len();
+
+    if out.len() > u16::MAX as usize || full_label_len > 255 || context.len() > 255 {
+        return Err(Error(Sha2Corrupted::BadParam));
+    }
+
+    // struct HkdfLabel { uint16 length; opaque label<7..255>; opaque context<0..255>; }
+    let mut hkdf_label = Vec::with_capacity(4 + full_label_len + context.len());
+    hkdf_label.extend_from_slice(&(out.len() as u16).to_be_bytes());
+    hkdf_label.push(full_label_len as u8);
+    hkdf_label.extend_from_slice(LABEL_PREFIX);
+    hkdf_label.extend_from_slice(label);
+    hkdf_label.push(context.len() as u8);
+    hkdf_label.extend_from_slice(context);
+
+    Hkdf::<P>::from_prk(secret)?.expand_into(&hkdf_label, out)
+}
+
+/// `Derive-Secret(secret, label, messages)` with `messages` given as the running transcript
+/// hash. The transcript is forked, so it can keep absorbing handshake messages afterwards.
+pub fn tls13_derive_secret<P: Sha2Params>(
+    secret: &[u8],
+    label: &[u8],
+    transcript: &Sha2Context<P>,
+) -> Result<P::Digest> {
+    let mut hash = tls13_transcript_hash(transcript)?;
+    let res = derive_secret_from_hash::<P>(secret, label, hash.as_ref());
+    wipe_slice(hash.as_mut(), 0);
+    res
+}
+
+/// The current value of a running transcript hash, leaving `transcript` untouched.
+pub fn tls13_transcript_hash<P: Sha2Params>(transcript: &Sha2Context<P>) -> Result<P::Digest> {
+    let mut hash = P::new_digest();
+    transcript.clone().result(&mut hash)?;
+    Ok(hash)
+}
+
+/// The stage the key schedule is in, i.e. which secret it currently holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tls13Stage {
+    Early,
+    Handshake,
+    Master,
+}
+
+/// The secret-deriving part of the TLS 1.3 key schedule (RFC 8446, section 7.1).
+///
+/// The schedule starts with the early secret and is advanced with [`Tls13KeySchedule::handshake`]
+/// and [`Tls13KeySchedule::master`]. Traffic secrets can only be derived in the stage they belong
+/// to; anything else is a `StateError`. Transcript hashes are taken from [`Sha2Context`]s that
+/// the caller keeps feeding with handshake messages.
+pub struct Tls13KeySchedule<P: Sha2Params> {
+    stage: Tls13Stage,
+    secret: P::Digest,
+}
+
+impl<P: Sha2Params> Tls13KeySchedule<P> {
+    /// Computes the early secret from a pre-shared key, or from `HashLen` zero bytes if there is
+    /// none.
+    pub fn new(psk: Option<&[u8]>) -> Result<Self> {
+        let zeros = P::new_digest();
+        let hkdf = Hkdf::<P>::extract(zeros.as_ref(), psk.unwrap_or(zeros.as_ref()))?;
+
+        let mut secret = P::new_digest();
+        secret.as_mut().copy_from_slice(hkdf.prk().as_ref());
+
+        Ok(Tls13KeySchedule {
+            stage: Tls13Stage::Early,
+            secret,
+        })
+    }
+
+    pub fn stage(&self) -> Tls13Stage {
+        self.stage
+    }
+
+    /// The early, handshake or master secret, depending on the stage.
+    pub fn secret(&self) -> &P::Digest {
+        &self.secret
+    }
+
+    /// Moves from the early to the handshake secret, mixing in the (EC)DHE shared secret. Pure
+    /// PSK handshakes pass `None`.
+    pub fn handshake(&mut self, shared_secret: Option<&[u8]>) -> Result<()> {
+        self.advance(Tls13Stage::Early, Tls13Stage::Handshake, shared_secret)
+    }
+
+    /// Moves from the handshake to the master secret.
+    pub fn master(&mut self) -> Result<()> {
+        self.advance(Tls13Stage::Handshake, Tls13Stage::Master, None)
+    }
+
+    /// `Derive-Secret` from the current secret, with the transcript given as a finished hash.
+    pub fn derive_secret(&self, label: &[u8], transcript_hash: &[u8]) -> Result<P::Digest> {
+        derive_secret_from_hash::<P>(self.secret.as_ref(), label, transcript_hash)
+    }
+
+    /// The PSK binder key: `external` selects `"ext binder"` over `"res binder"`.
+    pub fn binder_key(&self, external: bool) -> Result<P::Digest> {
+        let label: &[u8] = if external {
+            b"ext binder"
+        } else {
+            b"res binder"
+        };
+        self.derive_in(Tls13Stage::Early, label, &Sha2Context::new())
+    }
+
+    /// Transcript: ClientHello.
+    pub fn client_early_traffic_secret(&self, transcript: &Sha2Context<P>) -> Result<P::Digest> {
+        self.derive_in(Tls13Stage::Early, b"c e traffic", transcript)
+    }
+
+    /// Transcript: ClientHello.
+    pub fn early_exporter_master_secret(&self, transcript: &Sha2Context<P>) -> Result<P::Digest> {
+        self.derive_in(Tls13Stage::Early, b"e exp master", transcript)
+    }
+
+    /// Transcript: ClientHello...ServerHello.
+    pub fn client_handshake_traffic_secret(
+        &self,
+        transcript: &Sha2Context<P>,
+    ) -> Result<P::Digest> {
+        self.derive_in(Tls13Stage::Handshake, b"c hs traffic", transcript)
+    }
+
+    /// Transcript: ClientHello...ServerHello.
+    pub fn server_handshake_traffic_secret(
+        &self,
+        transcript: &Sha2Context<P>,
+    ) -> Result<P::Digest> {
+        self.derive_in(Tls13Stage::Handshake, b"s hs traffic", transcript)
+    }
+
+    /// Transcript: ClientHello...server Finished.
+    pub fn client_application_traffic_secret(
+        &self,
+        transcript: &Sha2Context<P>,
+    ) -> Result<P::Digest> {
+        self.derive_in(Tls13Stage::Master, b"c ap traffic", transcript)
+    }
+
+    /// Transcript: ClientHello...server Finished.
+    pub fn server_application_traffic_secret(
+        &self,
+        transcript: &Sha2Context<P>,
+    ) -> Result<P::Digest> {
+        self.derive_in(Tls13Stage::Master, b"s ap traffic", transcript)
+    }
+
+    /// Transcript: ClientHello...server Finished.
+    pub fn exporter_master_secret(&self, transcript: &Sha2Context<P>) -> Result<P::Digest> {
+        self.derive_in(Tls13Stage::Master, b"exp master", transcript)
+    }
+
+    /// Transcript: ClientHello...client Finished.
+    pub fn resumption_master_secret(&self, transcript: &Sha2Context<P>) -> Result<P::Digest> {
+        self.derive_in(Tls13Stage::Master, b"res master", transcript)
+    }
+
+    fn derive_in(
+        &self,
+        stage: Tls13Stage,
+        label: &[u8],
+        transcript: &Sha2Context<P>,
+    ) -> Result<P::Digest> {
+        if self.stage != stage {
+            return Err(Error(Sha2Corrupted::StateError));
+        }
+
+        tls13_derive_secret(self.secret.as_ref(), label, transcript)
+    }
+
+    fn advance(&mut self, from: Tls13Stage, to: Tls13Stage, ikm: Option<&[u8]>) -> Result<()> {
+        if self.stage != from {
+            return Err(Error(Sha2Corrupted::StateError));
+        }
+
+        let zeros = P::new_digest();
+        let mut salt = self.derive_in(from, b"derived", &Sha2Context::new())?;
+        let hkdf = Hkdf::<P>::extract(salt.as_ref(), ikm.unwrap_or(zeros.as_ref()));
+        wipe_slice(salt.as_mut(), 0);
+
+        self.secret.as_mut().copy_from_slice(hkdf?.prk().as_ref());
+        self.stage = to;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl<P: Sha2Params> Drop for Tls13KeySchedule<P> {
+    fn drop(&mut self) {
+        wipe_slice(self.secret.as_mut(), 0);
+    }
+}
+
+/// Record protection keys derived from a traffic secret.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tls13TrafficKeys {
+    key: Vec<u8>,
+    iv: [u8; TLS13_IV_LEN],
+}
+
+impl Tls13TrafficKeys {
+    /// The AEAD write key.
+    pub fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// The static IV that record sequence numbers are XORed into to form nonces.
+    pub fn iv(&self) -> &[u8; TLS13_IV_LEN] {
+        &self.iv
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for Tls13TrafficKeys {
+    fn drop(&mut self) {
+        wipe_slice(&mut self.key, 0);
+        wipe_slice(&mut self.iv, 0);
+    }
+}
+
+/// Derives the write key of `key_len` bytes and the IV from a traffic secret.
+pub fn tls13_traffic_keys<P: Sha2Params>(
+    traffic_secret: &[u8],
+    key_len: usize,
+) -> Result<Tls13TrafficKeys> {
+    let mut keys = Tls13TrafficKeys {
+        key: vec![0u8; key_len],
+        iv: [0u8; TLS13_IV_LEN],
+    };
+
+    tls13_hkdf_expand_label::<P>(traffic_secret, b"key", b"", &mut keys.key)?;
+    tls13_hkdf_expand_label::<P>(traffic_secret, b"iv", b"", &mut keys.iv)?;
+    Ok(keys)
+}
+
+/// The next generation of an application traffic secret, used by KeyUpdate.
+pub fn tls13_next_traffic_secret<P: Sha2Params>(traffic_secret: &[u8]) -> Result<P::Digest> {
+    let mut next = P::new_digest();
+    tls13_hkdf_expand_label::<P>(traffic_secret, b"traffic upd", b"", next.as_mut())?;
+    Ok(next)
+}
+
+/// The key used to compute Finished messages from the sender's handshake traffic secret.
+pub fn tls13_finished_key<P: Sha2Params>(base_key: &[u8]) -> Result<P::Digest> {
+    let mut finished_key = P::new_digest();
+    tls13_hkdf_expand_label::<P>(base_key, b"finished", b"", finished_key.as_mut())?;
+    Ok(finished_key)
+}
+
+/// The `verify_data` of a Finished message, an HMAC over the transcript hash up to (but not
+/// including) the Finished message itself.
+pub fn tls13_finished_verify_data<P: Sha2Params>(
+    base_key: &[u8],
+    transcript: &Sha2Context<P>,
+) -> Result<P::Digest> {
+    let mut finished_key = tls13_finished_key::<P>(base_key)?;
+    let mut hash = tls13_transcript_hash(transcript)?;
+    let mut verify_data = P::new_digest();
+
+    let res = Hmac::<P>::new(finished_key.as_ref()).and_then(|mut mac| {
+        mac.input(hash.as_ref())?;
+        mac.result(&mut verify_data)
+    });
+
+    wipe_slice(finished_key.as_mut(), 0);
+    wipe_slice(hash.as_mut(), 0);
+    res.map(|_| verify_data)
+}
+
+fn derive_secret_from_hash<P: Sha2Params>(
+    secret: &[u8],
+    label: &[u8],
+    transcript_hash: &[u8],
+) -> Result<P::Digest> {
+    if transcript_hash.len() != P::HASH_LEN_BYTES {
+        return Err(Error(Sha2Corrupted::BadParam));
+    }
+
+    let mut out = P::new_digest();
+    tls13_hkdf_expand_label::<P>(secret, label, transcript_hash, out.as_mut())?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        tls13_derive_secret, tls13_finished_key, tls13_finished_verify_data,
+        tls13_next_traffic_secret, tls13_traffic_keys, tls13_transcript_hash, Tls13KeySchedule,
+        Tls13Stage,
+    };
+    use crate::context::Sha2Context;
+    use crate::error::Result;
+    use crate::hmac::Hmac;
+    use crate::params::{Sha256Params, Sha384Params};
+
+    use hex_literal::hex;
+
+    // RFC 8448, section 3 (simple 1-RTT handshake). The transcript hashes are taken from the
+    // trace because the handshake messages themselves are not reproduced here.
+    const HASH_CH_SH: [u8; 32] =
+        hex!("860c06edc07858ee8e78f0e7428c58edd6b43f2ca3e6e95f02ed063cf0e1cad8");
+    const HASH_CH_CV: [u8; 32] =
+        hex!("edb7725fa7a3473b031ec8ef65a2485493900138a2b91291407d7951a06110ed");
+    const HASH_CH_SF: [u8; 32] =
+        hex!("9608102a0f1ccc6db6250b7b7e417b1a000eaada3daae4777a7686c9ff83df13");
+    const HASH_CH_CF: [u8; 32] =
+        hex!("209145a96ee8e2a122ff810047cc952684658d6049e86429426db87c54ad143d");
+
+    #[test]
+    fn test_rfc8448_secrets() -> Result<()> {
+        let mut ks: Tls13KeySchedule<Sha256Params> = Tls13KeySchedule::new(None)?;
+        assert_eq!(
+            ks.secret(),
+            &hex!("33ad0a1c607ec03b09e6cd9893680ce210adf300aa1f2660e1b22e10f170f92a")
+        );
+
+        ks.handshake(Some(&hex!(
+            "8bd4054fb55b9d63fdfbacf9f04b9f0d35e6d63f537563efd46272900f89492d"
+        )))?;
+        assert_eq!(
+            ks.secret(),
+            &hex!("1dc826e93606aa6fdc0aadc12f741b01046aa6b99f691ed221a9f0ca043fbeac")
+        );
+
+        let client_hs = ks.derive_secret(b"c hs traffic", &HASH_CH_SH)?;
+        let server_hs = ks.derive_secret(b"s hs traffic", &HASH_CH_SH)?;
+        assert_eq!(
+            client_hs,
+            hex!("b3eddb126e067f35a780b3abf45e2d8f3b1a950738f52e9600746a0e27a55a21")
+        );
+        assert_eq!(
+            server_hs,
+            hex!("b67b7d690cc16c4e75e54213cb2d37b4e9c912bcded9105d42befd59d391ad38")
+        );
+
+        let keys = tls13_traffic_keys::<Sha256Params>(&server_hs, 16)?;
+        assert_eq!(keys.key(), hex!("3fce516009c21727d0f2e4e86ee403bc"));
+        assert_eq!(keys.iv(), &hex!("5d313eb2671276ee13000b30"));
+
+        ks.master()?;
+        assert_eq!(
+            ks.secret(),
+            &hex!("18df06843d13a08bf2a449844c5f8a478001bc4d4c627984d5a41da8d0402919")
+        );
+        assert_eq!(
+            ks.derive_secret(b"c ap traffic", &HASH_CH_SF)?,
+            hex!("9e40646ce79a7f9dc05af8889bce6552875afa0b06df0087f792ebb7c17504a5")
+        );
+        assert_eq!(
+            ks.derive_secret(b"s ap traffic", &HASH_CH_SF)?,
+            hex!("a11af9f05531f856ad47116b45a950328204b4f44bfb6b3a4b4f1f3fcb631643")
+        );
+        assert_eq!(
+            ks.derive_secret(b"exp master", &HASH_CH_SF)?,
+            hex!("fe22f881176eda18eb8f44529e6792c50c9a3f89452f68d8ae311b4309d3cf50")
+        );
+        assert_eq!(
+            ks.derive_secret(b"res master", &HASH_CH_CF)?,
+            hex!("7df235f2031d2a051287d02b0241b0bfdaf86cc856231f2d5aba46c434ec196c")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_rfc8448_finished() -> Result<()> {
+        let server_hs = hex!("b67b7d690cc16c4e75e54213cb2d37b4e9c912bcded9105d42befd59d391ad38");
+        let client_hs = hex!("b3eddb126e067f35a780b3abf45e2d8f3b1a950738f52e9600746a0e27a55a21");
+
+        let server_key = tls13_finished_key::<Sha256Params>(&server_hs)?;
+        assert_eq!(
+            server_key,
+            hex!("008d3b66f816ea559f96b537e885c31fc068bf492c652f01f288a1d8cdc19fc8")
+        );
+
+        let mut mac: Hmac<Sha256Params> = Hmac::new(&server_key)?;
+        mac.input(&HASH_CH_CV)?;
+        assert!(mac.verify(&hex!(
+            "9b9b141d906337fbd2cbdce71df4deda4ab42c309572cb7fffee5454b78f0718"
+        ))?);
+
+        let client_key = tls13_finished_key::<Sha256Params>(&client_hs)?;
+        let mut mac: Hmac<Sha256Params> = Hmac::new(&client_key)?;
+        mac.input(&HASH_CH_SF)?;
+        assert!(mac.verify(&hex!(
+            "a8ec436d677634ae525ac1fcebe11a039ec17694fac6e98527b642f2edd5ce61"
+        ))?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_running_transcript() -> Result<()> {
+        let mut ks: Tls13KeySchedule<Sha256Params> = Tls13KeySchedule::new(None)?;
+        ks.handshake(Some(&[0x42; 32]))?;
+
+        let mut transcript: Sha2Context<Sha256Params> = Sha2Context::new();
+        transcript.input(b"ClientHello")?;
+        transcript.input(b"ServerHello")?;
+
+        let hash = tls13_transcript_hash(&transcript)?;
+        let server_hs = ks.server_handshake_traffic_secret(&transcript)?;
+        assert_eq!(server_hs, ks.derive_secret(b"s hs traffic", &hash)?);
+
+        // forking must leave the running hash usable
+        transcript.input(b"EncryptedExtensions")?;
+        let mut expected: Sha2Context<Sha256Params> = Sha2Context::new();
+        expected.input(b"ClientHelloServerHelloEncryptedExtensions")?;
+        assert_eq!(
+            tls13_transcript_hash(&transcript)?,
+            tls13_transcript_hash(&expected)?
+        );
+
+        let verify_data = tls13_finished_verify_data(&server_hs, &transcript)?;
+        let mut mac: Hmac<Sha256Params> =
+            Hmac::new(&tls13_finished_key::<Sha256Params>(&server_hs)?)?;
+        mac.input(&tls13_transcript_hash(&transcript)?)?;
+        assert!(mac.verify(&verify_data)?);
+
+        assert_eq!(
+            tls13_derive_secret(ks.secret(), b"c hs traffic", &transcript)?,
+            ks.client_handshake_traffic_secret(&transcript)?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_sha384_psk() -> Result<()> {
+        let psk: Vec<u8> = (0u8..48).collect();
+        let mut ks: Tls13KeySchedule<Sha384Params> = Tls13KeySchedule::new(Some(&psk))?;
+        assert_eq!(ks.secret(), &hex!("360732e7447a7e51164ebc696c02061ead7f37c94691c4c830c8bb5ccc75ad69aa72ba3bc5d8112486c6d0c5c6b9e0be"));
+        assert_eq!(ks.binder_key(true)?, hex!("501d1d02ce1b139bcda1ba7f77483c370562939f13d384b757f4eee72dd78d6975d163dc038e6bd776ddb4e8692c5bc4"));
+
+        ks.handshake(Some(&[0xaa; 48]))?;
+        assert_eq!(ks.secret(), &hex!("f2b461e08f47f4e7022da4f25998234236ef0ea050ec4c71b664bbb7faa36e44c3c8f5ee4414b7d9379f6088e4f66f00"));
+
+        let mut transcript: Sha2Context<Sha384Params> = Sha2Context::new();
+        transcript.input(b"ClientHelloServerHello")?;
+        let server_hs = ks.server_handshake_traffic_secret(&transcript)?;
+        assert_eq!(server_hs, hex!("975ac3c1d79d9481ac88c5326f6a6f41be88464060335ae5ae37134344cd19103ead73914dccba38b44de37d63af38e9"));
+
+        let keys = tls13_traffic_keys::<Sha384Params>(&server_hs, 32)?;
+        assert_eq!(
+            keys.key(),
+            hex!("40e5ea425ae61aff047d6504514701e883cc7529a6102095f7a293affe778056")
+        );
+        assert_eq!(keys.iv(), &hex!("48326b2fe8e087212eada9a8"));
+
+        ks.master()?;
+        assert_eq!(ks.secret(), &hex!("f436e61bc7f7d05a54f1b694a309f3fa2c61fa8d07ca7169e23567f0a3cedfeb78f5a878aa291247be6b92d52c6cb7bf"));
+
+        transcript.input(b"Finished")?;
+        let client_ap = ks.client_application_traffic_secret(&transcript)?;
+        assert_eq!(client_ap, hex!("75d293a3c45c90b714a9feb903518853e499ec5b69052ddacb225c8e4c1b6266f2f835be353d751ba8992f7b3d9aea3b"));
+        assert_eq!(tls13_next_traffic_secret::<Sha384Params>(&client_ap)?, hex!("f582d4919f6dd7323340d200c8b323bf42bbe66809daef5c66e8c489dd6570bea4b54e1533cd08028e27c5b2fefbd313"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_stage_errors() -> Result<()> {
+        let transcript: Sha2Context<Sha256Params> = Sha2Context::new();
+        let mut ks: Tls13KeySchedule<Sha256Params> = Tls13KeySchedule::new(None)?;
+
+        assert_eq!(ks.stage(), Tls13Stage::Early);
+        assert!(ks.client_handshake_traffic_secret(&transcript).is_err());
+        assert!(ks.master().is_err());
+
+        ks.handshake(None)?;
+        assert!(ks.binder_key(false).is_err());
+        assert!(ks.handshake(None).is_err());
+
+        ks.master()?;
+        assert_eq!(ks.stage(), Tls13Stage::Master);
+        assert!(ks.server_handshake_traffic_secret(&transcript).is_err());
+        assert!(ks.derive_secret(b"c ap traffic", &[0u8; 16]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,18 @@
+//! Zeroing of secret material using volatile writes, so the compiler can't remove the stores
+//! even if the memory is never read again.
+
+use std::sync::atomic::{compiler_fence, Ordering};
+
+pub(crate) fn wipe_value<T: Copy>(dst: &mut T, zero: T) {
+    // SAFETY: `dst` is a valid, aligned and exclusive reference.
+    unsafe { std::ptr::write_volatile(dst, zero) };
+    compiler_fence(Ordering::SeqCst);
+}
+
+pub(crate) fn wipe_slice<T: Copy>(dst: &mut [T], zero: T) {
+    for elem in dst.iter_mut() {
+        // SAFETY: `elem` is a valid, aligned and exclusive reference.
+        unsafe { std::ptr::write_volatile(elem, zero) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
@@ -0,0 +1,1042 @@
+use crate::context::Sha2Context;
+use crate::error::{Error, Result, Sha2Corrupted};
+use crate::params::Sha256Params;
+use crate::wipe::wipe_slice;
+
+/// Length of hashes, seeds and tree nodes in the SHA-256 parameter sets.
+pub const XMSS_N: usize = 32;
+
+/// Length of the seed for key generation: `SK_SEED || SK_PRF || PUB_SEED`.
+pub const XMSS_SEED_LEN: usize = 3 * XMSS_N;
+
+const N: usize = XMSS_N;
+const WOTS_W: u8 = 16;
+const WOTS_LEN1: usize = 64;
+const WOTS_LEN: usize = 67;
+
+// the first argument of the padded hash constructions of RFC 8391, section 5.1, and of
+// PRF_keygen from SP 800-208, section 5
+const PAD_F: u8 = 0;
+const PAD_H: u8 = 1;
+const PAD_H_MSG: u8 = 2;
+const PAD_PRF: u8 = 3;
+const PAD_PRF_KEYGEN: u8 = 4;
+
+const ADRS_TYPE_OTS: u32 = 0;
+const ADRS_TYPE_LTREE: u32 = 1;
+const ADRS_TYPE_HASH_TREE: u32 = 2;
+
+/// The XMSS parameter sets of RFC 8391 with SHA-256 and `n = 32`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XmssType {
+    Sha2_10_256,
+    Sha2_16_256,
+    Sha2_20_256,
+}
+
+impl XmssType {
+    /// The XMSS registry OID.
+    pub fn code(self) -> u32 {
+        match self {
+            XmssType::Sha2_10_256 => 1,
+            XmssType::Sha2_16_256 => 2,
+            XmssType::Sha2_20_256 => 3,
+        }
+    }
+
+    pub fn from_code(code: u32) -> Result<Self> {
+        match code {
+            1 => Ok(XmssType::Sha2_10_256),
+            2 => Ok(XmssType::Sha2_16_256),
+            3 => Ok(XmssType::Sha2_20_256),
+            _ => Err(Error(Sha2Corrupted::BadParam)),
+        }
+    }
+
+    /// Height of the tree; a key signs `2^h` messages.
+    pub fn h(self) -> usize {
+        match self {
+            XmssType::Sha2_10_256 => 10,
+            XmssType::Sha2_16_256 => 16,
+            XmssType::Sha2_20_256 => 20,
+        }
+    }
+
+    pub fn signature_len(self) -> usize {
+        self.params().signature_len()
+    }
+
+    fn params(self) -> Params {
+        Params {
+            oid: self.code(),
+            h: self.h(),
+            d: 1,
+            idx_len: 4,
+        }
+    }
+}
+
+/// The XMSS^MT parameter sets of RFC 8391 with SHA-256 and `n = 32`, named by total height and
+/// number of layers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XmssMtType {
+    Sha2_20_2_256,
+    Sha2_20_4_256,
+    Sha2_40_2_256,
+    Sha2_40_4_256,
+    Sha2_40_8_256,
+    Sha2_60_3_256,
+    Sha2_60_6_256,
+    Sha2_60_12_256,
+}
+
+impl XmssMtType {
+    /// The XMSS^MT registry OID.
+    pub fn code(self) -> u32 {
+        match self {
+            XmssMtType::Sha2_20_2_256 => 1,
+            XmssMtType::Sha2_20_4_256 => 2,
+            XmssMtType::Sha2_40_2_256 => 3,
+            XmssMtType::Sha2_40_4_256 => 4,
+            XmssMtType::Sha2_40_8_256 => 5,
+            XmssMtType::Sha2_60_3_256 => 6,
+            XmssMtType::Sha2_60_6_256 => 7,
+            XmssMtType::Sha2_60_12_256 => 8,
+        }
+    }
+
+    pub fn from_code(code: u32) -> Result<Self> {
+        match code {
+            1 => Ok(XmssMtType::Sha2_20_2_256),
+            2 => Ok(XmssMtType::Sha2_20_4_256),
+            3 => Ok(XmssMtType::Sha2_40_2_256),
+            4 => Ok(XmssMtType::Sha2_40_4_256),
+            5 => Ok(XmssMtType::Sha2_40_8_256),
+            6 => Ok(XmssMtType::Sha2_60_3_256),
+            7 => Ok(XmssMtType::Sha2_60_6_256),
+            8 => Ok(XmssMtType::Sha2_60_12_256),
+            _ => Err(Error(Sha2Corrupted::BadParam)),
+        }
+    }
+
+    /// Total height of the hypertree; a key signs `2^h` messages.
+    pub fn h(self) -> usize {
+        match self {
+            XmssMtType::Sha2_20_2_256 | XmssMtType::Sha2_20_4_256 => 20,
+            XmssMtType::Sha2_40_2_256 | XmssMtType::Sha2_40_4_256 | XmssMtType::Sha2_40_8_256 => 40,
+            _ => 60,
+        }
+    }
+
+    /// Number of layers of XMSS trees.
+    pub fn d(self) -> usize {
+        match self {
+            XmssMtType::Sha2_20_2_256 | XmssMtType::Sha2_40_2_256 => 2,
+            XmssMtType::Sha2_60_3_256 => 3,
+            XmssMtType::Sha2_20_4_256 | XmssMtType::Sha2_40_4_256 => 4,
+            XmssMtType::Sha2_60_6_256 => 6,
+            XmssMtType::Sha2_40_8_256 => 8,
+            XmssMtType::Sha2_60_12_256 => 12,
+        }
+    }
+
+    pub fn signature_len(self) -> usize {
+        self.params().signature_len()
+    }
+
+    fn params(self) -> Params {
+        Params {
+            oid: self.code(),
+            h: self.h(),
+            d: self.d(),
+            idx_len: self.h().div_ceil(8),
+        }
+    }
+}
+
+/// An XMSS private key (RFC 8391, section 4.1).
+///
+/// Keys are generated from [`XMSS_SEED_LEN`] bytes split into `SK_SEED`, `SK_PRF` and
+/// `PUB_SEED`, with the WOTS+ private keys derived by `PRF_keygen` as in SP 800-208. The tree is
+/// kept in memory once computed, so heights of 16 and 20 take a long time and a lot of memory.
+///
+/// Reusing a leaf breaks the scheme. [`XmssPrivateKey::sign`] advances the index, and the new
+/// state must be stored (see [`XmssPrivateKey::to_bytes`]) before the signature is released.
+pub struct XmssPrivateKey {
+    xmss_type: XmssType,
+    core: PrivateCore,
+}
+
+/// An XMSS public key: the OID, the root and `PUB_SEED`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmssPublicKey {
+    xmss_type: XmssType,
+    core: PublicCore,
+}
+
+/// An XMSS^MT private key (RFC 8391, section 4.2), generated like [`XmssPrivateKey`].
+///
+/// Only the tree in use on each layer is kept in memory, and trees are computed as signing
+/// reaches them.
+pub struct XmssMtPrivateKey {
+    xmss_mt_type: XmssMtType,
+    core: PrivateCore,
+}
+
+/// An XMSS^MT public key: the OID, the root of the top tree and `PUB_SEED`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmssMtPublicKey {
+    xmss_mt_type: XmssMtType,
+    core: PublicCore,
+}
+
+impl XmssPrivateKey {
+    pub fn new(xmss_type: XmssType, seed: &[u8]) -> Result<Self> {
+        Ok(XmssPrivateKey {
+            xmss_type,
+            core: PrivateCore::new(xmss_type.params(), seed)?,
+        })
+    }
+
+    pub fn xmss_type(&self) -> XmssType {
+        self.xmss_type
+    }
+
+    /// Index of the next leaf to sign with.
+    pub fn index(&self) -> u64 {
+        self.core.idx
+    }
+
+    /// Number of signatures the key can still make.
+    pub fn remaining(&self) -> u64 {
+        self.core.remaining()
+    }
+
+    pub fn public_key(&self) -> XmssPublicKey {
+        XmssPublicKey {
+            xmss_type: self.xmss_type,
+            core: self.core.public(),
+        }
+    }
+
+    /// Signs `msg` with the next leaf. Fails with a `StateError` once all leaves are used.
+    pub fn sign(&mut self, msg: &[u8]) -> Result<Vec<u8>> {
+        self.core.sign(msg)
+    }
+
+    /// Serializes the key as the OID followed by the reference implementation's format:
+    /// `idx || SK_SEED || SK_PRF || root || PUB_SEED`, with a 4-byte index.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.core.to_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let xmss_type = XmssType::from_code(read_oid(bytes)?)?;
+
+        Ok(XmssPrivateKey {
+            xmss_type,
+            core: PrivateCore::from_bytes(xmss_type.params(), bytes)?,
+        })
+    }
+}
+
+impl XmssPublicKey {
+    pub fn xmss_type(&self) -> XmssType {
+        self.xmss_type
+    }
+
+    /// Verifies an XMSS signature (RFC 8391, section 4.1.10). Malformed signatures are reported
+    /// as `Ok(false)`.
+    pub fn verify(&self, msg: &[u8], signature: &[u8]) -> Result<bool> {
+        self.core.verify(msg, signature)
+    }
+
+    /// The RFC 8391 encoding: `OID || root || PUB_SEED`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.core.to_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let xmss_type = XmssType::from_code(read_oid(bytes)?)?;
+
+        Ok(XmssPublicKey {
+            xmss_type,
+            core: PublicCore::from_bytes(xmss_type.params(), bytes)?,
+        })
+    }
+}
+
+impl XmssMtPrivateKey {
+    pub fn new(xmss_mt_type: XmssMtType, seed: &[u8]) -> Result<Self> {
+        Ok(XmssMtPrivateKey {
+            xmss_mt_type,
+            core: PrivateCore::new(xmss_mt_type.params(), seed)?,
+        })
+    }
+
+    pub fn xmss_mt_type(&self) -> XmssMtType {
+        self.xmss_mt_type
+    }
+
+    /// Index of the next leaf to sign with.
+    pub fn index(&self) -> u64 {
+        self.core.idx
+    }
+
+    /// Number of signatures the key can still make.
+    pub fn remaining(&self) -> u64 {
+        self.core.remaining()
+    }
+
+    pub fn public_key(&self) -> XmssMtPublicKey {
+        XmssMtPublicKey {
+            xmss_mt_type: self.xmss_mt_type,
+            core: self.core.public(),
+        }
+    }
+
+    /// Signs `msg` with the next leaf. Fails with a `StateError` once all leaves are used.
+    pub fn sign(&mut self, msg: &[u8]) -> Result<Vec<u8>> {
+        self.core.sign(msg)
+    }
+
+    /// Serializes the key as the OID followed by the reference implementation's format:
+    /// `idx || SK_SEED || SK_PRF || root || PUB_SEED`, with a `ceil(h / 8)`-byte index.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.core.to_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let xmss_mt_type = XmssMtType::from_code(read_oid(bytes)?)?;
+
+        Ok(XmssMtPrivateKey {
+            xmss_mt_type,
+            core: PrivateCore::from_bytes(xmss_mt_type.params(), bytes)?,
+        })
+    }
+}
+
+impl XmssMtPublicKey {
+    pub fn xmss_mt_type(&self) -> XmssMtType {
+        self.xmss_mt_type
+    }
+
+    /// Verifies an XMSS^MT signature (RFC 8391, section 4.2.5). Malformed signatures are
+    /// reported as `Ok(false)`.
+    pub fn verify(&self, msg: &[u8], signature: &[u8]) -> Result<bool> {
+        self.core.verify(msg, signature)
+    }
+
+    /// The RFC 8391 encoding: `OID || root || PUB_SEED`.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.core.to_bytes()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let xmss_mt_type = XmssMtType::from_code(read_oid(bytes)?)?;
+
+        Ok(XmssMtPublicKey {
+            xmss_mt_type,
+            core: PublicCore::from_bytes(xmss_mt_type.params(), bytes)?,
+        })
+    }
+}
+
+/// XMSS is XMSS^MT with a single layer; this carries what the algorithms need of either.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Params {
+    oid: u32,
+    h: usize,
+    d: usize,
+    idx_len: usize,
+}
+
+impl Params {
+    /// Height of each tree.
+    fn tree_height(self) -> usize {
+        self.h / self.d
+    }
+
+    fn signature_len(self) -> usize {
+        self.idx_len + N + self.d * (WOTS_LEN + self.tree_height()) * N
+    }
+
+    /// The tree on `layer` and the leaf within it that lead to signature index `idx`.
+    fn position(self, idx: u64, layer: usize) -> (u64, u32) {
+        let hp = self.tree_height();
+        let tree = idx.checked_shr((hp * (layer + 1)) as u32).unwrap_or(0);
+        let leaf = (idx >> (hp * layer)) & ((1 << hp) - 1);
+        (tree, leaf as u32)
+    }
+}
+
+struct PrivateCore {
+    params: Params,
+    idx: u64,
+    sk_seed: [u8; N],
+    sk_prf: [u8; N],
+    pub_seed: [u8; N],
+    root: [u8; N],
+    // for each layer, the tree in use and its nodes, node r at r as in LMS
+    trees: Vec<Option<(u64, Vec<[u8; N]>)>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct PublicCore {
+    params: Params,
+    root: [u8; N],
+    pub_seed: [u8; N],
+}
+
+impl PrivateCore {
+    fn new(params: Params, seed: &[u8]) -> Result<Self> {
+        if seed.len() != XMSS_SEED_LEN {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        let mut core = PrivateCore {
+            params,
+            idx: 0,
+            sk_seed: seed[..N].try_into().unwrap(),
+            sk_prf: seed[N..2 * N].try_into().unwrap(),
+            pub_seed: seed[2 * N..].try_into().unwrap(),
+            root: [0; N],
+            trees: vec![None; params.d],
+        };
+
+        core.root = core.tree(params.d - 1, 0)?[1];
+        Ok(core)
+    }
+
+    fn remaining(&self) -> u64 {
+        (1u64 << self.params.h) - self.idx
+    }
+
+    fn public(&self) -> PublicCore {
+        PublicCore {
+            params: self.params,
+            root: self.root,
+            pub_seed: self.pub_seed,
+        }
+    }
+
+    /// Algorithm 12 of RFC 8391, and algorithm 16 for more than one layer.
+    fn sign(&mut self, msg: &[u8]) -> Result<Vec<u8>> {
+        if self.remaining() == 0 {
+            return Err(Error(Sha2Corrupted::StateError));
+        }
+
+        let idx = self.idx;
+        let hp = self.params.tree_height();
+
+        let mut signature = Vec::with_capacity(self.params.signature_len());
+        signature.extend_from_slice(&idx.to_be_bytes()[8 - self.params.idx_len..]);
+
+        let r = padded_hash(PAD_PRF, &[&self.sk_prf, &to_byte_32(idx)])?;
+        signature.extend_from_slice(&r);
+
+        let mut node = h_msg(&r, &self.root, idx, msg)?;
+        for layer in 0..self.params.d {
+            let (tree, leaf) = self.params.position(idx, layer);
+            let adrs = Adrs::new(layer as u32, tree);
+
+            wots_sign(
+                &node,
+                &self.sk_seed,
+                &self.pub_seed,
+                adrs.ots(leaf),
+                &mut signature,
+            )?;
+
+            let nodes = self.tree(layer, tree)?;
+            let mut r = (1 << hp) + leaf as usize;
+            for _ in 0..hp {
+                signature.extend_from_slice(&nodes[r ^ 1]);
+                r >>= 1;
+            }
+            node = nodes[1];
+        }
+
+        self.idx += 1;
+        Ok(signature)
+    }
+
+    /// The nodes of tree `tree` on `layer`, computing them unless they are the ones kept.
+    fn tree(&mut self, layer: usize, tree: u64) -> Result<&[[u8; N]]> {
+        let cached = &mut self.trees[layer];
+
+        if cached.as_ref().is_none_or(|(index, _)| *index != tree) {
+            let nodes = build_tree(
+                &self.sk_seed,
+                &self.pub_seed,
+                Adrs::new(layer as u32, tree),
+                self.params.tree_height(),
+            )?;
+            *cached = Some((tree, nodes));
+        }
+
+        Ok(&cached.as_ref().expect("tree just computed").1)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(4 + self.params.idx_len + 4 * N);
+        out.extend_from_slice(&self.params.oid.to_be_bytes());
+        out.extend_from_slice(&self.idx.to_be_bytes()[8 - self.params.idx_len..]);
+        out.extend_from_slice(&self.sk_seed);
+        out.extend_from_slice(&self.sk_prf);
+        out.extend_from_slice(&self.root);
+        out.extend_from_slice(&self.pub_seed);
+        out
+    }
+
+    fn from_bytes(params: Params, bytes: &[u8]) -> Result<Self> {
+        let idx_len = params.idx_len;
+
+        if bytes.len() != 4 + idx_len + 4 * N {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        let idx = read_index(&bytes[4..4 + idx_len]);
+        if idx > 1u64 << params.h {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        let field = |i: usize| -> [u8; N] {
+            let start = 4 + idx_len + i * N;
+            bytes[start..start + N].try_into().unwrap()
+        };
+
+        Ok(PrivateCore {
+            params,
+            idx,
+            sk_seed: field(0),
+            sk_prf: field(1),
+            root: field(2),
+            pub_seed: field(3),
+            trees: vec![None; params.d],
+        })
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for PrivateCore {
+    fn drop(&mut self) {
+        wipe_slice(&mut self.sk_seed, 0);
+        wipe_slice(&mut self.sk_prf, 0);
+    }
+}
+
+impl PublicCore {
+    /// Algorithm 14 of RFC 8391, and algorithm 17 for more than one layer.
+    fn verify(&self, msg: &[u8], signature: &[u8]) -> Result<bool> {
+        let params = self.params;
+        let hp = params.tree_height();
+
+        if signature.len() != params.signature_len() {
+            return Ok(false);
+        }
+
+        let (idx, rest) = signature.split_at(params.idx_len);
+        let (r, mut rest) = rest.split_at(N);
+        let idx = read_index(idx);
+
+        if idx >= 1u64 << params.h {
+            return Ok(false);
+        }
+
+        let mut node = h_msg(r, &self.root, idx, msg)?;
+        for layer in 0..params.d {
+            let (tree, leaf) = params.position(idx, layer);
+            let (wots_signature, tail) = rest.split_at(WOTS_LEN * N);
+            let (auth, tail) = tail.split_at(hp * N);
+            rest = tail;
+
+            node = root_from_signature(
+                leaf,
+                wots_signature,
+                auth,
+                &node,
+                &self.pub_seed,
+                Adrs::new(layer as u32, tree),
+            )?;
+        }
+
+        Ok(node == self.root)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(4 + 2 * N);
+        out.extend_from_slice(&self.params.oid.to_be_bytes());
+        out.extend_from_slice(&self.root);
+        out.extend_from_slice(&self.pub_seed);
+        out
+    }
+
+    fn from_bytes(params: Params, bytes: &[u8]) -> Result<Self> {
+        if bytes.len() != 4 + 2 * N {
+            return Err(Error(Sha2Corrupted::BadParam));
+        }
+
+        Ok(PublicCore {
+            params,
+            root: bytes[4..4 + N].try_into().unwrap(),
+            pub_seed: bytes[4 + N..].try_into().unwrap(),
+        })
+    }
+}
+
+/// The hash address of RFC 8391, section 2.5, as its eight 32-bit words.
+#[derive(Clone, Copy)]
+struct Adrs([u32; 8]);
+
+impl Adrs {
+    fn new(layer: u32, tree: u64) -> Self {
+        Adrs([layer, (tree >> 32) as u32, tree as u32, 0, 0, 0, 0, 0])
+    }
+
+    fn with_type(self, adrs_type: u32, word4: u32) -> Self {
+        let [layer, tree_high, tree_low, ..] = self.0;
+        Adrs([layer, tree_high, tree_low, adrs_type, word4, 0, 0, 0])
+    }
+
+    fn ots(self, ots_address: u32) -> Self {
+        self.with_type(ADRS_TYPE_OTS, ots_address)
+    }
+
+    fn ltree(self, ltree_address: u32) -> Self {
+        self.with_type(ADRS_TYPE_LTREE, ltree_address)
+    }
+
+    fn hash_tree(self) -> Self {
+        self.with_type(ADRS_TYPE_HASH_TREE, 0)
+    }
+
+    /// Sets the chain address of an OTS address or the tree height of the others.
+    fn with_word5(mut self, value: u32) -> Self {
+        self.0[5] = value;
+        self
+    }
+
+    /// Sets the hash address of an OTS address or the tree index of the others.
+    fn with_word6(mut self, value: u32) -> Self {
+        self.0[6] = value;
+        self
+    }
+
+    fn with_key_and_mask(mut self, key_and_mask: u32) -> Self {
+        self.0[7] = key_and_mask;
+        self
+    }
+
+    fn to_bytes(self) -> [u8; 32] {
+        let mut out = [0u8; 32];
+        for (chunk, word) in out.chunks_mut(4).zip(self.0) {
+            chunk.copy_from_slice(&word.to_be_bytes());
+        }
+        out
+    }
+}
+
+/// SHA-256 of `toByte(pad, 32)` followed by `parts`.
+fn padded_hash(pad: u8, parts: &[&[u8]]) -> Result<[u8; N]> {
+    let mut ctx: Sha2Context<Sha256Params> = Sha2Context::new();
+    ctx.input(&to_byte_32(pad as u64))?;
+    for part in parts {
+        ctx.input(part)?;
+    }
+
+    let mut digest = [0u8; N];
+    ctx.result(&mut digest)?;
+    Ok(digest)
+}
+
+fn prf(pub_seed: &[u8; N], adrs: Adrs) -> Result<[u8; N]> {
+    padded_hash(PAD_PRF, &[pub_seed, &adrs.to_bytes()])
+}
+
+/// `H_msg(r || root || toByte(idx, 32), msg)`.
+fn h_msg(r: &[u8], root: &[u8; N], idx: u64, msg: &[u8]) -> Result<[u8; N]> {
+    padded_hash(PAD_H_MSG, &[r, root, &to_byte_32(idx), msg])
+}
+
+/// `RAND_HASH` (RFC 8391, algorithm 7) with `adrs` already holding height and index.
+fn rand_hash(left: &[u8; N], right: &[u8; N], pub_seed: &[u8; N], adrs: Adrs) -> Result<[u8; N]> {
+    let key = prf(pub_seed, adrs.with_key_and_mask(0))?;
+    let mut left_masked = prf(pub_seed, adrs.with_key_and_mask(1))?;
+    let mut right_masked = prf(pub_seed, adrs.with_key_and_mask(2))?;
+    xor_into(&mut left_masked, left);
+    xor_into(&mut right_masked, right);
+
+    padded_hash(PAD_H, &[&key, &left_masked, &right_masked])
+}
+
+/// The WOTS+ chaining function (RFC 8391, algorithm 2), in place. `adrs` is an OTS address
+/// with its chain address set.
+fn chain(x: &mut [u8; N], start: u8, steps: u8, pub_seed: &[u8; N], adrs: Adrs) -> Result<()> {
+    for j in start..start + steps {
+        let adrs = adrs.with_word6(j as u32);
+        let key = prf(pub_seed, adrs.with_key_and_mask(0))?;
+        let mask = prf(pub_seed, adrs.with_key_and_mask(1))?;
+
+        xor_into(x, &mask);
+        *x = padded_hash(PAD_F, &[&key, x])?;
+    }
+
+    Ok(())
+}
+
+/// WOTS+ private key element `i`: `PRF_keygen(SK_SEED, PUB_SEED || ADRS)`.
+fn wots_secret(sk_seed: &[u8; N], pub_seed: &[u8; N], adrs: Adrs, i: usize) -> Result<[u8; N]> {
+    let adrs = adrs.with_word5(i as u32);
+    padded_hash(PAD_PRF_KEYGEN, &[sk_seed, pub_seed, &adrs.to_bytes()])
+}
+
+/// The base-16 digits of `msg` followed by those of its checksum (RFC 8391, algorithm 5).
+fn wots_digits(msg: &[u8; N]) -> [u8; WOTS_LEN] {
+    let mut digits = [0u8; WOTS_LEN];
+
+    for (pair, byte) in digits[..WOTS_LEN1].chunks_mut(2).zip(msg) {
+        pair[0] = byte >> 4;
+        pair[1] = byte & 0x0f;
+    }
+
+    let checksum: u16 = digits[..WOTS_LEN1]
+        .iter()
+        .map(|&d| (WOTS_W - 1 - d) as u16)
+        .sum();
+    // len_2 * lg(w) = 12 bits, shifted to fill two bytes
+    let [high, low] = (checksum << 4).to_be_bytes();
+    digits[WOTS_LEN1..].copy_from_slice(&[high >> 4, high & 0x0f, low >> 4]);
+
+    digits
+}
+
+fn wots_public_key(sk_seed: &[u8; N], pub_seed: &[u8; N], adrs: Adrs) -> Result<Vec<[u8; N]>> {
+    (0..WOTS_LEN)
+        .map(|i| {
+            let mut x = wots_secret(sk_seed, pub_seed, adrs, i)?;
+            chain(&mut x, 0, WOTS_W - 1, pub_seed, adrs.with_word5(i as u32))?;
+            Ok(x)
+        })
+        .collect()
+}
+
+fn wots_sign(
+    msg: &[u8; N],
+    sk_seed: &[u8; N],
+    pub_seed: &[u8; N],
+    adrs: Adrs,
+    signature: &mut Vec<u8>,
+) -> Result<()> {
+    for (i, &digit) in wots_digits(msg).iter().enumerate() {
+        let mut x = wots_secret(sk_seed, pub_seed, adrs, i)?;
+        chain(&mut x, 0, digit, pub_seed, adrs.with_word5(i as u32))?;
+        signature.extend_from_slice(&x);
+        wipe_slice(&mut x, 0);
+    }
+
+    Ok(())
+}
+
+/// Compresses a WOTS+ public key to one node (RFC 8391, algorithm 8). `adrs` is an L-tree
+/// address.
+fn ltree(mut pk: Vec<[u8; N]>, pub_seed: &[u8; N], adrs: Adrs) -> Result<[u8; N]> {
+    let mut len = pk.len();
+    let mut height = 0;
+
+    while len > 1 {
+        for i in 0..len / 2 {
+            let adrs = adrs.with_word5(height).with_word6(i as u32);
+            pk[i] = rand_hash(&pk[2 * i], &pk[2 * i + 1], pub_seed, adrs)?;
+        }
+        if len % 2 == 1 {
+            pk[len / 2] = pk[len - 1];
+        }
+
+        len = len.div_ceil(2);
+        height += 1;
+    }
+
+    Ok(pk[0])
+}
+
+/// All nodes of the tree at `adrs` (layer and tree set), node `r` at index `r`: the root at 1,
+/// leaf `i` at `2^height + i`.
+fn build_tree(
+    sk_seed: &[u8; N],
+    pub_seed: &[u8; N],
+    adrs: Adrs,
+    height: usize,
+) -> Result<Vec<[u8; N]>> {
+    let leaves = 1usize << height;
+    let mut nodes = vec![[0u8; N]; 2 * leaves];
+
+    for i in 0..leaves {
+        let pk = wots_public_key(sk_seed, pub_seed, adrs.ots(i as u32))?;
+        nodes[leaves + i] = ltree(pk, pub_seed, adrs.ltree(i as u32))?;
+    }
+
+    for r in (1..leaves).rev() {
+        // the children of r sit one level below it, whose height is that of the children
+        let level = r.ilog2() as usize;
+        let node_adrs = adrs
+            .hash_tree()
+            .with_word5((height - level - 1) as u32)
+            .with_word6((r - (1 << level)) as u32);
+        nodes[r] = rand_hash(&nodes[2 * r], &nodes[2 * r + 1], pub_seed, node_adrs)?;
+    }
+
+    Ok(nodes)
+}
+
+/// The root implied by a WOTS+ signature and authentication path (RFC 8391, algorithm 13).
+fn root_from_signature(
+    leaf: u32,
+    wots_signature: &[u8],
+    auth: &[u8],
+    msg: &[u8; N],
+    pub_seed: &[u8; N],
+    adrs: Adrs,
+) -> Result<[u8; N]> {
+    let ots_adrs = adrs.ots(leaf);
+    let pk = wots_digits(msg)
+        .iter()
+        .zip(wots_signature.chunks(N))
+        .enumerate()
+        .map(|(i, (&digit, sig))| {
+            let mut x: [u8; N] = sig.try_into().unwrap();
+            chain(
+                &mut x,
+                digit,
+                WOTS_W - 1 - digit,
+                pub_seed,
+                ots_adrs.with_word5(i as u32),
+            )?;
+            Ok(x)
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut node = ltree(pk, pub_seed, adrs.ltree(leaf))?;
+    let mut index = leaf;
+
+    for (k, sibling) in auth.chunks(N).enumerate() {
+        let sibling: &[u8; N] = sibling.try_into().unwrap();
+        let node_adrs = adrs.hash_tree().with_word5(k as u32).with_word6(index >> 1);
+
+        node = if index.is_multiple_of(2) {
+            rand_hash(&node, sibling, pub_seed, node_adrs)?
+        } else {
+            rand_hash(sibling, &node, pub_seed, node_adrs)?
+        };
+        index >>= 1;
+    }
+
+    Ok(node)
+}
+
+fn xor_into(x: &mut [u8; N], y: &[u8; N]) {
+    for (a, b) in x.iter_mut().zip(y) {
+        *a ^= b;
+    }
+}
+
+fn to_byte_32(x: u64) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out[24..].copy_from_slice(&x.to_be_bytes());
+    out
+}
+
+fn read_index(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |acc, &b| (acc << 8) | b as u64)
+}
+
+fn read_oid(bytes: &[u8]) -> Result<u32> {
+    match bytes.get(..4) {
+        Some(oid) => Ok(u32::from_be_bytes(oid.try_into().unwrap())),
+        None => Err(Error(Sha2Corrupted::BadParam)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        XmssMtPrivateKey, XmssMtPublicKey, XmssMtType, XmssPrivateKey, XmssPublicKey, XmssType,
+    };
+    use crate::error::Result;
+    use crate::test_util::sha256;
+
+    use hex_literal::hex;
+
+    fn seed() -> Vec<u8> {
+        (0..96).collect()
+    }
+
+    /// The serialized key with its index replaced by `idx`, to sign at the end of the tree.
+    fn at_index(bytes: &[u8], idx_len: usize, idx: u64) -> Vec<u8> {
+        let mut bytes = bytes.to_vec();
+        bytes[4..4 + idx_len].copy_from_slice(&idx.to_be_bytes()[8 - idx_len..]);
+        bytes
+    }
+
+    // regression values for RFC 8391 with the SP 800-208 key generation, not checked against the
+    // reference implementation's KATs; signatures given as their SHA-256
+    #[test]
+    fn test_xmss_sha2_10_256() -> Result<()> {
+        let mut sk = XmssPrivateKey::new(XmssType::Sha2_10_256, &seed())?;
+        let pk = sk.public_key();
+        assert_eq!(
+            pk.to_bytes(),
+            hex!(
+                "00000001"
+                "9d898033e37af48e6a116f8b15651cc26773467007ad19375d38c23c690c3483"
+                "404142434445464748494a4b4c4d4e4f505152535455565758595a5b5c5d5e5f"
+            )
+        );
+
+        let signature = sk.sign(b"abc")?;
+        assert_eq!(signature.len(), XmssType::Sha2_10_256.signature_len());
+        assert_eq!(signature.len(), 2500);
+        assert_eq!(
+            sha256(&signature)?,
+            hex!("16587b3b539ecfc2312a93119deaba8e98a56abae091d38d0589812cae1202b4")
+        );
+        assert!(pk.verify(b"abc", &signature)?);
+        assert!(!pk.verify(b"abd", &signature)?);
+
+        // a restored key that has used every leaf refuses to sign without building the tree
+        let mut used_up = XmssPrivateKey::from_bytes(&at_index(&sk.to_bytes(), 4, 1024))?;
+        assert_eq!(used_up.remaining(), 0);
+        assert!(used_up.sign(b"abc").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_xmss_mt_sha2_20_4_256() -> Result<()> {
+        let mut sk = XmssMtPrivateKey::new(XmssMtType::Sha2_20_4_256, &seed())?;
+        let pk = sk.public_key();
+        assert_eq!(
+            pk.to_bytes(),
+            hex!(
+                "00000002"
+                "2063c0b3ddf86940b17f60d5f607b1af8a2a8be6281ce5121012291e66a1f83a"
+                "404142434445464748494a4b4c4d4e4f505152535455565758595a5b5c5d5e5f"
+            )
+        );
+
+        let signature = sk.sign(b"abc")?;
+        assert_eq!(signature.len(), 9251);
+        assert_eq!(
+            sha256(&signature)?,
+            hex!("c0403cc421a772e7c702dbafec4f1924ee573006f7606c29eeeba1ad571a60e2")
+        );
+        assert!(pk.verify(b"abc", &signature)?);
+
+        let mut sk = XmssMtPrivateKey::from_bytes(&at_index(&sk.to_bytes(), 3, 12345))?;
+        let signature = sk.sign(b"abc")?;
+        assert_eq!(signature[..3], hex!("003039"));
+        assert_eq!(
+            sha256(&signature)?,
+            hex!("f579d6a14b20043cdb02e352174d6cfcefcf59556da02db4095e5245477728b4")
+        );
+        assert!(pk.verify(b"abc", &signature)?);
+        assert_eq!(sk.index(), 12346);
+        Ok(())
+    }
+
+    #[test]
+    fn test_xmss_mt_sha2_60_12_256() -> Result<()> {
+        let mut sk = XmssMtPrivateKey::new(XmssMtType::Sha2_60_12_256, &seed())?;
+        let pk = sk.public_key();
+        assert_eq!(
+            pk.to_bytes(),
+            hex!(
+                "00000008"
+                "b8d0fb89fbba1e69901da91d476f985c65fac50020755d8725ca54a192816f92"
+                "404142434445464748494a4b4c4d4e4f505152535455565758595a5b5c5d5e5f"
+            )
+        );
+
+        let signature = sk.sign(b"abc")?;
+        assert_eq!(signature.len(), 27688);
+        assert_eq!(
+            sha256(&signature)?,
+            hex!("e0768cfc6966c825080a99e4c7b031bd1bbf4451cb5c69498e614703a1ecf8d7")
+        );
+        assert!(pk.verify(b"abc", &signature)?);
+
+        let mut last = XmssMtPrivateKey::from_bytes(&at_index(&sk.to_bytes(), 8, (1 << 60) - 1))?;
+        let signature = last.sign(b"abc")?;
+        assert_eq!(
+            sha256(&signature)?,
+            hex!("010fb7a53feaffe3b6d6c922703f6e4305dbaaebc6c365b5c19c42826afcfd5e")
+        );
+        assert!(pk.verify(b"abc", &signature)?);
+        assert!(last.sign(b"abc").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_tampering() -> Result<()> {
+        let mut sk = XmssMtPrivateKey::new(XmssMtType::Sha2_20_4_256, &seed())?;
+        let pk = sk.public_key();
+        let signature = sk.sign(b"msg")?;
+
+        for i in [0, 3, 40, 2000, 9250] {
+            let mut bad = signature.clone();
+            bad[i] ^= 1;
+            assert!(!pk.verify(b"msg", &bad)?);
+        }
+        assert!(!pk.verify(b"msg", &signature[1..])?);
+        assert!(!pk.verify(b"msg", &[])?);
+
+        // an index past the end of the hypertree
+        let mut bad = signature.clone();
+        bad[0] = 0x10;
+        assert!(!pk.verify(b"msg", &bad)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialization() -> Result<()> {
+        let mut sk = XmssMtPrivateKey::new(XmssMtType::Sha2_20_4_256, &seed())?;
+        sk.sign(b"first")?;
+
+        let bytes = sk.to_bytes();
+        assert_eq!(bytes.len(), 4 + 3 + 4 * 32);
+        assert_eq!(bytes[..7], hex!("00000002000001"));
+
+        let mut restored = XmssMtPrivateKey::from_bytes(&bytes)?;
+        assert_eq!(restored.xmss_mt_type(), XmssMtType::Sha2_20_4_256);
+        assert_eq!(restored.index(), 1);
+        assert_eq!(restored.remaining(), (1 << 20) - 1);
+
+        let pk = XmssMtPublicKey::from_bytes(&sk.public_key().to_bytes())?;
+        assert_eq!(pk, restored.public_key());
+
+        // the restored key continues where the original stopped
+        assert_eq!(restored.sign(b"second")?, sk.sign(b"second")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_bad_params() -> Result<()> {
+        assert!(XmssPrivateKey::new(XmssType::Sha2_10_256, &[0; 95]).is_err());
+        assert!(XmssMtPrivateKey::new(XmssMtType::Sha2_20_2_256, &[0; 97]).is_err());
+        assert!(XmssType::from_code(0).is_err());
+        assert!(XmssType::from_code(4).is_err());
+        assert!(XmssMtType::from_code(9).is_err());
+        assert!(XmssPrivateKey::from_bytes(&[]).is_err());
+        assert!(XmssPublicKey::from_bytes(&[0, 0, 0, 1]).is_err());
+        assert!(XmssMtPublicKey::from_bytes(&[0; 68]).is_err());
+
+        // an XMSS key is not an XMSS^MT key of the same length
+        let sk = XmssMtPrivateKey::new(XmssMtType::Sha2_20_4_256, &seed())?;
+        let mut bytes = sk.to_bytes();
+        assert!(XmssPrivateKey::from_bytes(&bytes).is_err());
+
+        // an index past the number of leaves
+        bytes[4] = 0x20;
+        assert!(XmssMtPrivateKey::from_bytes(&bytes).is_err());
+        Ok(())
+    }
+}